@@ -0,0 +1,637 @@
+// Antigravity 账户摘要索引模块
+// 将所有备份的摘要信息（邮箱、名称、套餐、备份时间、配额快照）保存在一个加密索引文件中
+// 列表接口只需读取并解密这一个文件，无需逐个解密全部备份
+// 索引同时记录每个备份文件的大小与修改时间，文件被替换或新增时只重新读取变化的文件
+//
+// 备份文件以随机账户 ID 命名（<id>.enc），邮箱到 ID 的映射只存在于加密索引中，
+// 列出目录无法得知有哪些账户。旧版以邮箱命名的文件会在读取索引时自动迁移。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::antigravity::account_keys::{self, KEYS_FILE_NAME};
+use crate::antigravity::backup::read_backup_file;
+use crate::antigravity::{credentials, secret_storage};
+use crate::antigravity::vault::vault;
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{random_bytes, secure_create_dir, secure_delete_file, secure_write_file};
+
+/// 索引文件名（位于备份目录中）
+pub const INDEX_FILE_NAME: &str = "index.enc";
+
+/// 当前索引格式版本
+const INDEX_VERSION: u32 = 5;
+
+/// 账户 ID 长度（16 字节随机数的十六进制表示）
+const ACCOUNT_ID_LEN: usize = 32;
+
+/// 列表中展示的时间格式（与原先基于文件修改时间的格式保持一致）
+const DISPLAY_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 串行化索引的读-改-写，避免并发备份/删除互相覆盖
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// 单个账户的摘要信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSummary {
+    /// 随机账户 ID（同时也是备份文件名）
+    pub id: String,
+    /// 规范化后的邮箱（小写）
+    pub email: String,
+    pub name: String,
+    /// 套餐名称（认证信息中存在时记录）
+    #[serde(default)]
+    pub plan: Option<String>,
+    /// 最近一次备份时间（RFC3339）
+    pub backup_time: String,
+    /// 最近一次切换/恢复到该账户的时间
+    #[serde(default)]
+    pub last_switched: String,
+    /// Base64 编码的头像
+    #[serde(default)]
+    pub profile_url: String,
+    /// 配额快照（userStatusProtoBinaryBase64）
+    #[serde(default)]
+    pub user_status_proto: Option<String>,
+    /// 备份中访问令牌的过期时间（Unix 秒，无法确定时为空）
+    #[serde(default)]
+    pub token_expires_at: Option<i64>,
+    /// 访问令牌指纹（用于识别当前活动账户，不保存令牌本身）
+    #[serde(default)]
+    pub token_fingerprint: Option<String>,
+    /// 刷新令牌指纹（相同表示同一次登录会话）
+    #[serde(default)]
+    pub refresh_fingerprint: Option<String>,
+    /// 备份中的扩展密钥字段（secret://，依赖系统钥匙串，换机后无法解密）
+    #[serde(default)]
+    pub secret_keys: Vec<String>,
+    /// 备份来源设备的指纹
+    #[serde(default)]
+    pub backup_machine: Option<String>,
+}
+
+/// 备份文件的大小与修改时间（用于判断索引中的摘要是否过期）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    len: u64,
+    modified_ns: u64,
+}
+
+/// 账户摘要索引（按账户 ID 索引）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AccountIndex {
+    version: u32,
+    accounts: BTreeMap<String, AccountSummary>,
+    /// 无法解析的备份文件名（记录下来避免每次列表都触发重建或迁移）
+    #[serde(default)]
+    unreadable: BTreeSet<String>,
+    /// 生成摘要时各 ID 命名备份文件的状态
+    #[serde(default)]
+    files: BTreeMap<String, FileStamp>,
+}
+
+impl AccountIndex {
+    /// 所有账户摘要
+    pub fn accounts(&self) -> impl Iterator<Item = &AccountSummary> {
+        self.accounts.values()
+    }
+
+    /// 按账户 ID 查找账户摘要
+    pub fn get(&self, id: &str) -> Option<&AccountSummary> {
+        self.accounts.get(id)
+    }
+
+    /// 按邮箱查找账户摘要（大小写不敏感）
+    pub fn find_by_email(&self, email: &str) -> Option<&AccountSummary> {
+        let email = normalize_email(email);
+        self.accounts.values().find(|s| s.email == email)
+    }
+
+    /// 获取邮箱对应的账户 ID；尚无备份时分配新的随机 ID
+    ///
+    /// 需在同一次 `update` 中写入备份并插入摘要，避免并发分配出两个 ID。
+    ///
+    /// # 返回
+    /// - `(id, exists)`: 账户 ID 和该账户是否已有备份
+    pub fn id_for_email(&self, email: &str) -> (String, bool) {
+        match self.find_by_email(email) {
+            Some(summary) => (summary.id.clone(), true),
+            None => (generate_account_id(), false),
+        }
+    }
+
+    /// 插入或更新账户摘要（以 summary.id 为键）
+    pub fn upsert(&mut self, summary: AccountSummary) {
+        self.unreadable.remove(&summary.id);
+        self.accounts.insert(summary.id.clone(), summary);
+    }
+
+    /// 按邮箱移除账户摘要
+    pub fn remove_by_email(&mut self, email: &str) -> Option<AccountSummary> {
+        let id = self.find_by_email(email)?.id.clone();
+        self.unreadable.remove(&id);
+        self.accounts.remove(&id)
+    }
+
+    /// 按邮箱获取可变的账户摘要
+    pub fn get_mut_by_email(&mut self, email: &str) -> Option<&mut AccountSummary> {
+        let email = normalize_email(email);
+        self.accounts.values_mut().find(|s| s.email == email)
+    }
+
+    /// 索引中已知的 ID 格式文件名（含无法解析的）
+    fn known_ids(&self) -> BTreeSet<String> {
+        self.accounts
+            .keys()
+            .chain(self.unreadable.iter().filter(|n| is_account_id(n)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 规范化邮箱：去除首尾空白并转为小写
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+/// 生成新的随机账户 ID
+fn generate_account_id() -> String {
+    hex::encode(random_bytes::<16>())
+}
+
+/// 判断文件名是否为账户 ID 格式（否则视为旧版邮箱命名）
+fn is_account_id(name: &str) -> bool {
+    name.len() == ACCOUNT_ID_LEN && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// 账户 ID 对应的备份文件路径
+pub fn backup_file_path(dir: &Path, id: &str) -> PathBuf {
+    dir.join(format!("{}.enc", id))
+}
+
+/// 从备份数据中提取账户摘要
+pub fn summarize_backup(id: &str, backup_data: &Value, fallback_email: &str) -> AccountSummary {
+    let email = normalize_email(
+        backup_data
+            .get("account_email")
+            .and_then(|v| v.as_str())
+            .unwrap_or(fallback_email),
+    );
+
+    let backup_time = backup_data
+        .get("backup_time")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let default_name = email.split('@').next().unwrap_or(&email).to_string();
+
+    let auth_data = backup_data
+        .get(database::AUTH_STATUS)
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str::<Value>(s).ok());
+
+    // 访问令牌不进入摘要索引（只记录过期时间与指纹），需要时由 credentials 模块从备份中读取
+    let (name, plan, user_status_proto, token_expires_at, fingerprints) = match auth_data {
+        Some(auth) => {
+            let name = auth
+                .get("name")
+                .or_else(|| auth.get("email"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or(default_name);
+
+            let plan = auth
+                .get("planName")
+                .or_else(|| auth.get("plan"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            let user_status_proto = auth
+                .get("userStatusProtoBinaryBase64")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            let token_expires_at = credentials::token_expiry(&auth);
+            let fingerprints = credentials::session_fingerprints(&auth);
+
+            (name, plan, user_status_proto, token_expires_at, fingerprints)
+        }
+        None => (default_name, None, None, None, (None, None)),
+    };
+
+    let profile_url = backup_data
+        .get(database::PROFILE_URL)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    AccountSummary {
+        id: id.to_string(),
+        email,
+        name,
+        plan,
+        last_switched: format_display_time(&backup_time),
+        backup_time,
+        profile_url,
+        user_status_proto,
+        token_expires_at,
+        token_fingerprint: fingerprints.0,
+        refresh_fingerprint: fingerprints.1,
+        secret_keys: secret_storage::secret_keys(backup_data),
+        backup_machine: backup_data
+            .get(secret_storage::BACKUP_MACHINE_KEY)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+    }
+}
+
+/// 当前本地时间（列表展示格式）
+pub fn now_display_time() -> String {
+    chrono::Local::now().format(DISPLAY_TIME_FORMAT).to_string()
+}
+
+/// 将 RFC3339 时间转换为列表展示格式，无法解析时原样返回
+fn format_display_time(rfc3339: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format(DISPLAY_TIME_FORMAT)
+                .to_string()
+        })
+        .unwrap_or_else(|_| rfc3339.to_string())
+}
+
+/// 读取索引；当索引缺失或与备份目录不一致时重建，并迁移旧版邮箱命名的备份
+pub fn load_or_rebuild() -> Result<AccountIndex, String> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    load_synced(&dir)
+}
+
+/// 对索引执行读-改-写操作
+pub fn update<R>(f: impl FnOnce(&mut AccountIndex) -> R) -> Result<R, String> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+
+    let mut index = load_synced(&dir)?;
+    let result = f(&mut index);
+    // 闭包中可能写入或删除了备份文件
+    index.files = file_stamps(&dir, &list_backup_names(&dir)?);
+    save_index(&dir, &index)?;
+    Ok(result)
+}
+
+/// 按邮箱查找备份文件路径（大小写不敏感）
+pub fn resolve_backup_path(email: &str) -> Result<Option<PathBuf>, String> {
+    let dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    let index = load_or_rebuild()?;
+    Ok(index
+        .find_by_email(email)
+        .map(|summary| backup_file_path(&dir, &summary.id)))
+}
+
+/// 加载索引并与备份目录同步（调用方需持有 INDEX_LOCK）
+fn load_synced(dir: &Path) -> Result<AccountIndex, String> {
+    let backup_names = list_backup_names(dir)?;
+    let (ids, legacy_names): (BTreeSet<String>, BTreeSet<String>) =
+        backup_names.iter().cloned().partition(|n| is_account_id(n));
+
+    let stamps = file_stamps(dir, &ids);
+
+    let (mut index, mut dirty) = match load_index(dir)? {
+        Some(index) if index.files == stamps => (index, false),
+        Some(mut index) => {
+            tracing::info!(target: "backup::index", "账户索引与备份文件不一致，正在同步");
+            resync_index(dir, &mut index, stamps);
+            (index, true)
+        }
+        None => (rebuild_index(dir, stamps), true),
+    };
+
+    // 清理已不存在的不可读记录，已知不可读的旧文件不重复尝试迁移
+    let unreadable_before = index.unreadable.len();
+    index.unreadable.retain(|n| backup_names.contains(n));
+    dirty |= index.unreadable.len() != unreadable_before;
+
+    let pending: Vec<String> = legacy_names
+        .into_iter()
+        .filter(|n| !index.unreadable.contains(n))
+        .collect();
+    if !pending.is_empty() {
+        migrate_legacy_files(dir, &mut index, &pending);
+        index.files = file_stamps(dir, &list_backup_names(dir)?);
+        dirty = true;
+    }
+
+    if dirty {
+        save_index(dir, &index)?;
+    }
+    Ok(index)
+}
+
+/// 将旧版以邮箱命名的备份迁移为随机 ID 命名的加密文件
+///
+/// 大小写不同的同一邮箱会合并，保留备份时间最新的一份。
+fn migrate_legacy_files(dir: &Path, index: &mut AccountIndex, legacy_names: &[String]) {
+    for name in legacy_names {
+        let encrypted_file = dir.join(format!("{}.enc", name));
+        let legacy_json = dir.join(format!("{}.json", name));
+        let source = if encrypted_file.exists() {
+            encrypted_file.clone()
+        } else {
+            legacy_json.clone()
+        };
+
+        let mut data = match read_backup_file(&source) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(target: "backup::index", error = %e, "旧版备份无法读取，跳过迁移");
+                index.unreadable.insert(name.clone());
+                continue;
+            }
+        };
+        if let Some(obj) = data.as_object_mut() {
+            obj.entry("account_email")
+                .or_insert_with(|| Value::String(name.clone()));
+        }
+
+        let email = normalize_email(
+            data.get("account_email")
+                .and_then(|v| v.as_str())
+                .unwrap_or(name),
+        );
+        let existing = index.find_by_email(&email).cloned();
+        let id = existing
+            .as_ref()
+            .map(|s| s.id.clone())
+            .unwrap_or_else(generate_account_id);
+
+        let mut summary = summarize_backup(&id, &data, name);
+        if summary.last_switched.is_empty() {
+            summary.last_switched = file_modified_display_time(&source).unwrap_or_default();
+        }
+
+        // 已存在同一邮箱的较新备份时，仅删除旧文件
+        let keep_existing = existing
+            .as_ref()
+            .is_some_and(|s| s.backup_time >= summary.backup_time);
+
+        if !keep_existing {
+            let written = serde_json::to_vec_pretty(&data)
+                .map_err(|e| e.to_string())
+                .and_then(|json| account_keys::encrypt(&id, &json))
+                .and_then(|encrypted| {
+                    secure_write_file(&backup_file_path(dir, &id), &encrypted)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = written {
+                tracing::warn!(target: "backup::index", error = %e, "写入迁移后的备份失败，保留旧文件");
+                index.unreadable.insert(name.clone());
+                continue;
+            }
+            if let Some(previous) = existing {
+                summary.last_switched = summary.last_switched.max(previous.last_switched);
+            }
+            index.upsert(summary);
+        }
+
+        for old_file in [&encrypted_file, &legacy_json] {
+            if let Err(e) = secure_delete_file(old_file) {
+                tracing::warn!(target: "backup::index", error = %e, "删除旧版备份文件失败");
+            }
+        }
+    }
+
+    tracing::info!(target: "backup::index", migrated = legacy_names.len(), "旧版邮箱命名备份已迁移");
+}
+
+/// 文件修改时间（列表展示格式）
+fn file_modified_display_time(path: &Path) -> Option<String> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let datetime: chrono::DateTime<chrono::Local> = modified.into();
+    Some(datetime.format(DISPLAY_TIME_FORMAT).to_string())
+}
+
+/// ID 命名备份文件的大小与修改时间（名称集合中的旧版邮箱命名文件被忽略）
+fn file_stamps(dir: &Path, names: &BTreeSet<String>) -> BTreeMap<String, FileStamp> {
+    names
+        .iter()
+        .filter(|name| is_account_id(name))
+        .filter_map(|id| {
+            let metadata = fs::metadata(backup_file_path(dir, id)).ok()?;
+            let modified_ns = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_nanos() as u64);
+            Some((id.clone(), FileStamp { len: metadata.len(), modified_ns }))
+        })
+        .collect()
+}
+
+/// 列出备份目录中的账户备份名称（不含索引等元数据文件）
+fn list_backup_names(dir: &Path) -> Result<BTreeSet<String>, String> {
+    let mut names = BTreeSet::new();
+    if !dir.exists() {
+        return Ok(names);
+    }
+
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取备份目录失败: {}", e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.file_name().is_some_and(|n| n == INDEX_FILE_NAME || n == KEYS_FILE_NAME) {
+            continue;
+        }
+        let ext = path.extension().and_then(|e| e.to_str());
+        if !matches!(ext, Some("enc") | Some("json")) {
+            continue;
+        }
+        if let Some(stem) = path.file_stem() {
+            names.insert(stem.to_string_lossy().to_string());
+        }
+    }
+    Ok(names)
+}
+
+fn load_index(dir: &Path) -> Result<Option<AccountIndex>, String> {
+    let path = dir.join(INDEX_FILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read(&path).map_err(|e| format!("读取账户索引失败: {}", e))?;
+    let decrypted = vault().decrypt(&content)?;
+    match serde_json::from_slice::<AccountIndex>(&decrypted) {
+        Ok(index) if index.version == INDEX_VERSION => Ok(Some(index)),
+        Ok(_) => Ok(None),
+        Err(e) => {
+            tracing::warn!(target: "backup::index", error = %e, "账户索引损坏，将重建");
+            Ok(None)
+        }
+    }
+}
+
+fn save_index(dir: &Path, index: &AccountIndex) -> Result<(), String> {
+    secure_create_dir(dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec(index).map_err(|e| format!("序列化账户索引失败: {}", e))?;
+    let encrypted = vault().encrypt(&json)?;
+    secure_write_file(&dir.join(INDEX_FILE_NAME), &encrypted).map_err(|e| e.to_string())
+}
+
+/// 逐个解密 ID 命名的备份重建索引（仅在索引缺失或无法解析时执行）
+fn rebuild_index(dir: &Path, stamps: BTreeMap<String, FileStamp>) -> AccountIndex {
+    let mut index = AccountIndex {
+        version: INDEX_VERSION,
+        ..Default::default()
+    };
+    resync_index(dir, &mut index, stamps);
+
+    tracing::info!(target: "backup::index", account_count = index.accounts.len(), "账户索引已重建");
+    index
+}
+
+/// 按文件状态同步索引：移除已删除的备份，只重新读取新增或变化的备份
+///
+/// 备份被外部替换时保留原有的最后切换时间。
+fn resync_index(dir: &Path, index: &mut AccountIndex, stamps: BTreeMap<String, FileStamp>) {
+    for id in index.known_ids() {
+        if !stamps.contains_key(&id) {
+            index.accounts.remove(&id);
+            index.unreadable.remove(&id);
+        }
+    }
+
+    for (id, stamp) in &stamps {
+        let known = index.accounts.contains_key(id) || index.unreadable.contains(id);
+        if known && index.files.get(id) == Some(stamp) {
+            continue;
+        }
+
+        let path = backup_file_path(dir, id);
+        match read_backup_file(&path) {
+            Ok(data) => {
+                let mut summary = summarize_backup(id, &data, "");
+                if summary.last_switched.is_empty() {
+                    summary.last_switched = file_modified_display_time(&path).unwrap_or_default();
+                }
+                if let Some(previous) = index.accounts.get(id) {
+                    summary.last_switched = summary.last_switched.max(previous.last_switched.clone());
+                }
+                index.upsert(summary);
+            }
+            Err(e) => {
+                tracing::warn!(target: "backup::index", file = %id, error = %e, "读取备份失败，跳过");
+                index.accounts.remove(id);
+                index.unreadable.insert(id.clone());
+            }
+        }
+    }
+    index.files = stamps;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_utils::use_test_config_dir;
+
+    fn write_backup(dir: &Path, id: &str, email: &str, name: &str) {
+        let auth = serde_json::json!({ "name": name, "email": email }).to_string();
+        let data = serde_json::json!({
+            "account_email": email,
+            "backup_time": "2024-01-01T00:00:00+00:00",
+            database::AUTH_STATUS: auth,
+        });
+        let encrypted = vault().encrypt(data.to_string().as_bytes()).unwrap();
+        secure_write_file(&backup_file_path(dir, id), &encrypted).unwrap();
+    }
+
+    #[test]
+    fn test_index_resyncs_replaced_files() {
+        use_test_config_dir("account-index-resync");
+        let dir = AppPaths::backup_dir().unwrap();
+        secure_create_dir(&dir).unwrap();
+        let (a, b) = (generate_account_id(), generate_account_id());
+        write_backup(&dir, &a, "a@example.com", "Alice");
+        write_backup(&dir, &b, "b@example.com", "Bob");
+
+        let index = load_or_rebuild().unwrap();
+        assert_eq!(index.accounts().count(), 2);
+        update(|index| index.get_mut_by_email("a@example.com").unwrap().last_switched = "2099-01-01 00:00:00".into())
+            .unwrap();
+
+        // 文件名集合不变，但内容被外部替换
+        write_backup(&dir, &a, "a@example.com", "Alice Replaced");
+        let stamp = file_stamps(&dir, &BTreeSet::from([a.clone()])).remove(&a).unwrap();
+        let index = load_or_rebuild().unwrap();
+        let summary = index.get(&a).unwrap();
+        assert_eq!(summary.name, "Alice Replaced");
+        assert_eq!(summary.last_switched, "2099-01-01 00:00:00");
+        assert_eq!(index.files.get(&a), Some(&stamp));
+
+        fs::remove_file(backup_file_path(&dir, &b)).unwrap();
+        let index = load_or_rebuild().unwrap();
+        assert!(index.find_by_email("b@example.com").is_none());
+        assert_eq!(index.files.len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_email() {
+        assert_eq!(normalize_email("  Foo.Bar@Example.COM \n"), "foo.bar@example.com");
+        assert!(is_account_id(&generate_account_id()));
+        assert!(!is_account_id("foo@example.com"));
+    }
+
+    #[test]
+    fn test_migrates_legacy_email_named_backups() {
+        use_test_config_dir("account-index-migrate");
+        let dir = AppPaths::backup_dir().unwrap();
+        secure_create_dir(&dir).unwrap();
+
+        // 同一邮箱大小写不同的两份旧版备份：加密的较新，明文的较旧
+        let newer = serde_json::json!({ "account_email": "Foo@Example.com", "backup_time": "2024-06-01T00:00:00+00:00" });
+        let older = serde_json::json!({ "backup_time": "2024-01-01T00:00:00+00:00" });
+        let encrypted = vault().encrypt(newer.to_string().as_bytes()).unwrap();
+        secure_write_file(&dir.join("Foo@Example.com.enc"), &encrypted).unwrap();
+        fs::write(dir.join("foo@example.com.json"), older.to_string()).unwrap();
+
+        let index = load_or_rebuild().unwrap();
+        assert_eq!(index.accounts().count(), 1);
+        let summary = index.find_by_email("FOO@example.com").unwrap();
+        assert_eq!(summary.email, "foo@example.com");
+        assert_eq!(summary.backup_time, "2024-06-01T00:00:00+00:00");
+
+        let names = list_backup_names(&dir).unwrap();
+        assert_eq!(names, BTreeSet::from([summary.id.clone()]));
+        let migrated = read_backup_file(&backup_file_path(&dir, &summary.id)).unwrap();
+        assert_eq!(migrated["backup_time"], "2024-06-01T00:00:00+00:00");
+
+        // 迁移后的索引与目录一致，再次读取不触发同步
+        assert_eq!(load_or_rebuild().unwrap().files, index.files);
+    }
+
+    #[test]
+    fn test_imported_legacy_file_keeps_existing_entries() {
+        use_test_config_dir("account-index-import");
+        let dir = AppPaths::backup_dir().unwrap();
+        secure_create_dir(&dir).unwrap();
+        fs::write(dir.join("a@example.com.json"), serde_json::json!({ "backup_time": "2024-01-01T00:00:00+00:00" }).to_string())
+            .unwrap();
+        load_or_rebuild().unwrap();
+        update(|index| index.get_mut_by_email("a@example.com").unwrap().last_switched = "2099-01-01 00:00:00".into())
+            .unwrap();
+
+        // 导入写入的旧版文件在下次读取时迁移，已有记录不变
+        fs::write(dir.join("b@example.com.json"), serde_json::json!({ "backup_time": "2024-02-01T00:00:00+00:00" }).to_string())
+            .unwrap();
+        let index = load_or_rebuild().unwrap();
+        assert_eq!(index.accounts().count(), 2);
+        assert!(index.find_by_email("b@example.com").is_some());
+        assert_eq!(index.find_by_email("a@example.com").unwrap().last_switched, "2099-01-01 00:00:00");
+    }
+}
@@ -0,0 +1,277 @@
+// Antigravity 账户数据密钥模块
+// 每个账户的备份（清单、快照、数据块）使用独立的随机数据密钥加密，
+// 数据密钥保存在由保险库密钥加密的密钥表（keys.enc）中
+//
+// 删除账户时从密钥表中销毁其数据密钥，残留在磁盘上的密文无法再用当前密钥表解密（加密擦除）。
+// 注意这只是较弱的保证：密钥表通过临时文件整体替换，旧版本 keys.enc 的数据块
+// 在 SSD / 写时复制文件系统、文件系统快照或外部备份中可能仍然存在，
+// 同时拿到旧密钥表与保险库密钥的人仍可解密已删除账户的备份。
+// 切换保险库模式时也只需重新加密密钥表，无需逐个转换备份文件。
+//
+// 解密后的密钥表在会话内缓存（按文件大小与修改时间校验），避免每解密一个数据块都重新读取；
+// 保险库锁定时缓存随之清除。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use zeroize::Zeroizing;
+
+use crate::antigravity::vault::vault;
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{
+    data_key_id, decrypt_with_data_key, encrypt_with_data_key, is_data_key_encrypted,
+    random_bytes, secure_create_dir, secure_delete_file, secure_write_file,
+};
+
+/// 密钥表文件名（位于备份目录中）
+pub const KEYS_FILE_NAME: &str = "keys.enc";
+
+/// 当前密钥表格式版本
+const KEY_TABLE_VERSION: u32 = 1;
+
+/// 已解密的密钥表缓存，同时串行化密钥表的读-改-写
+static KEYS: Mutex<Option<CachedTable>> = Mutex::new(None);
+
+/// 数据密钥表（账户 ID -> 十六进制数据密钥）
+#[derive(Serialize, Deserialize)]
+struct KeyTable {
+    version: u32,
+    keys: BTreeMap<String, String>,
+}
+
+impl Drop for KeyTable {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        for key in self.keys.values_mut() {
+            key.zeroize();
+        }
+    }
+}
+
+/// 缓存的密钥表及读取时的文件状态
+struct CachedTable {
+    path: PathBuf,
+    stamp: Option<(u64, SystemTime)>,
+    table: KeyTable,
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+fn keys_path() -> Result<PathBuf, String> {
+    AppPaths::backup_dir()
+        .map(|dir| dir.join(KEYS_FILE_NAME))
+        .ok_or_else(|| "无法获取备份目录".to_string())
+}
+
+fn load_table(path: &Path) -> Result<KeyTable, String> {
+    if !path.exists() {
+        return Ok(KeyTable {
+            version: KEY_TABLE_VERSION,
+            keys: BTreeMap::new(),
+        });
+    }
+
+    let content = fs::read(path).map_err(|e| format!("读取密钥表失败: {}", e))?;
+    let decrypted = Zeroizing::new(vault().decrypt(&content)?);
+    let table: KeyTable =
+        serde_json::from_slice(&decrypted).map_err(|e| format!("密钥表解析失败: {}", e))?;
+    if table.version > KEY_TABLE_VERSION {
+        return Err(format!("不支持的密钥表版本: {}", table.version));
+    }
+    Ok(table)
+}
+
+/// 写入密钥表：先写临时文件再整体替换，写入中断时不会留下损坏的密钥表
+fn save_table(path: &Path, table: &KeyTable) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        secure_create_dir(parent).map_err(|e| e.to_string())?;
+    }
+    let json = Zeroizing::new(serde_json::to_vec(table).map_err(|e| e.to_string())?);
+    let encrypted = vault().encrypt(&json)?;
+    let tmp = path.with_extension("enc.tmp");
+    secure_write_file(&tmp, &encrypted).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        format!("写入密钥表失败: {}", e)
+    })
+}
+
+/// 获取密钥表（调用方需持有 KEYS 锁），文件未变化时使用缓存
+///
+/// 主密码模式下保险库锁定时拒绝访问，即使缓存中仍有密钥表。
+fn table<'a>(cache: &'a mut Option<CachedTable>, path: &Path) -> Result<&'a mut KeyTable, String> {
+    if let Err(e) = vault().ensure_unlocked() {
+        cache.take();
+        return Err(e);
+    }
+    let stamp = file_stamp(path);
+    let cached = match cache.take() {
+        Some(cached) if cached.path == path && cached.stamp == stamp => cached,
+        _ => CachedTable {
+            path: path.to_path_buf(),
+            stamp,
+            table: load_table(path)?,
+        },
+    };
+    Ok(&mut cache.insert(cached).table)
+}
+
+/// 保存缓存中修改过的密钥表；保存失败时丢弃缓存，避免使用未落盘的密钥
+fn store(cache: &mut Option<CachedTable>) -> Result<(), String> {
+    let Some(cached) = cache.as_mut() else {
+        return Ok(());
+    };
+    match save_table(&cached.path, &cached.table) {
+        Ok(()) => {
+            cached.stamp = file_stamp(&cached.path);
+            Ok(())
+        }
+        Err(e) => {
+            *cache = None;
+            Err(e)
+        }
+    }
+}
+
+/// 清除缓存的密钥表（保险库锁定时调用）
+///
+/// 锁定可能发生在本模块持有锁访问保险库期间，此时跳过清除；
+/// 下次访问时 `table` 会因保险库已锁定而丢弃缓存。
+pub fn clear_cache() {
+    if let Ok(mut cache) = KEYS.try_lock() {
+        cache.take();
+    }
+}
+
+fn parse_key(hex_key: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let bytes = Zeroizing::new(hex::decode(hex_key).map_err(|e| format!("数据密钥格式错误: {}", e))?);
+    let mut key = Zeroizing::new([0u8; 32]);
+    if bytes.len() != key.len() {
+        return Err("数据密钥长度错误".to_string());
+    }
+    key.copy_from_slice(&bytes);
+    Ok(key)
+}
+
+/// 查找账户的数据密钥（已销毁或不存在时返回 None）
+fn find_key(account_id: &str) -> Result<Option<Zeroizing<[u8; 32]>>, String> {
+    let mut cache = KEYS.lock().unwrap();
+    let table = table(&mut cache, &keys_path()?)?;
+    table.keys.get(account_id).map(|k| parse_key(k)).transpose()
+}
+
+/// 获取账户的数据密钥，不存在时生成新的随机密钥并写入密钥表
+pub fn data_key(account_id: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let mut cache = KEYS.lock().unwrap();
+    let table = table(&mut cache, &keys_path()?)?;
+    if let Some(existing) = table.keys.get(account_id) {
+        return parse_key(existing);
+    }
+
+    let key = Zeroizing::new(random_bytes::<32>());
+    table.keys.insert(account_id.to_string(), hex::encode(*key));
+    store(&mut cache)?;
+    tracing::info!(target: "backup::keys", "已为账户生成新的数据密钥");
+    Ok(key)
+}
+
+/// 使用账户的数据密钥加密
+pub fn encrypt(account_id: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let key = data_key(account_id)?;
+    encrypt_with_data_key(account_id, plaintext, &key).map_err(|e| e.to_string())
+}
+
+/// 解密备份内容
+///
+/// 数据密钥格式按内嵌的账户 ID 查找密钥；其他格式（旧版备份、索引等）交由保险库解密。
+pub fn decrypt(content: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_data_key_encrypted(content) {
+        return vault().decrypt(content);
+    }
+
+    let account_id = data_key_id(content).ok_or("数据密钥格式损坏")?;
+    let key = find_key(account_id)?.ok_or("该备份的数据密钥已销毁，内容不可恢复")?;
+    decrypt_with_data_key(content, &key).map_err(|e| e.to_string())
+}
+
+/// 判断密文的数据密钥是否已被销毁（用于清理孤立文件）
+pub fn is_shredded(content: &[u8]) -> Result<bool, String> {
+    match data_key_id(content) {
+        Some(account_id) => Ok(find_key(account_id)?.is_none()),
+        None => Ok(false),
+    }
+}
+
+/// 从密钥表中销毁账户的数据密钥，使其备份密文无法再被解密
+///
+/// 旧版本密钥表可能仍残留在磁盘上（见模块说明）。
+///
+/// # 返回
+/// - `Ok(true)`: 密钥已销毁；`Ok(false)`: 该账户没有数据密钥
+pub fn destroy(account_id: &str) -> Result<bool, String> {
+    let mut cache = KEYS.lock().unwrap();
+    let table = table(&mut cache, &keys_path()?)?;
+    let Some(mut removed) = table.keys.remove(account_id) else {
+        return Ok(false);
+    };
+    zeroize::Zeroize::zeroize(&mut removed);
+    store(&mut cache)?;
+    tracing::info!(target: "backup::keys", "账户数据密钥已销毁");
+    Ok(true)
+}
+
+/// 销毁整个密钥表，使全部备份密文无法再被解密
+pub fn destroy_all() -> Result<(), String> {
+    let mut cache = KEYS.lock().unwrap();
+    cache.take();
+    let path = keys_path()?;
+    if path.exists() {
+        secure_delete_file(&path).map_err(|e| e.to_string())?;
+        tracing::info!(target: "backup::keys", "密钥表已销毁");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_utils::use_test_config_dir;
+
+    #[test]
+    fn test_generate_find_destroy() {
+        use_test_config_dir("account-keys");
+        let path = keys_path().unwrap();
+        assert!(find_key("a").unwrap().is_none());
+
+        let key_a = data_key("a").unwrap();
+        assert_eq!(*data_key("a").unwrap(), *key_a);
+        assert_ne!(*data_key("b").unwrap(), *key_a);
+        assert_eq!(*find_key("a").unwrap().unwrap(), *key_a);
+        assert!(path.exists());
+        assert!(!path.with_extension("enc.tmp").exists());
+
+        let encrypted = encrypt("a", b"secret").unwrap();
+        assert!(!is_shredded(&encrypted).unwrap());
+        assert_eq!(decrypt(&encrypted).unwrap(), b"secret");
+
+        // 清除缓存后从磁盘重新读取
+        clear_cache();
+        assert!(destroy("a").unwrap());
+        assert!(!destroy("a").unwrap());
+        assert!(is_shredded(&encrypted).unwrap());
+        assert!(decrypt(&encrypted).is_err());
+        clear_cache();
+        assert!(find_key("a").unwrap().is_none());
+        assert!(find_key("b").unwrap().is_some());
+
+        destroy_all().unwrap();
+        assert!(!path.exists());
+        assert!(find_key("b").unwrap().is_none());
+    }
+}
@@ -0,0 +1,209 @@
+// Antigravity 活动账户检测模块
+// 将 state.vscdb 中实时的 antigravityAuthStatus 与所有已保存备份比较，
+// 判断当前登录的是哪个账户，以及实时会话相对备份是更新、更旧还是已分叉
+//
+// 比较只使用索引中保存的令牌指纹与过期时间，无需逐个解密备份。
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::antigravity::account_index::{self, AccountIndex};
+use crate::antigravity::credentials;
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+
+/// 活动账户变化时推送的事件名
+pub const ACTIVE_ACCOUNT_EVENT: &str = "active-account-changed";
+
+/// 上一次检测结果（用于判断是否需要推送事件）
+static LAST_STATUS: Mutex<Option<ActiveAccountStatus>> = Mutex::new(None);
+
+/// 实时会话与备份的关系
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Antigravity 未登录
+    NotLoggedIn,
+    /// 已登录但该账户没有备份
+    Unsaved,
+    /// 实时令牌与备份一致
+    InSync,
+    /// 同一会话的令牌已刷新，比备份更新
+    Newer,
+    /// 实时令牌比备份更旧
+    Older,
+    /// 令牌不同且无法判断先后（如重新登录产生的新会话）
+    Divergent,
+}
+
+/// 活动账户检测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActiveAccountStatus {
+    /// 活动账户的句柄（没有对应备份时为空）
+    pub handle: Option<String>,
+    /// 实时登录的邮箱
+    pub email: Option<String>,
+    pub state: SessionState,
+    /// 实时令牌的过期时间（Unix 秒）
+    pub live_token_expires_at: Option<i64>,
+    /// 备份令牌的过期时间（Unix 秒）
+    pub saved_token_expires_at: Option<i64>,
+}
+
+impl ActiveAccountStatus {
+    fn without_account(email: Option<String>, state: SessionState, live_expiry: Option<i64>) -> Self {
+        Self {
+            handle: None,
+            email,
+            state,
+            live_token_expires_at: live_expiry,
+            saved_token_expires_at: None,
+        }
+    }
+}
+
+/// 读取 state.vscdb 中实时的认证信息
+pub(crate) fn read_live_auth() -> Result<Option<Value>, String> {
+    let db_path = AppPaths::antigravity_data_dir()
+        .map(|dir| dir.join("state.vscdb"))
+        .ok_or("未找到数据库路径")?;
+    if !db_path.exists() {
+        return Ok(None);
+    }
+
+    let conn = Connection::open(&db_path).map_err(|e| format!("连接数据库失败: {}", e))?;
+    let auth_json: Option<String> = conn
+        .query_row(
+            "SELECT value FROM ItemTable WHERE key = ?",
+            [database::AUTH_STATUS],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("查询认证信息失败: {}", e))?;
+
+    Ok(auth_json.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+/// 将实时认证信息与索引中的备份摘要比较
+pub fn compare_with_backups(live_auth: Option<&Value>, index: &AccountIndex) -> ActiveAccountStatus {
+    let Some(auth) = live_auth else {
+        return ActiveAccountStatus::without_account(None, SessionState::NotLoggedIn, None);
+    };
+    let email = auth.get("email").and_then(|v| v.as_str()).map(account_index::normalize_email);
+    let (access, refresh) = credentials::session_fingerprints(auth);
+    let live_expiry = credentials::token_expiry(auth);
+    if access.is_none() && email.is_none() {
+        return ActiveAccountStatus::without_account(None, SessionState::NotLoggedIn, None);
+    }
+
+    // 令牌指纹完全一致的备份优先（即使邮箱字段缺失也能识别）
+    let exact = access
+        .as_ref()
+        .and_then(|fp| index.accounts().find(|s| s.token_fingerprint.as_ref() == Some(fp)));
+    let (summary, state) = match exact {
+        Some(summary) => (summary, SessionState::InSync),
+        None => {
+            let Some(summary) = email.as_deref().and_then(|e| index.find_by_email(e)) else {
+                return ActiveAccountStatus::without_account(email, SessionState::Unsaved, live_expiry);
+            };
+
+            // 刷新令牌不同说明是另一次登录，先后无从比较
+            let same_session = match (&refresh, &summary.refresh_fingerprint) {
+                (Some(live), Some(saved)) => live == saved,
+                _ => true,
+            };
+            let state = match (live_expiry, summary.token_expires_at) {
+                (Some(live), Some(saved)) if same_session && live > saved => SessionState::Newer,
+                (Some(live), Some(saved)) if same_session && live < saved => SessionState::Older,
+                _ => SessionState::Divergent,
+            };
+            (summary, state)
+        }
+    };
+
+    ActiveAccountStatus {
+        handle: Some(summary.id.clone()),
+        email: email.or_else(|| Some(summary.email.clone())),
+        state,
+        live_token_expires_at: live_expiry,
+        saved_token_expires_at: summary.token_expires_at,
+    }
+}
+
+/// 检测当前活动账户
+pub fn detect() -> Result<ActiveAccountStatus, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    let live_auth = read_live_auth()?;
+    let index = account_index::load_or_rebuild()?;
+    Ok(compare_with_backups(live_auth.as_ref(), &index))
+}
+
+/// 重新检测活动账户，结果变化时更新 AppState 并推送事件
+pub fn refresh(app: &AppHandle) -> Result<ActiveAccountStatus, String> {
+    let status = detect()?;
+
+    let changed = {
+        let mut last = LAST_STATUS.lock().unwrap();
+        let changed = last.as_ref() != Some(&status);
+        *last = Some(status.clone());
+        changed
+    };
+
+    if changed {
+        *app.state::<crate::AppState>().current_account_id.lock().unwrap() = status.handle.clone();
+        tracing::info!(target: "account::active", state = ?status.state, "活动账户状态变化");
+        if let Err(e) = app.emit(ACTIVE_ACCOUNT_EVENT, &status) {
+            tracing::warn!(target: "account::active", error = %e, "推送活动账户事件失败");
+        }
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antigravity::account_index::{summarize_backup, AccountIndex};
+
+    fn index_with(auth: Value) -> AccountIndex {
+        let backup = serde_json::json!({
+            "antigravityAuthStatus": auth.to_string(),
+            "account_email": "user@example.com",
+            "backup_time": "2025-01-01T00:00:00+00:00",
+        });
+        let mut index = AccountIndex::default();
+        index.upsert(summarize_backup("a".repeat(32).as_str(), &backup, "user@example.com"));
+        index
+    }
+
+    #[test]
+    fn test_compare_session_states() {
+        let saved = serde_json::json!({
+            "email": "user@example.com", "apiKey": "t1", "refreshToken": "r1", "expiresAt": 1000
+        });
+        let index = index_with(saved.clone());
+
+        let in_sync = compare_with_backups(Some(&saved), &index);
+        assert_eq!(in_sync.state, SessionState::InSync);
+        assert_eq!(in_sync.handle.as_deref(), Some("a".repeat(32).as_str()));
+
+        let refreshed = serde_json::json!({
+            "email": "User@Example.com", "apiKey": "t2", "refreshToken": "r1", "expiresAt": 2000
+        });
+        assert_eq!(compare_with_backups(Some(&refreshed), &index).state, SessionState::Newer);
+
+        let relogin = serde_json::json!({
+            "email": "user@example.com", "apiKey": "t3", "refreshToken": "r2", "expiresAt": 3000
+        });
+        assert_eq!(compare_with_backups(Some(&relogin), &index).state, SessionState::Divergent);
+
+        let other = serde_json::json!({ "email": "other@example.com", "apiKey": "t4" });
+        let unsaved = compare_with_backups(Some(&other), &index);
+        assert_eq!(unsaved.state, SessionState::Unsaved);
+        assert!(unsaved.handle.is_none());
+
+        assert_eq!(compare_with_backups(None, &index).state, SessionState::NotLoggedIn);
+    }
+}
@@ -1,6 +1,6 @@
 // Antigravity 用户数据备份模块
 // 负责将 Antigravity 应用数据备份到加密文件
-// 使用 AES-256-GCM 加密，基于机器特征派生密钥
+// 使用 AES-256-GCM 加密，基于机器特征或主密码（保险库模式）派生密钥
 
 use rusqlite::{Connection, OptionalExtension};
 use serde_json::Value;
@@ -9,7 +9,11 @@ use std::path::Path;
 
 use crate::constants::database;
 use crate::path_utils::AppPaths;
-use crate::utils::crypto::{encrypt_machine_data, decrypt_machine_data, secure_write_file, secure_create_dir, secure_delete_file, is_encrypted};
+use crate::antigravity::{
+    account_index, account_keys, key_sets, quota_history, secret_storage, snapshot_store, user_files,
+};
+use crate::antigravity::vault::vault;
+use crate::utils::crypto::{secure_write_file, secure_create_dir, is_encrypted};
 
 /// 智能备份 Antigravity 账户（终极版 - 保存完整 Marker）
 ///
@@ -21,23 +25,24 @@ use crate::utils::crypto::{encrypt_machine_data, decrypt_machine_data, secure_wr
 /// # 参数
 /// - `email`: 用户邮箱
 ///
+/// 备份文件以随机账户 ID 命名，邮箱到 ID 的映射保存在加密索引中。
+///
 /// # 返回
-/// - `Ok((backup_name, is_overwrite))`: 备份文件名和是否为覆盖操作
+/// - `Ok((backup_name, is_overwrite))`: 规范化后的邮箱和是否为覆盖操作
 /// - `Err(message)`: 错误信息
 pub fn smart_backup_antigravity_account(email: &str) -> Result<(String, bool), String> {
     tracing::info!("🔧 执行智能备份（加密模式），邮箱: {}", email);
 
+    // 主密码模式下需要先解锁保险库
+    vault().ensure_unlocked()?;
+
     let config_dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
     
     // 使用安全方式创建目录（设置 0700 权限）
     secure_create_dir(&config_dir).map_err(|e| e.to_string())?;
 
-    // 简单的覆盖逻辑：每个邮箱只保留一个备份
-    // 使用 .enc 扩展名表示加密文件
-    let backup_name = email.to_string();
-    let encrypted_file = config_dir.join(format!("{}.enc", backup_name));
-    let legacy_file = config_dir.join(format!("{}.json", backup_name));
-    let is_overwrite = encrypted_file.exists() || legacy_file.exists();
+    // 简单的覆盖逻辑：每个邮箱（大小写不敏感）只保留一个备份
+    let backup_name = account_index::normalize_email(email);
 
     let app_data = AppPaths::antigravity_data_dir()
         .map(|path| path.join("state.vscdb"))
@@ -47,24 +52,94 @@ pub fn smart_backup_antigravity_account(email: &str) -> Result<(String, bool), S
         return Err(format!("数据库文件不存在: {}", app_data.display()));
     }
 
-    let conn = Connection::open(&app_data).map_err(|e| e.to_string())?;
+    // 1-2. 提取字段原始值与 Marker
+    let mut data_map = read_account_data(&app_data)?;
+
+    // 3. 添加元信息
+    data_map.insert(
+        "account_email".to_string(),
+        Value::String(backup_name.clone()),
+    );
+    data_map.insert(
+        "backup_time".to_string(),
+        Value::String(chrono::Local::now().to_rfc3339()),
+    );
+    // 记录来源设备，恢复时判断扩展密钥能否解密
+    if let Some(machine) = secret_storage::current_machine() {
+        data_map.insert(
+            secret_storage::BACKUP_MACHINE_KEY.to_string(),
+            Value::String(machine.to_string()),
+        );
+    }
+
+    let auth = data_map
+        .get(database::AUTH_STATUS)
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str::<Value>(s).ok());
+
+    // 4. 写入去重快照存储，账户备份文件（以账户 ID 命名）保存最新快照的清单
+    //    分配账户 ID、写入文件与更新索引在同一次索引锁内完成，同一新邮箱的并发备份不会得到两个 ID
+    let (account_id, is_overwrite, backup_file) = account_index::update(|index| {
+        let (account_id, is_overwrite) = index.id_for_email(&backup_name);
+        let manifest = snapshot_store::write_snapshot(&account_id, &data_map)?;
+        let backup_file = account_index::backup_file_path(&config_dir, &account_id);
+
+        // 使用账户数据密钥加密，安全方式写入文件（设置 0600 权限）
+        let encrypted_content = snapshot_store::encrypt_manifest(&manifest)?;
+        secure_write_file(&backup_file, &encrypted_content).map_err(|e| e.to_string())?;
+
+        // 同步更新账户摘要索引
+        index.upsert(account_index::summarize_backup(&account_id, &Value::Object(data_map), &backup_name));
+        Ok::<_, String>((account_id, is_overwrite, backup_file))
+    })??;
+
+    // 超出保留数量的旧快照被删除后，回收不再引用的数据
+    match snapshot_store::prune_snapshots(&account_id, snapshot_store::MAX_SNAPSHOTS_PER_ACCOUNT) {
+        Ok(0) => {}
+        Ok(_) => {
+            if let Err(e) = snapshot_store::collect_garbage() {
+                tracing::warn!(target: "backup::store", error = %e, "快照垃圾回收失败");
+            }
+        }
+        Err(e) => tracing::warn!(target: "backup::store", error = %e, "清理旧快照失败"),
+    }
+
+    // 5. 记录配额历史（失败不影响备份）
+    if let Some(auth) = auth {
+        if let Err(e) = quota_history::record_from_auth(&account_id, &auth, quota_history::SampleSource::Backup) {
+            tracing::warn!(target: "quota::history", error = %e, "记录配额历史失败");
+        }
+    }
 
-    // 使用常量定义所有需要备份的关键字段
-    let keys_to_backup = database::ALL_KEYS;
+    let action = if is_overwrite { "覆盖" } else { "创建" };
+    tracing::info!(target: "backup::database", action = %action, file = %backup_file.display(), "加密备份成功");
+    Ok((backup_name, is_overwrite))
+}
+
+/// 读取数据库中与账户相关的全部字段（原始字符串）、解析后的 Marker 及定义中的账户文件
+///
+/// 备份与切换前的撤销快照共用此读取逻辑。
+pub fn read_account_data(db_path: &Path) -> Result<serde_json::Map<String, Value>, String> {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    // 按字段定义确定需要备份的字段（精确字段及匹配通配规则的字段）
+    let key_set = key_sets::current();
+    let existing = key_sets::list_db_keys(&conn)?;
+    let keys_to_backup = key_set.resolve(&existing);
 
     let mut data_map = serde_json::Map::new();
 
     // 1. 提取数据（保持原始字符串格式）
     for key in keys_to_backup {
         let val: Option<String> = conn
-            .query_row("SELECT value FROM ItemTable WHERE key = ?", [key], |row| {
+            .query_row("SELECT value FROM ItemTable WHERE key = ?", [&key], |row| {
                 row.get(0)
             })
             .optional()
             .unwrap_or(None);
 
         if let Some(v) = val {
-            data_map.insert(key.to_string(), Value::String(v));
+            data_map.insert(key, Value::String(v));
         } else {
             tracing::debug!(target: "backup::database", key = %key, "字段不存在，跳过");
         }
@@ -96,46 +171,22 @@ pub fn smart_backup_antigravity_account(email: &str) -> Result<(String, bool), S
         }
     }
 
-    // 3. 添加元信息
-    data_map.insert(
-        "account_email".to_string(),
-        Value::String(email.to_string()),
-    );
-    data_map.insert(
-        "backup_time".to_string(),
-        Value::String(chrono::Local::now().to_rfc3339()),
-    );
-
-    // 4. 加密并写入备份文件
-    let backup_file = config_dir.join(format!("{}.enc", backup_name));
-    let file_content = serde_json::to_string_pretty(&data_map).map_err(|e| e.to_string())?;
-    
-    // 派生机器密钥并加密 (自动使用 Argon2 V2)
-    let encrypted_content = encrypt_machine_data(file_content.as_bytes())
-        .map_err(|e| format!("加密失败: {}", e))?;
-    
-    // 使用安全方式写入文件（设置 0600 权限）
-    secure_write_file(&backup_file, &encrypted_content).map_err(|e| e.to_string())?;
-    
-    // 删除旧的明文备份文件（如果存在）
-    let legacy_file = config_dir.join(format!("{}.json", backup_name));
-    if legacy_file.exists() {
-        if let Err(e) = secure_delete_file(&legacy_file) {
-            tracing::warn!(target: "backup::database", error = %e, "删除旧明文备份失败");
-        } else {
-            tracing::info!(target: "backup::database", "已安全删除旧明文备份文件");
+    // 3. 读取 ItemTable 之外的账户文件（数据库位于 User/globalStorage 下）
+    if !key_set.file_definitions().is_empty() {
+        if let Some(user_dir) = db_path.parent().and_then(Path::parent) {
+            data_map.extend(user_files::capture(user_dir, key_set.file_definitions()));
         }
     }
 
-    let action = if is_overwrite { "覆盖" } else { "创建" };
-    tracing::info!(target: "backup::database", action = %action, file = %backup_file.display(), "加密备份成功");
-    Ok((backup_name, is_overwrite))
+    Ok(data_map)
 }
 
 /// 读取备份文件（支持加密和明文格式）
 /// 
 /// 自动检测文件格式：
-/// - .enc 文件：使用机器密钥解密（自动支持 V1 SHA-256 和 V2 Argon2）
+/// - .enc 文件：账户数据密钥格式从密钥表取密钥解密；旧版文件使用机器密钥
+///   （自动支持 V1 SHA-256 和 V2 Argon2）或已解锁会话中的主密码密钥解密；
+///   快照清单会从去重存储中展开
 /// - .json 文件：直接读取（兼容旧版本）
 pub fn read_backup_file(backup_path: &Path) -> Result<Value, String> {
     let content = fs::read(backup_path)
@@ -145,13 +196,19 @@ pub fn read_backup_file(backup_path: &Path) -> Result<Value, String> {
     if is_encrypted(&content) {
         tracing::debug!(target: "backup::read", "检测到加密备份文件，正在解密");
         
-        let decrypted = decrypt_machine_data(&content)
-            .map_err(|e| format!("解密失败: {}", e))?;
+        let decrypted = account_keys::decrypt(&content)?;
             
         let json_str = String::from_utf8(decrypted)
             .map_err(|e| format!("UTF-8 解码失败: {}", e))?;
-        serde_json::from_str(&json_str)
-            .map_err(|e| format!("JSON 解析失败: {}", e))
+        let data: Value = serde_json::from_str(&json_str)
+            .map_err(|e| format!("JSON 解析失败: {}", e))?;
+
+        // 快照清单需从去重存储中展开为完整数据
+        if snapshot_store::is_manifest(&data) {
+            snapshot_store::expand_manifest(data)
+        } else {
+            Ok(data)
+        }
     } else {
         // 明文 JSON 文件（兼容旧版本）
         tracing::warn!(target: "backup::read", "检测到明文备份文件，建议重新备份以加密");
@@ -1,19 +1,94 @@
 // Antigravity 用户数据清除模块
-// 负责清除 Antigravity 应用的所有用户认证和设置信息
+// 负责清除 Antigravity 应用的用户认证和设置信息
+//
+// 注销深度：
+// - soft：只删除认证字段（字段定义中属于 auth 分组且注销时删除的字段）
+// - standard：删除字段定义中标记为注销时删除的字段（默认，保留设备指纹、聊天会话与 Agent 状态）
+// - deep：删除全部参与备份的字段（含设备指纹与聊天会话索引）、账户文件以及 Antigravity 的浏览器缓存
 
 use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 // 导入 platform_utils 模块
+use crate::antigravity::db_snapshots::{self, SnapshotReason};
+use crate::antigravity::key_groups::KeyGroup;
+use crate::antigravity::key_sets::{self, KeySet};
+use crate::antigravity::user_files;
 use crate::constants::database;
 use crate::platform;
 
-/// 使用常量定义需要物理删除的字段
-const DELETE_KEYS: &[&str] = database::DELETE_KEYS;
+/// 深度注销时删除的缓存（相对 Antigravity 应用数据目录，即 User 目录的上级）
+const DEEP_CACHE_PATHS: &[&str] = &[
+    "Cache",
+    "CachedData",
+    "Code Cache",
+    "GPUCache",
+    "Local Storage",
+    "Session Storage",
+    "Network",
+    "Cookies",
+    "Cookies-journal",
+];
+
+/// 注销深度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogoutProfile {
+    /// 只删除认证字段
+    Soft,
+    /// 删除注销相关字段（原有行为）
+    #[default]
+    Standard,
+    /// 删除全部参与备份的字段、账户文件与缓存
+    Deep,
+}
+
+impl LogoutProfile {
+    pub fn label(self) -> &'static str {
+        match self {
+            LogoutProfile::Soft => "仅认证",
+            LogoutProfile::Standard => "标准",
+            LogoutProfile::Deep => "深度",
+        }
+    }
+}
+
+/// 注销将删除（或已删除）的内容
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogoutItems {
+    /// state.vscdb 中的字段
+    pub main_keys: Vec<String>,
+    /// state.vscdb.backup 中的字段
+    pub backup_keys: Vec<String>,
+    /// 文件与目录（相对 Antigravity 应用数据目录）
+    pub files: Vec<String>,
+}
+
+/// 注销预览
+#[derive(Debug, Clone, Serialize)]
+pub struct LogoutPlan {
+    pub profile: LogoutProfile,
+    pub items: LogoutItems,
+    /// 是否保留设备指纹（google.antigravity）
+    pub keeps_fingerprint: bool,
+}
+
+/// 注销结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LogoutReport {
+    pub profile: LogoutProfile,
+    /// 实际删除的内容
+    pub removed: LogoutItems,
+    /// 未能删除的内容及原因
+    pub failures: Vec<String>,
+    pub message: String,
+}
 
 /// 智能更新 Marker：彻底移除指定的 Key（而非设为0）
-fn remove_keys_from_marker(conn: &Connection, keys_to_remove: &[&str]) -> Result<(), String> {
+fn remove_keys_from_marker(conn: &Connection, keys_to_remove: &[String]) -> Result<(), String> {
     tracing::debug!(target: "cleanup::marker", "正在修正校验标记 (Marker)");
 
     let current_marker_json: Option<String> = conn
@@ -36,7 +111,7 @@ fn remove_keys_from_marker(conn: &Connection, keys_to_remove: &[&str]) -> Result
     let mut changed = false;
     for key in keys_to_remove {
         // 关键修正：这里必须是 remove，完全从 JSON 中移除该字段，而不是设为 0
-        if marker_obj.remove(*key).is_some() {
+        if marker_obj.remove(key).is_some() {
             changed = true;
         }
     }
@@ -61,33 +136,91 @@ fn remove_keys_from_marker(conn: &Connection, keys_to_remove: &[&str]) -> Result
     Ok(())
 }
 
-fn clear_database(db_path: &Path, db_name: &str) -> Result<usize, String> {
-    tracing::info!(target: "cleanup::database", db_name = %db_name, "开始清理数据库");
+/// 按注销深度确定要删除的字段（只返回数据库中存在的字段）
+fn profile_keys(key_set: &KeySet, existing: &[String], profile: LogoutProfile) -> Vec<String> {
+    let keys = match profile {
+        LogoutProfile::Soft => key_set
+            .logout_keys(existing)
+            .into_iter()
+            .filter(|key| key_set.classify(key).and_then(|def| def.group) == Some(KeyGroup::Auth))
+            .collect(),
+        LogoutProfile::Standard => key_set.logout_keys(existing),
+        LogoutProfile::Deep => key_set
+            .resolve(existing)
+            .into_iter()
+            .filter(|key| key != database::TARGET_STORAGE_MARKER)
+            .collect(),
+    };
+    keys.into_iter().filter(|key| existing.contains(key)).collect()
+}
+
+fn database_keys(db_path: &Path, key_set: &KeySet, profile: LogoutProfile) -> Result<Vec<String>, String> {
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let existing = key_sets::list_db_keys(&conn)?;
+    Ok(profile_keys(key_set, &existing, profile))
+}
+
+/// 深度注销时删除的文件与目录：(展示名称, 路径)，只返回存在的项
+fn profile_files(db_path: &Path, key_set: &KeySet, profile: LogoutProfile) -> Vec<(String, PathBuf)> {
+    if profile != LogoutProfile::Deep {
+        return Vec::new();
+    }
+    // state.vscdb 位于 <应用数据目录>/User/globalStorage 下
+    let Some(user_dir) = db_path.parent().and_then(Path::parent) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for def in key_set.file_definitions() {
+        if let Ok(root) = user_files::normalize_root(&def.path) {
+            files.push((format!("User/{}", root), user_dir.join(&root)));
+        }
+    }
+    if let Some(app_dir) = user_dir.parent() {
+        for cache in DEEP_CACHE_PATHS {
+            files.push((cache.to_string(), app_dir.join(cache)));
+        }
+    }
+    files.retain(|(_, path)| path.exists());
+    files
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn clear_database(db_path: &Path, db_name: &str, profile: LogoutProfile) -> Result<Vec<String>, String> {
+    tracing::info!(target: "cleanup::database", db_name = %db_name, profile = ?profile, "开始清理数据库");
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    // 按字段定义与注销深度确定需要物理删除的字段
+    let existing = key_sets::list_db_keys(&conn)?;
+    let delete_keys = profile_keys(&key_sets::current(), &existing, profile);
 
-    let mut count = 0;
+    let mut removed = Vec::new();
     // 1. 物理删除数据行
-    for key in DELETE_KEYS {
+    for key in &delete_keys {
         let rows = conn
             .execute("DELETE FROM ItemTable WHERE key = ?", [key])
             .unwrap_or(0);
         if rows > 0 {
             tracing::debug!(target: "cleanup::database", key = %key, "已删除字段");
-            count += 1;
+            removed.push(key.clone());
         }
     }
 
     // 2. 同步修改 Marker 清单
-    if let Err(e) = remove_keys_from_marker(&conn, DELETE_KEYS) {
+    if let Err(e) = remove_keys_from_marker(&conn, &delete_keys) {
         tracing::warn!(target: "cleanup::marker", error = %e, "Marker 更新警告");
     }
 
-    Ok(count)
+    Ok(removed)
 }
 
-pub async fn clear_all_antigravity_data() -> Result<String, String> {
-    tracing::info!(target: "cleanup::main", "开始清除 Antigravity 用户认证数据（保留设备指纹）");
-
+fn locate_database() -> Result<PathBuf, String> {
     let app_data = match platform::get_antigravity_db_path() {
         Some(p) => p,
         None => {
@@ -105,34 +238,151 @@ pub async fn clear_all_antigravity_data() -> Result<String, String> {
             app_data.display()
         ));
     }
+    Ok(app_data)
+}
+
+/// 预览指定深度的注销将删除的内容（不修改任何数据）
+pub fn preview_logout(profile: LogoutProfile) -> Result<LogoutPlan, String> {
+    let app_data = locate_database()?;
+    let key_set = key_sets::current();
+    let backup_db = app_data.with_extension("vscdb.backup");
+
+    let items = LogoutItems {
+        main_keys: database_keys(&app_data, &key_set, profile)?,
+        backup_keys: if backup_db.exists() {
+            database_keys(&backup_db, &key_set, profile)?
+        } else {
+            Vec::new()
+        },
+        files: profile_files(&app_data, &key_set, profile)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+    };
+    Ok(LogoutPlan {
+        profile,
+        keeps_fingerprint: !items.main_keys.iter().any(|key| key == database::GOOGLE_DATA),
+        items,
+    })
+}
+
+/// 按指定深度注销 Antigravity 账户
+///
+/// 深度注销会删除文件，必须在 Antigravity 未运行时调用。
+pub async fn clear_antigravity_data(profile: LogoutProfile) -> Result<LogoutReport, String> {
+    tracing::info!(target: "cleanup::main", profile = ?profile, "开始清除 Antigravity 用户认证数据");
+
+    if profile == LogoutProfile::Deep && platform::is_antigravity_running() {
+        return Err("请先关闭 Antigravity 再执行深度注销".to_string());
+    }
+    let app_data = locate_database()?;
+
+    // 清除前保存整库安全快照，误操作时可回滚
+    db_snapshots::take(SnapshotReason::Cleanup)
+        .map_err(|e| format!("创建数据库安全快照失败，已取消清除: {}", e))?;
 
     let mut msg = String::new();
+    let mut removed = LogoutItems::default();
+    let mut failures = Vec::new();
 
     // 清理主库
     tracing::info!(target: "cleanup::main", "步骤1: 清除 state.vscdb 数据库");
-    match clear_database(&app_data, "state.vscdb") {
-        Ok(c) => {
-            tracing::info!(target: "cleanup::main", cleaned_count = %c, "主数据库已清除");
-            msg.push_str(&format!("主库清理 {} 项", c));
-        }
-        Err(e) => return Err(e),
-    }
+    removed.main_keys = clear_database(&app_data, "state.vscdb", profile)?;
+    tracing::info!(target: "cleanup::main", cleaned_count = %removed.main_keys.len(), "主数据库已清除");
+    msg.push_str(&format!("主库清理 {} 项", removed.main_keys.len()));
 
     // 清理备份库
     tracing::info!(target: "cleanup::main", "步骤2: 清除 state.vscdb.backup");
     let backup_db = app_data.with_extension("vscdb.backup");
     if backup_db.exists() {
-        if let Ok(c) = clear_database(&backup_db, "state.vscdb.backup") {
-            tracing::info!(target: "cleanup::main", cleaned_count = %c, "备份数据库已清除");
-            msg.push_str(&format!("; 备份库清理 {} 项", c));
+        match clear_database(&backup_db, "state.vscdb.backup", profile) {
+            Ok(keys) => {
+                tracing::info!(target: "cleanup::main", cleaned_count = %keys.len(), "备份数据库已清除");
+                msg.push_str(&format!("; 备份库清理 {} 项", keys.len()));
+                removed.backup_keys = keys;
+            }
+            Err(e) => {
+                // 主库已清除，不中断；两库不一致可通过一致性检查同步
+                tracing::warn!(target: "cleanup::main", error = %e, "备份数据库清除失败");
+                msg.push_str("; 备份库清理失败，建议执行数据库一致性检查");
+                failures.push(format!("state.vscdb.backup: {}", e));
+            }
         }
     } else {
         tracing::debug!(target: "cleanup::main", "备份数据库不存在，跳过");
     }
 
-    // 添加设备指纹保护说明
-    tracing::info!(target: "cleanup::main", "设备指纹保护: google.antigravity 已保留，避免风控触发");
-    msg.push_str(" (设备指纹已保留)");
+    // 深度注销：删除账户文件与缓存
+    let files = profile_files(&app_data, &key_sets::current(), profile);
+    if !files.is_empty() {
+        tracing::info!(target: "cleanup::main", count = files.len(), "步骤3: 删除账户文件与缓存");
+        for (name, path) in files {
+            match remove_path(&path) {
+                Ok(()) => removed.files.push(name),
+                Err(e) => {
+                    tracing::warn!(target: "cleanup::main", path = %name, error = %e, "删除文件失败");
+                    failures.push(format!("{}: {}", name, e));
+                }
+            }
+        }
+        msg.push_str(&format!("; 文件与缓存清理 {} 项", removed.files.len()));
+    }
+
+    if removed.main_keys.iter().any(|key| key == database::GOOGLE_DATA) {
+        msg.push_str(" (设备指纹已清除)");
+    } else {
+        // 添加设备指纹保护说明
+        tracing::info!(target: "cleanup::main", "设备指纹保护: google.antigravity 已保留，避免风控触发");
+        msg.push_str(" (设备指纹已保留)");
+    }
+
+    Ok(LogoutReport {
+        profile,
+        removed,
+        failures,
+        message: format!("✅ 登出成功（{}）: {}", profile.label(), msg),
+    })
+}
 
-    Ok(format!("✅ 登出成功: {}", msg))
+/// 标准注销（保留设备指纹、聊天会话与 Agent 状态）
+pub async fn clear_all_antigravity_data() -> Result<String, String> {
+    clear_antigravity_data(LogoutProfile::Standard)
+        .await
+        .map(|report| report.message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_keys() {
+        let key_set = KeySet::builtin();
+        let existing: Vec<String> = [
+            database::AUTH_STATUS,
+            database::USER_SETTINGS,
+            database::GOOGLE_DATA,
+            database::CHAT_SESSION,
+            database::TARGET_STORAGE_MARKER,
+            "antigravity.notification.seen",
+            "unrelated.key",
+        ]
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+
+        assert_eq!(
+            profile_keys(&key_set, &existing, LogoutProfile::Soft),
+            vec![database::AUTH_STATUS.to_string()]
+        );
+        assert_eq!(
+            profile_keys(&key_set, &existing, LogoutProfile::Standard),
+            vec![database::AUTH_STATUS.to_string(), database::USER_SETTINGS.to_string()]
+        );
+        let deep = profile_keys(&key_set, &existing, LogoutProfile::Deep);
+        for key in [database::GOOGLE_DATA, database::CHAT_SESSION, "antigravity.notification.seen"] {
+            assert!(deep.iter().any(|k| k == key), "{} 应被深度注销删除", key);
+        }
+        assert!(!deep.iter().any(|k| k == database::TARGET_STORAGE_MARKER || k == "unrelated.key"));
+    }
 }
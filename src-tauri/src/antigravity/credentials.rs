@@ -0,0 +1,172 @@
+// Antigravity 凭据模块
+// 访问令牌只在 Rust 侧使用：从备份中按账户句柄取出令牌，
+// 返回给前端的认证信息一律去除令牌字段
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::antigravity::account_index;
+use crate::antigravity::backup::read_backup_file;
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+use crate::utils::secret::SecretString;
+
+/// 认证信息中承载令牌的字段
+const TOKEN_FIELDS: &[&str] = &["apiKey", "accessToken", "refreshToken", "idToken"];
+
+/// 认证信息中显式记录过期时间的字段
+const EXPIRY_FIELDS: &[&str] = &[
+    "expiresAt",
+    "expires_at",
+    "expiry",
+    "expiryDate",
+    "expiry_date",
+    "expirationTime",
+    "tokenExpiry",
+];
+
+/// 距过期不足该时长（秒）视为即将过期
+pub const EXPIRING_SOON_SECS: i64 = 24 * 3600;
+
+/// 切换到令牌已过期账户时返回的错误前缀（前端据此提示并确认）
+pub const TOKEN_EXPIRED_ERROR_PREFIX: &str = "令牌已过期";
+
+/// 访问令牌状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStatus {
+    Fresh,
+    ExpiringSoon,
+    Expired,
+    /// 无法确定过期时间（如非 JWT 令牌且无显式过期字段）
+    Unknown,
+}
+
+/// 从认证信息（antigravityAuthStatus）中提取访问令牌
+pub fn extract_token(auth: &Value) -> Option<SecretString> {
+    auth.get("apiKey")
+        .or_else(|| auth.get("accessToken"))
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(SecretString::from)
+}
+
+/// 令牌指纹（SHA-256 前 16 字节），用于比较会话而不保存令牌本身
+pub fn token_fingerprint(token: &str) -> String {
+    hex::encode(&Sha256::digest(token.as_bytes())[..16])
+}
+
+/// 访问令牌与刷新令牌的指纹
+pub fn session_fingerprints(auth: &Value) -> (Option<String>, Option<String>) {
+    let access = extract_token(auth).map(|t| token_fingerprint(t.expose_secret()));
+    let refresh = auth
+        .get("refreshToken")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .map(token_fingerprint);
+    (access, refresh)
+}
+
+/// 解析时间值：Unix 秒/毫秒（数字或数字字符串）或 RFC3339 字符串
+fn parse_time_value(value: &Value) -> Option<i64> {
+    let from_number = |n: i64| if n > 1_000_000_000_000 { n / 1000 } else { n };
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)).map(from_number),
+        Value::String(s) => s
+            .parse::<i64>()
+            .ok()
+            .map(from_number)
+            .or_else(|| chrono::DateTime::parse_from_rfc3339(s).ok().map(|t| t.timestamp())),
+        _ => None,
+    }
+}
+
+/// 读取 JWT 载荷中的 exp（不校验签名，仅用于提示）
+fn jwt_expiry(token: &str) -> Option<i64> {
+    let mut parts = token.split('.');
+    let (_, payload, _) = (parts.next()?, parts.next()?, parts.next()?);
+    let decoded = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+    let claims: Value = serde_json::from_slice(&decoded).ok()?;
+    claims.get("exp").and_then(parse_time_value)
+}
+
+/// 提取访问令牌的过期时间（Unix 秒）
+///
+/// 优先使用认证信息中的显式过期字段，其次读取 JWT 令牌的 exp。
+pub fn token_expiry(auth: &Value) -> Option<i64> {
+    EXPIRY_FIELDS
+        .iter()
+        .find_map(|field| auth.get(*field).and_then(parse_time_value))
+        .or_else(|| {
+            TOKEN_FIELDS
+                .iter()
+                .filter_map(|field| auth.get(*field).and_then(|v| v.as_str()))
+                .find_map(jwt_expiry)
+        })
+}
+
+/// 按过期时间判断令牌状态
+pub fn token_status(expires_at: Option<i64>, now: i64) -> TokenStatus {
+    match expires_at {
+        None => TokenStatus::Unknown,
+        Some(exp) if exp <= now => TokenStatus::Expired,
+        Some(exp) if exp - now <= EXPIRING_SOON_SECS => TokenStatus::ExpiringSoon,
+        Some(_) => TokenStatus::Fresh,
+    }
+}
+
+/// 去除认证信息中的令牌字段，以 hasApiKey 标记是否存在令牌
+pub fn redact_auth_status(mut auth: Value) -> Value {
+    let has_token = extract_token(&auth).is_some();
+    if let Some(obj) = auth.as_object_mut() {
+        for field in TOKEN_FIELDS {
+            obj.remove(*field);
+        }
+        obj.insert("hasApiKey".to_string(), Value::Bool(has_token));
+    }
+    auth
+}
+
+/// 按账户句柄（随机账户 ID）从备份中读取访问令牌
+pub fn account_token(handle: &str) -> Result<SecretString, String> {
+    let dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    let index = account_index::load_or_rebuild()?;
+    let summary = index.get(handle).ok_or("账户不存在或已被删除")?;
+
+    let backup = read_backup_file(&account_index::backup_file_path(&dir, &summary.id))?;
+    let auth: Value = backup
+        .get(database::AUTH_STATUS)
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str(s).ok())
+        .ok_or("备份中没有认证信息")?;
+
+    extract_token(&auth).ok_or_else(|| "备份中没有访问令牌".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_expiry_from_jwt_and_fields() {
+        let payload = URL_SAFE_NO_PAD.encode(br#"{"sub":"x","exp":1700000000}"#);
+        let jwt = format!("eyJhbGciOiJSUzI1NiJ9.{}.sig", payload);
+        assert_eq!(token_expiry(&serde_json::json!({ "apiKey": jwt })), Some(1_700_000_000));
+
+        // 显式字段优先，毫秒时间戳自动换算
+        let auth = serde_json::json!({ "apiKey": "ya29.opaque", "expiresAt": 1_800_000_000_000i64 });
+        assert_eq!(token_expiry(&auth), Some(1_800_000_000));
+
+        assert_eq!(token_expiry(&serde_json::json!({ "apiKey": "ya29.opaque" })), None);
+    }
+
+    #[test]
+    fn test_token_status_thresholds() {
+        assert_eq!(token_status(None, 100), TokenStatus::Unknown);
+        assert_eq!(token_status(Some(100), 100), TokenStatus::Expired);
+        assert_eq!(token_status(Some(100 + EXPIRING_SOON_SECS), 100), TokenStatus::ExpiringSoon);
+        assert_eq!(token_status(Some(200 + EXPIRING_SOON_SECS), 100), TokenStatus::Fresh);
+    }
+}
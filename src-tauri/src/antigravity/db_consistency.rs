@@ -0,0 +1,280 @@
+// Antigravity 主库与备份库一致性检查模块
+// 恢复与清除会同时写入 state.vscdb 与 state.vscdb.backup，但备份库的写入失败不会中断操作。
+// 此模块比较两库中参与备份的字段与 __$__targetStorageMarker，报告差异，
+// 并可按指定方向同步（只覆盖参与备份的字段与 Marker，其余字段保持不变）。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::antigravity::db_health::DbFile;
+use crate::antigravity::db_snapshots::{self, SnapshotReason};
+use crate::antigravity::key_sets::{self, KeySet};
+use crate::antigravity::switch_preview;
+use crate::constants::database;
+
+/// 同步方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    /// 以主库为准覆盖备份库
+    MainToBackup,
+    /// 以备份库为准覆盖主库
+    BackupToMain,
+}
+
+impl SyncDirection {
+    fn files(self) -> (DbFile, DbFile) {
+        match self {
+            SyncDirection::MainToBackup => (DbFile::Main, DbFile::Backup),
+            SyncDirection::BackupToMain => (DbFile::Backup, DbFile::Main),
+        }
+    }
+}
+
+/// 字段差异类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// 只存在于备份库
+    MissingInMain,
+    /// 只存在于主库
+    MissingInBackup,
+    /// 两库的值不同
+    Different,
+}
+
+/// 单个字段的差异
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyDrift {
+    pub key: String,
+    pub kind: DriftKind,
+    /// 主库中的值（已脱敏）
+    pub main: Option<String>,
+    /// 备份库中的值（已脱敏）
+    pub backup: Option<String>,
+    /// 两侧均为 JSON 对象时不同的子字段（Marker 即为不同的同步标记）
+    pub changed_fields: Vec<String>,
+}
+
+/// 一致性检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsistencyReport {
+    pub backup_exists: bool,
+    pub consistent: bool,
+    /// 比较的字段数量（含 Marker）
+    pub checked_keys: usize,
+    pub drifts: Vec<KeyDrift>,
+    pub summary: String,
+}
+
+/// 同步结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncResult {
+    pub direction: SyncDirection,
+    /// 写入的字段数量
+    pub written: usize,
+    /// 删除的字段数量（源库中不存在）
+    pub removed: usize,
+}
+
+/// 参与比较的字段：两库中参与备份的字段并集，Marker 在最后
+fn tracked_keys(main: &Connection, backup: &Connection, key_set: &KeySet) -> Result<Vec<String>, String> {
+    let mut existing = key_sets::list_db_keys(main)?;
+    existing.extend(key_sets::list_db_keys(backup)?);
+    existing.sort();
+    existing.dedup();
+
+    let mut keys: Vec<String> = key_set
+        .resolve(&existing)
+        .into_iter()
+        .filter(|key| key != database::TARGET_STORAGE_MARKER)
+        .collect();
+    keys.push(database::TARGET_STORAGE_MARKER.to_string());
+    Ok(keys)
+}
+
+fn read_values(conn: &Connection, keys: &[String]) -> Result<BTreeMap<String, String>, String> {
+    let mut values = BTreeMap::new();
+    for key in keys {
+        let value: Option<String> = conn
+            .query_row("SELECT value FROM ItemTable WHERE key = ?", [key], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("读取字段 {} 失败: {}", key, e))?;
+        if let Some(value) = value {
+            values.insert(key.clone(), value);
+        }
+    }
+    Ok(values)
+}
+
+fn compare_conns(main: &Connection, backup: &Connection, key_set: &KeySet) -> Result<ConsistencyReport, String> {
+    let keys = tracked_keys(main, backup, key_set)?;
+    let main_values = read_values(main, &keys)?;
+    let backup_values = read_values(backup, &keys)?;
+
+    let mut drifts = Vec::new();
+    for key in &keys {
+        let main_value = main_values.get(key).map(String::as_str);
+        let backup_value = backup_values.get(key).map(String::as_str);
+        let (kind, changed_fields) = match (main_value, backup_value) {
+            (None, Some(_)) => (DriftKind::MissingInMain, Vec::new()),
+            (Some(_), None) => (DriftKind::MissingInBackup, Vec::new()),
+            (Some(a), Some(b)) if a != b && !switch_preview::same_json(a, b) => {
+                (DriftKind::Different, switch_preview::changed_object_fields(a, b))
+            }
+            _ => continue,
+        };
+        drifts.push(KeyDrift {
+            key: key.clone(),
+            kind,
+            main: main_value.map(|v| switch_preview::redacted_preview(key, v)),
+            backup: backup_value.map(|v| switch_preview::redacted_preview(key, v)),
+            changed_fields,
+        });
+    }
+
+    let summary = if drifts.is_empty() {
+        format!("主库与备份库一致（比较 {} 个字段）", keys.len())
+    } else {
+        format!("主库与备份库有 {} 个字段不一致（比较 {} 个字段）", drifts.len(), keys.len())
+    };
+    Ok(ConsistencyReport {
+        backup_exists: true,
+        consistent: drifts.is_empty(),
+        checked_keys: keys.len(),
+        drifts,
+        summary,
+    })
+}
+
+/// 将源库中参与比较的字段写入目标库，目标库中多出的字段删除
+fn sync_conns(source: &Connection, target: &mut Connection, key_set: &KeySet) -> Result<(usize, usize), String> {
+    let keys = tracked_keys(source, target, key_set)?;
+    let source_values = read_values(source, &keys)?;
+
+    let tx = target.transaction().map_err(|e| format!("开启事务失败: {}", e))?;
+    let mut written = 0;
+    let mut removed = 0;
+    for key in &keys {
+        match source_values.get(key) {
+            Some(value) => {
+                tx.execute("INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)", params![key, value])
+                    .map_err(|e| format!("写入字段 {} 失败: {}", key, e))?;
+                written += 1;
+            }
+            None => {
+                removed += tx
+                    .execute("DELETE FROM ItemTable WHERE key = ?", [key])
+                    .map_err(|e| format!("删除字段 {} 失败: {}", key, e))?;
+            }
+        }
+    }
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+    Ok((written, removed))
+}
+
+fn open(file: DbFile) -> Result<Connection, String> {
+    Connection::open(file.path()?).map_err(|e| format!("连接数据库 {} 失败: {}", file.file_name(), e))
+}
+
+/// 比较主库与备份库
+pub fn check() -> Result<ConsistencyReport, String> {
+    if !DbFile::Main.path()?.exists() {
+        return Err("Antigravity 数据库不存在".to_string());
+    }
+    if !DbFile::Backup.path()?.exists() {
+        return Ok(ConsistencyReport {
+            backup_exists: false,
+            consistent: true,
+            checked_keys: 0,
+            drifts: Vec::new(),
+            summary: "备份数据库不存在，无需比较".to_string(),
+        });
+    }
+
+    let report = compare_conns(&open(DbFile::Main)?, &open(DbFile::Backup)?, &key_sets::current())?;
+    tracing::info!(
+        target: "db::consistency",
+        checked = report.checked_keys,
+        drifts = report.drifts.len(),
+        "主库与备份库一致性检查完成"
+    );
+    Ok(report)
+}
+
+/// 按指定方向同步主库与备份库
+///
+/// 必须在 Antigravity 未运行时调用，同步前会先创建数据库安全快照。
+pub fn sync(direction: SyncDirection) -> Result<SyncResult, String> {
+    if crate::platform::is_antigravity_running() {
+        return Err("请先关闭 Antigravity 再同步数据库".to_string());
+    }
+    let (source_file, target_file) = direction.files();
+    for file in [source_file, target_file] {
+        if !file.path()?.exists() {
+            return Err(format!("数据库不存在: {}", file.file_name()));
+        }
+    }
+
+    db_snapshots::take(SnapshotReason::Sync).map_err(|e| format!("创建数据库安全快照失败，已取消同步: {}", e))?;
+
+    let source = open(source_file)?;
+    let mut target = open(target_file)?;
+    let (written, removed) = sync_conns(&source, &mut target, &key_sets::current())?;
+
+    tracing::info!(
+        target: "db::consistency",
+        direction = ?direction,
+        written,
+        removed,
+        "主库与备份库已同步"
+    );
+    Ok(SyncResult { direction, written, removed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(rows: &[(&str, &str)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)")
+            .unwrap();
+        for (key, value) in rows {
+            conn.execute("INSERT INTO ItemTable VALUES (?, ?)", [key, value]).unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn test_detects_drift_and_syncs() {
+        let key_set = KeySet::builtin();
+        let main = db(&[
+            (database::AUTH_STATUS, r#"{"email":"a@example.com"}"#),
+            (database::USER_SETTINGS, r#"{"a":1,"b":2}"#),
+            (database::TARGET_STORAGE_MARKER, r#"{"antigravityAuthStatus":0,"x":1}"#),
+            ("unrelated.key", "1"),
+        ]);
+        let mut backup = db(&[
+            (database::USER_SETTINGS, r#"{"b":2,"a":1}"#),
+            (database::ONBOARDING, "true"),
+            (database::TARGET_STORAGE_MARKER, r#"{"antigravityAuthStatus":1,"x":1}"#),
+        ]);
+
+        let report = compare_conns(&main, &backup, &key_set).unwrap();
+        let drifts: Vec<(&str, DriftKind)> = report.drifts.iter().map(|d| (d.key.as_str(), d.kind)).collect();
+        assert!(!report.consistent);
+        assert!(drifts.contains(&(database::AUTH_STATUS, DriftKind::MissingInBackup)));
+        assert!(drifts.contains(&(database::ONBOARDING, DriftKind::MissingInMain)));
+        assert!(!drifts.iter().any(|(key, _)| *key == database::USER_SETTINGS));
+        let marker = report.drifts.last().unwrap();
+        assert_eq!(marker.key, database::TARGET_STORAGE_MARKER);
+        assert_eq!(marker.changed_fields, vec!["antigravityAuthStatus".to_string()]);
+
+        let (_, removed) = sync_conns(&main, &mut backup, &key_set).unwrap();
+        assert_eq!(removed, 1);
+        assert!(compare_conns(&main, &backup, &key_set).unwrap().consistent);
+        assert!(key_sets::list_db_keys(&backup).unwrap().iter().all(|key| key != "unrelated.key"));
+    }
+}
@@ -0,0 +1,326 @@
+// Antigravity 数据库健康检查模块
+// 检查 state.vscdb 与 state.vscdb.backup 的完整性与日志模式，
+// 在 Antigravity 未运行时整理（VACUUM）数据库，并列出 ItemTable 中的全部字段
+//
+// 字段值只以脱敏形式返回：先按切换预览的规则遮盖令牌与不透明长串，再经 LogSanitizer 处理邮箱、路径与密钥。
+
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::antigravity::db_snapshots::{self, SnapshotReason};
+use crate::antigravity::switch_preview;
+use crate::path_utils::AppPaths;
+use crate::utils::log_sanitizer::LogSanitizer;
+
+/// integrity_check 最多返回的问题条数
+const INTEGRITY_MAX_ERRORS: u32 = 100;
+
+/// 查看字段值时的最大长度
+const VALUE_MAX_CHARS: usize = 64 * 1024;
+
+/// 要检查的数据库文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DbFile {
+    /// state.vscdb
+    Main,
+    /// state.vscdb.backup
+    Backup,
+}
+
+impl DbFile {
+    pub const ALL: [DbFile; 2] = [DbFile::Main, DbFile::Backup];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            DbFile::Main => "state.vscdb",
+            DbFile::Backup => "state.vscdb.backup",
+        }
+    }
+
+    pub(crate) fn path(self) -> Result<PathBuf, String> {
+        AppPaths::antigravity_data_dir()
+            .map(|dir| dir.join(self.file_name()))
+            .ok_or_else(|| "未找到数据库路径".to_string())
+    }
+
+    /// 只读打开数据库（不存在时报错，不会创建新文件）
+    fn open_read_only(self) -> Result<Connection, String> {
+        let path = self.path()?;
+        if !path.exists() {
+            return Err(format!("数据库不存在: {}", self.file_name()));
+        }
+        Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+            .map_err(|e| format!("连接数据库 {} 失败: {}", self.file_name(), e))
+    }
+}
+
+/// 单个数据库的健康状态
+#[derive(Debug, Clone, Serialize)]
+pub struct DbHealth {
+    pub file: DbFile,
+    pub exists: bool,
+    /// 文件大小（字节）
+    pub size: u64,
+    /// 日志模式（delete / wal 等）
+    pub journal_mode: Option<String>,
+    pub page_count: Option<i64>,
+    /// 空闲页数量，较多时可通过 VACUUM 回收空间
+    pub freelist_count: Option<i64>,
+    /// integrity_check 是否返回 ok
+    pub integrity_ok: bool,
+    /// integrity_check 报告的问题（或检查本身的错误）
+    pub problems: Vec<String>,
+}
+
+/// 单个数据库的整理结果
+#[derive(Debug, Clone, Serialize)]
+pub struct VacuumResult {
+    pub file: DbFile,
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/// 字段值的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueKind {
+    Null,
+    JsonObject,
+    JsonArray,
+    /// JSON 字符串、数字或布尔值
+    JsonScalar,
+    Text,
+    Blob,
+}
+
+/// ItemTable 中的单个字段
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyEntry {
+    pub key: String,
+    /// 值的大小（字节）
+    pub size: u64,
+    pub kind: ValueKind,
+}
+
+fn file_size(path: &PathBuf) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+fn pragma_value<T: rusqlite::types::FromSql>(conn: &Connection, pragma: &str) -> Option<T> {
+    conn.query_row(&format!("PRAGMA {}", pragma), [], |row| row.get(0)).ok()
+}
+
+fn check_file(file: DbFile) -> Result<DbHealth, String> {
+    let path = file.path()?;
+    let mut health = DbHealth {
+        file,
+        exists: path.exists(),
+        size: file_size(&path),
+        journal_mode: None,
+        page_count: None,
+        freelist_count: None,
+        integrity_ok: false,
+        problems: Vec::new(),
+    };
+    if !health.exists {
+        return Ok(health);
+    }
+
+    let conn = match file.open_read_only() {
+        Ok(conn) => conn,
+        Err(e) => {
+            health.problems.push(e);
+            return Ok(health);
+        }
+    };
+    health.journal_mode = pragma_value(&conn, "journal_mode");
+    health.page_count = pragma_value(&conn, "page_count");
+    health.freelist_count = pragma_value(&conn, "freelist_count");
+
+    let result = conn
+        .prepare(&format!("PRAGMA integrity_check({})", INTEGRITY_MAX_ERRORS))
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        });
+    match result {
+        Ok(rows) if rows.len() == 1 && rows[0] == "ok" => health.integrity_ok = true,
+        Ok(rows) => health.problems = rows,
+        Err(e) => health.problems.push(format!("完整性检查失败: {}", e)),
+    }
+
+    tracing::info!(
+        target: "db::health",
+        file = file.file_name(),
+        integrity_ok = health.integrity_ok,
+        journal_mode = ?health.journal_mode,
+        "数据库健康检查完成"
+    );
+    Ok(health)
+}
+
+/// 检查主库与备份库的完整性与日志模式
+pub fn check() -> Result<Vec<DbHealth>, String> {
+    DbFile::ALL.into_iter().map(check_file).collect()
+}
+
+/// 整理主库与备份库
+///
+/// 必须在 Antigravity 未运行时调用，整理前会先创建数据库安全快照。
+pub fn vacuum() -> Result<Vec<VacuumResult>, String> {
+    if crate::platform::is_antigravity_running() {
+        return Err("请先关闭 Antigravity 再整理数据库".to_string());
+    }
+    db_snapshots::take(SnapshotReason::Vacuum)
+        .map_err(|e| format!("创建数据库安全快照失败，已取消整理: {}", e))?;
+
+    let mut results = Vec::new();
+    for file in DbFile::ALL {
+        let path = file.path()?;
+        if !path.exists() {
+            continue;
+        }
+        let size_before = file_size(&path);
+        let conn = Connection::open(&path).map_err(|e| format!("连接数据库 {} 失败: {}", file.file_name(), e))?;
+        conn.execute_batch("VACUUM")
+            .map_err(|e| format!("整理数据库 {} 失败: {}", file.file_name(), e))?;
+        drop(conn);
+
+        let size_after = file_size(&path);
+        tracing::info!(target: "db::health", file = file.file_name(), size_before, size_after, "数据库整理完成");
+        results.push(VacuumResult { file, size_before, size_after });
+    }
+    Ok(results)
+}
+
+fn value_kind(value: ValueRef<'_>) -> ValueKind {
+    let text = match value {
+        ValueRef::Null => return ValueKind::Null,
+        ValueRef::Integer(_) | ValueRef::Real(_) => return ValueKind::JsonScalar,
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(_) => return ValueKind::Blob,
+        },
+    };
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(serde_json::Value::Object(_)) => ValueKind::JsonObject,
+        Ok(serde_json::Value::Array(_)) => ValueKind::JsonArray,
+        Ok(serde_json::Value::Null) => ValueKind::Null,
+        Ok(_) => ValueKind::JsonScalar,
+        Err(_) => ValueKind::Text,
+    }
+}
+
+fn value_size(value: ValueRef<'_>) -> u64 {
+    match value {
+        ValueRef::Null => 0,
+        ValueRef::Integer(_) | ValueRef::Real(_) => 8,
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes.len() as u64,
+    }
+}
+
+fn list_keys_in(conn: &Connection) -> Result<Vec<KeyEntry>, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM ItemTable ORDER BY key")
+        .map_err(|e| format!("读取字段列表失败: {}", e))?;
+    let entries = stmt
+        .query_map([], |row| {
+            let value = row.get_ref(1)?;
+            Ok(KeyEntry {
+                key: row.get(0)?,
+                size: value_size(value),
+                kind: value_kind(value),
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("读取字段列表失败: {}", e))?;
+    Ok(entries)
+}
+
+/// 列出数据库 ItemTable 中的全部字段及其大小与类型
+pub fn list_keys(file: DbFile) -> Result<Vec<KeyEntry>, String> {
+    list_keys_in(&file.open_read_only()?)
+}
+
+fn read_value_in(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    let raw: Option<String> = conn
+        .query_row("SELECT value FROM ItemTable WHERE key = ?", [key], |row| {
+            Ok(match row.get_ref(0)? {
+                ValueRef::Null => "null".to_string(),
+                ValueRef::Integer(i) => i.to_string(),
+                ValueRef::Real(f) => f.to_string(),
+                ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                ValueRef::Blob(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => format!("[BLOB:{} bytes]", bytes.len()),
+                },
+            })
+        })
+        .optional()
+        .map_err(|e| format!("读取字段失败: {}", e))?;
+
+    Ok(raw.map(|raw| {
+        let text = LogSanitizer::new().sanitize(&switch_preview::redact_value(key, &raw));
+        if text.chars().count() > VALUE_MAX_CHARS {
+            let truncated: String = text.chars().take(VALUE_MAX_CHARS).collect();
+            format!("{}…", truncated)
+        } else {
+            text
+        }
+    }))
+}
+
+/// 读取单个字段的脱敏值
+pub fn read_value(file: DbFile, key: &str) -> Result<String, String> {
+    read_value_in(&file.open_read_only()?, key)?.ok_or_else(|| format!("字段不存在: {}", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB);
+             INSERT INTO ItemTable VALUES ('b.settings', '{\"theme\":\"dark\",\"owner\":\"someone@example.com\"}');
+             INSERT INTO ItemTable VALUES ('a.list', '[1,2]');
+             INSERT INTO ItemTable VALUES ('c.flag', 'true');
+             INSERT INTO ItemTable VALUES ('d.text', 'plain words');
+             INSERT INTO ItemTable VALUES ('e.blob', x'fffe');",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_list_keys_sizes_and_kinds() {
+        let entries = list_keys_in(&sample_db()).unwrap();
+        let summary: Vec<(&str, u64, ValueKind)> =
+            entries.iter().map(|e| (e.key.as_str(), e.size, e.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a.list", 5, ValueKind::JsonArray),
+                ("b.settings", 46, ValueKind::JsonObject),
+                ("c.flag", 4, ValueKind::JsonScalar),
+                ("d.text", 11, ValueKind::Text),
+                ("e.blob", 2, ValueKind::Blob),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_value_redacted() {
+        let conn = sample_db();
+        let value = read_value_in(&conn, "b.settings").unwrap().unwrap();
+        assert!(value.contains("dark"));
+        assert!(!value.contains("someone@example.com"));
+        assert_eq!(read_value_in(&conn, "e.blob").unwrap().unwrap(), "[BLOB:2 bytes]");
+        assert!(read_value_in(&conn, "missing").unwrap().is_none());
+    }
+}
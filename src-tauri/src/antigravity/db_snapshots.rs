@@ -0,0 +1,429 @@
+// Antigravity 数据库安全快照模块
+// 恢复、注销等会直接修改 state.vscdb 与 state.vscdb.backup 的操作执行前，
+// 先用 SQLite 在线备份 API 保存整库的一致性快照，出错时可整库回滚
+//
+// 快照保存在备份目录的 db_snapshots/<快照ID>/ 下：
+//   meta.enc              - 快照信息
+//   state.vscdb.enc       - 主库
+//   state.vscdb.backup.enc - 备份库（存在时）
+// 所有文件按当前保险库模式加密（数据库中含有明文令牌），超出保留数量时删除最旧的快照。
+
+use rusqlite::{Connection, DatabaseName};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::antigravity::account_index;
+use crate::antigravity::switch_undo::PurgeReport;
+use crate::antigravity::vault::vault;
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{random_bytes, secure_create_dir, secure_delete_file, secure_write_file};
+
+/// 快照目录名（位于备份目录中）
+const SNAPSHOT_DIR_NAME: &str = "db_snapshots";
+
+/// 快照信息文件名
+const META_FILE: &str = "meta.enc";
+
+/// 最多保留的快照数量（整库快照体积较大）
+pub const MAX_DB_SNAPSHOTS: usize = 10;
+
+/// 快照中保存的数据库文件
+const DB_FILES: &[&str] = &["state.vscdb", "state.vscdb.backup"];
+
+/// 串行化快照的创建、回滚与清理
+static SNAPSHOT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 创建快照的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotReason {
+    /// 恢复账户数据前
+    Restore,
+    /// 清除登录数据前
+    Cleanup,
+    /// 回滚到其他快照前
+    Rollback,
+    /// 整理（VACUUM）数据库前
+    Vacuum,
+    /// 同步主库与备份库前
+    Sync,
+    /// 手动创建
+    Manual,
+}
+
+/// 快照中的数据库文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbFileInfo {
+    pub name: String,
+    pub size: u64,
+}
+
+/// 快照信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DbSnapshotInfo {
+    pub id: String,
+    pub created_at: String,
+    pub reason: SnapshotReason,
+    /// 快照时登录的账户
+    pub email: Option<String>,
+    pub databases: Vec<DbFileInfo>,
+}
+
+fn snapshots_root() -> Result<PathBuf, String> {
+    AppPaths::backup_dir()
+        .map(|dir| dir.join(SNAPSHOT_DIR_NAME))
+        .ok_or_else(|| "无法获取备份目录".to_string())
+}
+
+fn data_dir() -> Result<PathBuf, String> {
+    AppPaths::antigravity_data_dir().ok_or_else(|| "未找到数据库路径".to_string())
+}
+
+/// 校验快照 ID（只允许字母、数字与连字符，防止路径穿越）
+fn snapshot_dir(id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("快照 ID 无效: {}", id));
+    }
+    let dir = snapshots_root()?.join(id);
+    if !dir.is_dir() {
+        return Err(format!("快照不存在: {}", id));
+    }
+    Ok(dir)
+}
+
+/// 快照目录（按创建时间升序，ID 以时间开头，可直接按名称排序）
+fn snapshot_dirs() -> Result<Vec<PathBuf>, String> {
+    let root = snapshots_root()?;
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let mut dirs: Vec<PathBuf> = fs::read_dir(&root)
+        .map_err(|e| format!("读取快照目录失败: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn read_meta(dir: &Path) -> Result<DbSnapshotInfo, String> {
+    let content = fs::read(dir.join(META_FILE)).map_err(|e| format!("读取快照信息失败: {}", e))?;
+    let decrypted = Zeroizing::new(vault().decrypt(&content)?);
+    serde_json::from_slice(&decrypted).map_err(|e| format!("快照信息解析失败: {}", e))
+}
+
+/// 快照时登录的账户
+fn current_email(db_path: &Path) -> Option<String> {
+    let conn = Connection::open(db_path).ok()?;
+    let auth: String = conn
+        .query_row("SELECT value FROM ItemTable WHERE key = ?", [database::AUTH_STATUS], |row| row.get(0))
+        .ok()?;
+    serde_json::from_str::<Value>(&auth)
+        .ok()?
+        .get("email")
+        .and_then(|v| v.as_str())
+        .map(account_index::normalize_email)
+}
+
+/// 删除目录及其中的文件（文件先安全擦除）
+fn remove_snapshot_dir(dir: &Path) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        secure_delete_file(&entry.path()).map_err(|e| e.to_string())?;
+    }
+    fs::remove_dir(dir).map_err(|e| format!("删除快照失败: {}", e))
+}
+
+/// 使用在线备份 API 复制数据库并加密保存
+fn snapshot_database(source: &Path, dir: &Path, name: &str) -> Result<u64, String> {
+    let tmp = dir.join(format!("{}.tmp", name));
+    let result = (|| {
+        let conn = Connection::open(source).map_err(|e| format!("连接数据库失败: {}", e))?;
+        conn.backup(DatabaseName::Main, &tmp, None)
+            .map_err(|e| format!("备份数据库 {} 失败: {}", name, e))?;
+        let content = Zeroizing::new(fs::read(&tmp).map_err(|e| e.to_string())?);
+        let encrypted = vault().encrypt(&content)?;
+        secure_write_file(&dir.join(format!("{}.enc", name)), &encrypted).map_err(|e| e.to_string())?;
+        Ok(content.len() as u64)
+    })();
+    let _ = secure_delete_file(&tmp);
+    result
+}
+
+/// 为当前数据库创建安全快照
+///
+/// 数据库不存在时返回 `Ok(None)`。调用方应在快照失败时取消后续的破坏性操作。
+pub fn take(reason: SnapshotReason) -> Result<Option<DbSnapshotInfo>, String> {
+    vault().ensure_unlocked()?;
+    let data_dir = data_dir()?;
+    let main_db = data_dir.join(DB_FILES[0]);
+    if !main_db.exists() {
+        return Ok(None);
+    }
+
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let now = chrono::Local::now();
+    let id = format!("{}-{}", now.format("%Y%m%dT%H%M%S%3f"), hex::encode(random_bytes::<2>()));
+    let dir = snapshots_root()?.join(&id);
+    secure_create_dir(&dir).map_err(|e| e.to_string())?;
+
+    let mut databases = Vec::new();
+    for name in DB_FILES {
+        let source = data_dir.join(name);
+        if !source.exists() {
+            continue;
+        }
+        match snapshot_database(&source, &dir, name) {
+            Ok(size) => databases.push(DbFileInfo { name: name.to_string(), size }),
+            Err(e) => {
+                let _ = remove_snapshot_dir(&dir);
+                return Err(e);
+            }
+        }
+    }
+
+    let info = DbSnapshotInfo {
+        id,
+        created_at: now.to_rfc3339(),
+        reason,
+        email: current_email(&main_db),
+        databases,
+    };
+    let json = serde_json::to_vec(&info).map_err(|e| e.to_string())?;
+    secure_write_file(&dir.join(META_FILE), &vault().encrypt(&json)?).map_err(|e| e.to_string())?;
+
+    // 超出保留数量时删除最旧的快照
+    let dirs = snapshot_dirs()?;
+    if dirs.len() > MAX_DB_SNAPSHOTS {
+        for old in &dirs[..dirs.len() - MAX_DB_SNAPSHOTS] {
+            if let Err(e) = remove_snapshot_dir(old) {
+                tracing::warn!(target: "db::snapshot", error = %e, "删除旧快照失败");
+            }
+        }
+    }
+
+    tracing::info!(target: "db::snapshot", id = %info.id, reason = ?reason, "已创建数据库安全快照");
+    Ok(Some(info))
+}
+
+/// 列出数据库快照（最新的在前）
+pub fn list() -> Result<Vec<DbSnapshotInfo>, String> {
+    vault().ensure_unlocked()?;
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let mut infos = Vec::new();
+    for dir in snapshot_dirs()?.iter().rev() {
+        match read_meta(dir) {
+            Ok(info) => infos.push(info),
+            Err(e) => tracing::warn!(target: "db::snapshot", error = %e, "快照信息无法读取，跳过"),
+        }
+    }
+    Ok(infos)
+}
+
+/// 将整个数据库回滚到指定快照
+///
+/// 必须在 Antigravity 未运行时调用。回滚前会为当前状态再创建一个快照，回滚本身也可撤销。
+pub fn rollback(id: &str) -> Result<DbSnapshotInfo, String> {
+    if crate::platform::is_antigravity_running() {
+        return Err("请先关闭 Antigravity 再回滚数据库".to_string());
+    }
+    vault().ensure_unlocked()?;
+    let dir = snapshot_dir(id)?;
+    let info = read_meta(&dir)?;
+
+    // 先解密到快照目录外的临时文件：回滚前的新快照可能使该快照因超出保留数量被删除
+    let root = snapshots_root()?;
+    let mut staged = Vec::new();
+    let staged_result = info.databases.iter().try_for_each(|db| {
+        if !DB_FILES.contains(&db.name.as_str()) {
+            return Err(format!("快照中的数据库名无效: {}", db.name));
+        }
+        let content = fs::read(dir.join(format!("{}.enc", db.name)))
+            .map_err(|e| format!("读取快照 {} 失败: {}", db.name, e))?;
+        let decrypted = Zeroizing::new(vault().decrypt(&content)?);
+        let tmp = root.join(format!("rollback-{}-{}.tmp", id, db.name));
+        secure_write_file(&tmp, &decrypted).map_err(|e| e.to_string())?;
+        staged.push((db.name.clone(), tmp));
+        Ok::<_, String>(())
+    });
+    let result = staged_result
+        .and_then(|_| {
+            take(SnapshotReason::Rollback).map_err(|e| format!("回滚前创建快照失败，已取消回滚: {}", e))
+        })
+        .and_then(|_| {
+            // 通过在线备份 API 写回，由 SQLite 处理日志文件
+            let _guard = SNAPSHOT_LOCK.lock().unwrap();
+            let data_dir = data_dir()?;
+            for (name, tmp) in &staged {
+                let mut conn = Connection::open(data_dir.join(name))
+                    .map_err(|e| format!("连接数据库失败: {}", e))?;
+                conn.restore(DatabaseName::Main, tmp, None::<fn(rusqlite::backup::Progress)>)
+                    .map_err(|e| format!("回滚数据库 {} 失败: {}", name, e))?;
+            }
+            Ok(())
+        });
+    for (_, tmp) in &staged {
+        let _ = secure_delete_file(tmp);
+    }
+    result?;
+
+    tracing::info!(target: "db::snapshot", id = %id, "数据库已回滚到快照");
+    Ok(info)
+}
+
+/// 删除指定快照
+pub fn delete(id: &str) -> Result<(), String> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    remove_snapshot_dir(&snapshot_dir(id)?)
+}
+
+/// 删除登录指定账户时创建的快照（删除账户备份时调用，避免令牌残留）
+///
+/// 需要保险库已解锁；信息无法读取的快照不删除，计入 `unchecked` 由调用方提示用户。
+pub fn purge_account(email: &str) -> Result<PurgeReport, String> {
+    vault().ensure_unlocked()?;
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let email = account_index::normalize_email(email);
+    let mut report = PurgeReport::default();
+    for dir in snapshot_dirs()? {
+        match read_meta(&dir) {
+            Ok(info) if info.email.as_deref() == Some(email.as_str()) => {
+                remove_snapshot_dir(&dir)?;
+                report.removed += 1;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(target: "db::snapshot", error = %e, "快照信息无法读取，未确认是否涉及该账户");
+                report.unchecked += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// 删除全部快照
+pub fn clear() -> Result<(), String> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    for dir in snapshot_dirs()? {
+        remove_snapshot_dir(&dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_utils::use_test_config_dir;
+
+    /// 在测试数据目录中写入主库（登录指定账户）与备份库
+    fn write_databases(email: &str, theme: &str) {
+        let dir = data_dir().unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        for name in DB_FILES {
+            let conn = Connection::open(dir.join(name)).unwrap();
+            conn.execute_batch("CREATE TABLE IF NOT EXISTS ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)")
+                .unwrap();
+            let auth = serde_json::json!({ "email": email }).to_string();
+            conn.execute("INSERT INTO ItemTable VALUES (?, ?)", [database::AUTH_STATUS, auth.as_str()])
+                .unwrap();
+            conn.execute("INSERT INTO ItemTable VALUES ('theme', ?)", [theme]).unwrap();
+        }
+    }
+
+    fn theme(name: &str) -> String {
+        let conn = Connection::open(data_dir().unwrap().join(name)).unwrap();
+        conn.query_row("SELECT value FROM ItemTable WHERE key = 'theme'", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    /// ID 精确到毫秒，连续创建时间隔开以保证顺序
+    fn take_snapshot(reason: SnapshotReason) -> DbSnapshotInfo {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        take(reason).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_take_encrypts_both_databases() {
+        use_test_config_dir("db-snapshot-take");
+        assert!(take(SnapshotReason::Manual).unwrap().is_none());
+
+        write_databases("A@example.com", "dark");
+        let info = take_snapshot(SnapshotReason::Restore);
+        assert_eq!(info.email.as_deref(), Some("a@example.com"));
+        let names: Vec<&str> = info.databases.iter().map(|db| db.name.as_str()).collect();
+        assert_eq!(names, DB_FILES);
+
+        let dir = snapshot_dir(&info.id).unwrap();
+        let content = fs::read(dir.join("state.vscdb.enc")).unwrap();
+        assert!(!content.windows(4).any(|w| w == b"dark"));
+        assert!(!fs::read_dir(&dir).unwrap().flatten().any(|e| e.path().extension() == Some("tmp".as_ref())));
+
+        let listed = list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].reason, SnapshotReason::Restore);
+        assert!(snapshot_dir("../undo").is_err());
+    }
+
+    #[test]
+    fn test_retention_keeps_newest() {
+        use_test_config_dir("db-snapshot-retention");
+        write_databases("a@example.com", "dark");
+
+        let taken: Vec<DbSnapshotInfo> =
+            (0..MAX_DB_SNAPSHOTS + 2).map(|_| take_snapshot(SnapshotReason::Manual)).collect();
+        let listed = list().unwrap();
+        assert_eq!(listed.len(), MAX_DB_SNAPSHOTS);
+        assert_eq!(listed[0].id, taken.last().unwrap().id);
+        assert_eq!(listed.last().unwrap().id, taken[2].id);
+    }
+
+    #[test]
+    fn test_rollback_round_trip() {
+        use_test_config_dir("db-snapshot-rollback");
+        write_databases("a@example.com", "dark");
+        let before = take_snapshot(SnapshotReason::Restore);
+
+        write_databases("b@example.com", "light");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        rollback(&before.id).unwrap();
+        for name in DB_FILES {
+            assert_eq!(theme(name), "dark");
+        }
+
+        // 回滚前的状态另存为新快照，回滚本身可撤销
+        let listed = list().unwrap();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].reason, SnapshotReason::Rollback);
+        assert_eq!(listed[0].email.as_deref(), Some("b@example.com"));
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        rollback(&listed[0].id).unwrap();
+        assert_eq!(theme(DB_FILES[0]), "light");
+        assert!(rollback("missing").is_err());
+    }
+
+    #[test]
+    fn test_purge_account_reports_unreadable_snapshots() {
+        use_test_config_dir("db-snapshot-purge");
+        write_databases("a@example.com", "dark");
+        take_snapshot(SnapshotReason::Restore);
+        take_snapshot(SnapshotReason::Cleanup);
+        write_databases("b@example.com", "dark");
+        let kept = take_snapshot(SnapshotReason::Restore);
+
+        let corrupt = snapshots_root().unwrap().join("00000000T000000000-0000");
+        fs::create_dir_all(&corrupt).unwrap();
+        fs::write(corrupt.join(META_FILE), b"garbage").unwrap();
+
+        let report = purge_account("A@Example.com").unwrap();
+        assert_eq!(report, PurgeReport { removed: 2, unchecked: 1 });
+        assert!(corrupt.exists());
+        let listed = list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, kept.id);
+    }
+}
@@ -0,0 +1,182 @@
+// Antigravity 字段分组模块
+// 将备份/恢复的字段按用途分组，恢复时可只选择其中几组（例如切换账户时保留本机的用户设置）
+// 每个字段属于哪个分组由字段定义（key_sets）决定
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+use crate::antigravity::key_sets::KeySet;
+
+/// 恢复字段分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyGroup {
+    /// 认证信息、头像与 Google 账户数据
+    Auth,
+    /// 用户设置与新手引导状态
+    Settings,
+    /// 命令模型配置
+    CommandConfigs,
+    /// Agent 状态同步
+    AgentState,
+    /// 聊天会话索引
+    ChatSessions,
+    /// 已读通知
+    Notifications,
+}
+
+/// 全部分组（默认恢复全部，与原有行为一致）
+pub const ALL_GROUPS: &[KeyGroup] = &[
+    KeyGroup::Auth,
+    KeyGroup::Settings,
+    KeyGroup::CommandConfigs,
+    KeyGroup::AgentState,
+    KeyGroup::ChatSessions,
+    KeyGroup::Notifications,
+];
+
+/// 分组说明（供前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyGroupInfo {
+    pub group: KeyGroup,
+    pub label: &'static str,
+    /// 分组包含的字段规则
+    pub keys: Vec<String>,
+}
+
+impl KeyGroup {
+    pub fn label(self) -> &'static str {
+        match self {
+            KeyGroup::Auth => "认证信息",
+            KeyGroup::Settings => "用户设置",
+            KeyGroup::CommandConfigs => "命令模型配置",
+            KeyGroup::AgentState => "Agent 状态",
+            KeyGroup::ChatSessions => "聊天会话",
+            KeyGroup::Notifications => "已读通知",
+        }
+    }
+}
+
+/// 全部分组的说明
+pub fn describe_all(key_set: &KeySet) -> Vec<KeyGroupInfo> {
+    ALL_GROUPS
+        .iter()
+        .map(|&group| KeyGroupInfo {
+            group,
+            label: group.label(),
+            keys: key_set.patterns_for(group),
+        })
+        .collect()
+}
+
+/// 排序并去重
+pub fn normalize(mut groups: Vec<KeyGroup>) -> Vec<KeyGroup> {
+    groups.sort();
+    groups.dedup();
+    groups
+}
+
+/// 解析分组名称，忽略未知分组（如新版本写入的分组）
+pub fn parse_groups(names: &[String]) -> Vec<KeyGroup> {
+    let groups = names
+        .iter()
+        .filter_map(|name| match serde_json::from_value(serde_json::Value::String(name.clone())) {
+            Ok(group) => Some(group),
+            Err(_) => {
+                tracing::warn!(target: "key_groups::parse", group = %name, "未知的恢复分组，已忽略");
+                None
+            }
+        })
+        .collect();
+    normalize(groups)
+}
+
+/// 反序列化各账户保存的分组（应用设置用），未知分组不会导致整个设置文件读取失败
+pub fn deserialize_saved<'de, D>(deserializer: D) -> Result<HashMap<String, Vec<KeyGroup>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw = HashMap::<String, Vec<String>>::deserialize(deserializer)?;
+    Ok(raw
+        .into_iter()
+        .map(|(account_id, names)| (account_id, parse_groups(&names)))
+        .collect())
+}
+
+/// 确定恢复分组：显式指定 > 账户保存的默认分组 > 全部分组
+///
+/// 显式指定空分组时报错；保存的分组为空（或只含未知分组）时视为未设置。
+/// `saved` 只在未显式指定时调用。
+pub fn resolve(
+    explicit: Option<Vec<KeyGroup>>,
+    saved: impl FnOnce() -> Result<Option<Vec<KeyGroup>>, String>,
+) -> Result<Vec<KeyGroup>, String> {
+    if let Some(groups) = explicit {
+        if groups.is_empty() {
+            return Err("至少需要选择一个恢复分组".to_string());
+        }
+        return Ok(groups);
+    }
+    Ok(saved()?
+        .filter(|groups| !groups.is_empty())
+        .unwrap_or_else(|| ALL_GROUPS.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_names_round_trip() {
+        for &group in ALL_GROUPS {
+            let name = serde_json::to_value(group).unwrap();
+            assert_eq!(serde_json::from_value::<KeyGroup>(name).unwrap(), group);
+        }
+        assert_eq!(serde_json::to_string(&KeyGroup::CommandConfigs).unwrap(), "\"command_configs\"");
+        assert!(serde_json::from_str::<KeyGroup>("\"CommandConfigs\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_groups_skips_unknown() {
+        let names: Vec<String> = ["settings", "future_group", "auth", "settings"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(parse_groups(&names), vec![KeyGroup::Auth, KeyGroup::Settings]);
+        assert!(parse_groups(&["unknown".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_saved_groups_with_unknown_names() {
+        #[derive(Deserialize)]
+        struct Settings {
+            #[serde(deserialize_with = "deserialize_saved")]
+            restore_groups: HashMap<String, Vec<KeyGroup>>,
+        }
+
+        let json = r#"{"restore_groups":{"acc-1":["auth","future_group"],"acc-2":["future_group"],"acc-3":[]}}"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(settings.restore_groups["acc-1"], vec![KeyGroup::Auth]);
+        assert!(settings.restore_groups["acc-2"].is_empty());
+
+        // 只含未知分组或为空时退回全部分组
+        for account in ["acc-2", "acc-3"] {
+            let saved = settings.restore_groups.get(account).cloned();
+            assert_eq!(resolve(None, || Ok(saved)).unwrap(), ALL_GROUPS);
+        }
+    }
+
+    #[test]
+    fn test_resolve_precedence() {
+        let saved = || Ok(Some(vec![KeyGroup::Auth]));
+
+        // 显式指定优先，且不读取保存的分组
+        let explicit = resolve(Some(vec![KeyGroup::Settings]), || panic!("不应读取保存的分组")).unwrap();
+        assert_eq!(explicit, vec![KeyGroup::Settings]);
+        assert!(resolve(Some(Vec::new()), saved).is_err());
+
+        assert_eq!(resolve(None, saved).unwrap(), vec![KeyGroup::Auth]);
+        assert_eq!(resolve(None, || Ok(None)).unwrap(), ALL_GROUPS);
+        assert!(resolve(None, || Err("索引读取失败".to_string())).is_err());
+    }
+}
@@ -0,0 +1,546 @@
+// Antigravity 字段集合模块
+// 备份、恢复、注销涉及的数据库字段由版本化的定义文件（配置目录下的 key_sets.json）描述，
+// 无需重新编译即可适配 Antigravity 新增的会话字段
+//
+// 每条定义包含：
+// - 匹配规则：精确字段名、glob 通配符或正则表达式（如通知类字段）
+// - 所属恢复分组（为空表示随任意分组一起恢复，如存储标记）
+// - 是否参与 Marker 合并、是否在注销时删除
+//
+// 此外可通过 `files` 指定 ItemTable 之外的账户文件/目录（相对 Antigravity 的 User 目录，
+// 如 `settings.json`、`globalStorage/<扩展 ID>`），随备份一起保存和恢复（见 user_files）。
+//
+// 定义文件不存在时使用内置默认定义（与原先硬编码的字段列表一致，不包含文件）。
+
+use regex::Regex;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::antigravity::key_groups::KeyGroup;
+use crate::antigravity::{secret_storage, user_files};
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+
+/// 定义文件名（位于配置目录）
+pub const KEY_SETS_FILE: &str = "key_sets.json";
+
+/// 当前定义文件格式版本
+pub const KEY_SETS_VERSION: u32 = 1;
+
+/// 备份文件中的元数据字段，任何定义都不能匹配
+const RESERVED_KEYS: &[&str] = &[
+    database::TARGET_STORAGE_MARKER,
+    "account_email",
+    "backup_time",
+    secret_storage::BACKUP_MACHINE_KEY,
+    user_files::FILES_INDEX_KEY,
+];
+
+/// 当前生效的字段集合（首次使用时加载）
+static CURRENT: Mutex<Option<Arc<KeySet>>> = Mutex::new(None);
+
+/// 字段匹配规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPattern {
+    /// 精确字段名
+    Exact(String),
+    /// glob 通配符（`*` 匹配任意字符，`?` 匹配单个字符）
+    Glob(String),
+    /// 正则表达式
+    Regex(String),
+}
+
+impl KeyPattern {
+    fn compile(&self) -> Result<Matcher, String> {
+        match self {
+            KeyPattern::Exact(key) => Ok(Matcher::Exact(key.clone())),
+            KeyPattern::Glob(glob) => Regex::new(&glob_to_regex(glob))
+                .map(Matcher::Pattern)
+                .map_err(|e| format!("glob 规则无效 {}: {}", glob, e)),
+            KeyPattern::Regex(re) => Regex::new(re)
+                .map(Matcher::Pattern)
+                .map_err(|e| format!("正则规则无效 {}: {}", re, e)),
+        }
+    }
+
+    /// 规则的展示文本
+    pub fn display(&self) -> String {
+        match self {
+            KeyPattern::Exact(key) => key.clone(),
+            KeyPattern::Glob(glob) => format!("glob:{}", glob),
+            KeyPattern::Regex(re) => format!("regex:{}", re),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 单条字段定义
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyDefinition {
+    pub pattern: KeyPattern,
+    /// 所属恢复分组（为空表示只要恢复了任意分组就一并恢复）
+    #[serde(default)]
+    pub group: Option<KeyGroup>,
+    /// 恢复后是否在 Marker 中登记同步标记
+    #[serde(default = "default_true")]
+    pub marker: bool,
+    /// 注销时是否删除
+    #[serde(default)]
+    pub logout: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 账户文件/目录定义
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDefinition {
+    /// 相对 Antigravity User 目录的路径（使用 `/` 分隔）
+    pub path: String,
+    /// 所属恢复分组（为空表示只要恢复了任意分组就一并恢复）
+    #[serde(default)]
+    pub group: Option<KeyGroup>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// 定义文件内容
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeySetFile {
+    pub version: u32,
+    pub keys: Vec<KeyDefinition>,
+    /// 需要随备份保存的账户文件/目录
+    #[serde(default)]
+    pub files: Vec<FileDefinition>,
+}
+
+/// 定义来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySetSource {
+    /// 内置默认定义
+    Builtin,
+    /// 配置目录中的定义文件
+    File,
+}
+
+/// 当前生效的定义（供前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct KeySetInfo {
+    pub source: KeySetSource,
+    pub path: Option<String>,
+    pub definitions: KeySetFile,
+}
+
+enum Matcher {
+    Exact(String),
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn matches(&self, key: &str) -> bool {
+        match self {
+            Matcher::Exact(exact) => exact == key,
+            Matcher::Pattern(re) => re.is_match(key),
+        }
+    }
+}
+
+/// 编译后的字段集合
+pub struct KeySet {
+    source: KeySetSource,
+    file: KeySetFile,
+    matchers: Vec<Matcher>,
+}
+
+/// 将 glob 通配符转换为锚定的正则表达式
+fn glob_to_regex(glob: &str) -> String {
+    let mut re = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => re.push_str(".*"),
+            '?' => re.push('.'),
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+fn exact(key: &str, group: Option<KeyGroup>, marker: bool, logout: bool, description: &str) -> KeyDefinition {
+    KeyDefinition {
+        pattern: KeyPattern::Exact(key.to_string()),
+        group,
+        marker,
+        logout,
+        description: Some(description.to_string()),
+    }
+}
+
+/// 内置默认定义
+pub fn builtin_definitions() -> KeySetFile {
+    KeySetFile {
+        version: KEY_SETS_VERSION,
+        keys: vec![
+            exact(database::AUTH_STATUS, Some(KeyGroup::Auth), true, true, "认证状态"),
+            exact(database::PROFILE_URL, Some(KeyGroup::Auth), true, true, "用户头像 URL"),
+            exact(database::USER_SETTINGS, Some(KeyGroup::Settings), true, true, "用户设置"),
+            exact(database::ONBOARDING, Some(KeyGroup::Settings), true, true, "新手引导"),
+            exact(database::GOOGLE_DATA, Some(KeyGroup::Auth), true, false, "Google 相关数据"),
+            exact(database::COMMAND_CONFIGS, Some(KeyGroup::CommandConfigs), true, true, "命令模型配置"),
+            exact(database::AGENT_STATE, Some(KeyGroup::AgentState), true, false, "Agent 状态同步"),
+            exact(database::CHAT_SESSION, Some(KeyGroup::ChatSessions), true, false, "聊天会话存储"),
+            exact(database::NEW_STORAGE_MARKER, None, false, false, "新存储标记"),
+            KeyDefinition {
+                pattern: KeyPattern::Glob("antigravity.notification*".to_string()),
+                group: Some(KeyGroup::Notifications),
+                // 通知字段不需要参与 Marker 同步
+                marker: false,
+                logout: false,
+                description: Some("已读通知（避免历史通知重复弹窗）".to_string()),
+            },
+            KeyDefinition {
+                pattern: KeyPattern::Glob(format!("{}*", secret_storage::SECRET_KEY_PREFIX)),
+                group: Some(KeyGroup::Auth),
+                marker: true,
+                logout: false,
+                description: Some("扩展密钥（系统钥匙串加密，换机后无法解密）".to_string()),
+            },
+        ],
+        files: Vec::new(),
+    }
+}
+
+impl KeySet {
+    /// 校验并编译定义
+    pub fn compile(file: KeySetFile, source: KeySetSource) -> Result<Self, String> {
+        if file.version > KEY_SETS_VERSION {
+            return Err(format!("不支持的字段定义版本: {}", file.version));
+        }
+        if file.keys.is_empty() {
+            return Err("字段定义不能为空".to_string());
+        }
+
+        let matchers = file
+            .keys
+            .iter()
+            .map(|def| def.pattern.compile())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (def, matcher) in file.keys.iter().zip(&matchers) {
+            if let Some(key) = RESERVED_KEYS.iter().find(|key| matcher.matches(key)) {
+                return Err(format!("字段定义 {} 不能匹配保留字段 {}", def.pattern.display(), key));
+            }
+        }
+
+        let mut roots: Vec<String> = Vec::new();
+        for def in &file.files {
+            let root = user_files::normalize_root(&def.path)?;
+            if let Some(other) = roots.iter().find(|other| user_files::roots_overlap(other, &root)) {
+                return Err(format!("文件定义 {} 与 {} 重叠", root, other));
+            }
+            roots.push(root);
+        }
+
+        let set = Self { source, file, matchers };
+        // 没有认证字段的定义会使切换失效
+        if set.classify(database::AUTH_STATUS).and_then(|def| def.group) != Some(KeyGroup::Auth) {
+            return Err(format!("字段定义必须将 {} 归入认证分组", database::AUTH_STATUS));
+        }
+        Ok(set)
+    }
+
+    /// 内置默认字段集合
+    pub fn builtin() -> Self {
+        Self::compile(builtin_definitions(), KeySetSource::Builtin).expect("内置字段定义无效")
+    }
+
+    pub fn definitions(&self) -> &KeySetFile {
+        &self.file
+    }
+
+    /// 字段对应的定义（多条匹配时取第一条）
+    pub fn classify(&self, key: &str) -> Option<&KeyDefinition> {
+        self.file
+            .keys
+            .iter()
+            .zip(&self.matchers)
+            .find(|(_, matcher)| matcher.matches(key))
+            .map(|(def, _)| def)
+    }
+
+    /// 恢复该字段后是否需要在 Marker 中登记
+    pub fn participates_in_marker(&self, key: &str) -> bool {
+        self.classify(key).is_some_and(|def| def.marker)
+    }
+
+    /// 需要备份的字段：全部精确字段，加上数据库中匹配通配规则的字段
+    ///
+    /// # 参数
+    /// - `existing`: 数据库中现有的全部字段名
+    pub fn resolve(&self, existing: &[String]) -> Vec<String> {
+        self.resolve_where(existing, |_| true)
+    }
+
+    /// 注销时需要删除的字段
+    pub fn logout_keys(&self, existing: &[String]) -> Vec<String> {
+        self.resolve_where(existing, |def| def.logout)
+    }
+
+    fn resolve_where(&self, existing: &[String], filter: impl Fn(&KeyDefinition) -> bool) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut keys = Vec::new();
+        for matcher in &self.matchers {
+            let matched: Vec<&str> = match matcher {
+                Matcher::Exact(key) => vec![key.as_str()],
+                Matcher::Pattern(_) => {
+                    let mut found: Vec<&str> = existing
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|key| matcher.matches(key))
+                        .collect();
+                    found.sort_unstable();
+                    found
+                }
+            };
+            for key in matched {
+                // 字段的属性由第一条匹配的定义决定
+                if seen.insert(key.to_string()) && self.classify(key).is_some_and(&filter) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+        keys
+    }
+
+    /// 备份中属于所选分组、需要恢复的字段
+    ///
+    /// 没有分组的字段（如存储标记）只在至少恢复了一个分组字段时才一并恢复。
+    pub fn restore_keys(&self, backup: &Value, groups: &[KeyGroup]) -> Vec<String> {
+        let Some(obj) = backup.as_object() else {
+            return Vec::new();
+        };
+        let existing: Vec<String> = obj
+            .iter()
+            .filter(|(_, value)| value.is_string())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut has_grouped = false;
+        let keys: Vec<String> = self
+            .resolve(&existing)
+            .into_iter()
+            .filter(|key| obj.get(key).is_some_and(Value::is_string))
+            .filter(|key| match self.classify(key).and_then(|def| def.group) {
+                Some(group) if groups.contains(&group) => {
+                    has_grouped = true;
+                    true
+                }
+                Some(_) => false,
+                None => true,
+            })
+            .collect();
+
+        if has_grouped {
+            keys
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// 需要随备份保存的账户文件/目录
+    pub fn file_definitions(&self) -> &[FileDefinition] {
+        &self.file.files
+    }
+
+    /// 分组包含的规则（展示用）
+    pub fn patterns_for(&self, group: KeyGroup) -> Vec<String> {
+        self.file
+            .keys
+            .iter()
+            .filter(|def| def.group == Some(group))
+            .map(|def| def.pattern.display())
+            .chain(
+                self.file
+                    .files
+                    .iter()
+                    .filter(|def| def.group == Some(group))
+                    .map(|def| format!("file:{}", def.path)),
+            )
+            .collect()
+    }
+}
+
+fn definitions_path() -> Option<PathBuf> {
+    AppPaths::config_dir().map(|dir| dir.join(KEY_SETS_FILE))
+}
+
+/// 从定义文件加载（文件不存在时使用内置定义）
+fn load() -> Result<KeySet, String> {
+    let Some(path) = definitions_path().filter(|path| path.exists()) else {
+        return Ok(KeySet::builtin());
+    };
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取字段定义失败: {}", e))?;
+    let file: KeySetFile =
+        serde_json::from_str(&content).map_err(|e| format!("字段定义解析失败: {}", e))?;
+    KeySet::compile(file, KeySetSource::File)
+}
+
+/// 当前生效的字段集合
+///
+/// 定义文件无效时记录警告并使用内置定义，避免切换功能整体失效。
+pub fn current() -> Arc<KeySet> {
+    let mut current = CURRENT.lock().unwrap();
+    if let Some(set) = current.as_ref() {
+        return set.clone();
+    }
+    let set = Arc::new(load().unwrap_or_else(|e| {
+        tracing::warn!(target: "key_sets::load", error = %e, "字段定义无效，使用内置定义");
+        KeySet::builtin()
+    }));
+    *current = Some(set.clone());
+    set
+}
+
+/// 当前生效定义的说明
+pub fn info() -> KeySetInfo {
+    let set = current();
+    KeySetInfo {
+        source: set.source,
+        path: definitions_path().map(|path| path.display().to_string()),
+        definitions: set.definitions().clone(),
+    }
+}
+
+/// 重新加载定义文件（文件无效时返回错误并保留当前定义）
+pub fn reload() -> Result<KeySetInfo, String> {
+    let set = load()?;
+    tracing::info!(target: "key_sets::load", source = ?set.source, count = set.file.keys.len(), "字段定义已加载");
+    *CURRENT.lock().unwrap() = Some(Arc::new(set));
+    Ok(info())
+}
+
+/// 保存定义文件，传入 `None` 删除定义文件并恢复内置定义
+pub fn save(file: Option<KeySetFile>) -> Result<KeySetInfo, String> {
+    let path = definitions_path().ok_or("无法获取配置目录")?;
+    match file {
+        Some(file) => {
+            // 先校验再写入，避免写入无法加载的文件
+            KeySet::compile(file.clone(), KeySetSource::File)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+            }
+            let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+            fs::write(&path, json).map_err(|e| format!("写入字段定义失败: {}", e))?;
+        }
+        None => {
+            if path.exists() {
+                fs::remove_file(&path).map_err(|e| format!("删除字段定义失败: {}", e))?;
+            }
+        }
+    }
+    reload()
+}
+
+/// 读取数据库中的全部字段名
+pub fn list_db_keys(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT key FROM ItemTable")
+        .map_err(|e| format!("查询字段列表失败: {}", e))?;
+    let keys = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("查询字段列表失败: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("读取字段列表失败: {}", e))?;
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_matches_legacy_key_lists() {
+        let set = KeySet::builtin();
+        let all = set.resolve(&[]);
+        assert_eq!(all.len(), 9);
+        assert!(all.iter().any(|k| k == database::NEW_STORAGE_MARKER));
+
+        let logout = set.logout_keys(&[]);
+        assert_eq!(
+            logout,
+            vec![
+                database::AUTH_STATUS,
+                database::PROFILE_URL,
+                database::USER_SETTINGS,
+                database::ONBOARDING,
+                database::COMMAND_CONFIGS,
+            ]
+        );
+        assert!(!set.participates_in_marker(database::NEW_STORAGE_MARKER));
+    }
+
+    #[test]
+    fn test_patterns_and_groups() {
+        let set = KeySet::builtin();
+        let existing = vec![
+            "antigravity.notification.seen".to_string(),
+            "workbench.panel".to_string(),
+        ];
+        let keys = set.resolve(&existing);
+        assert!(keys.contains(&"antigravity.notification.seen".to_string()));
+        assert!(!keys.contains(&"workbench.panel".to_string()));
+        assert!(!set.participates_in_marker("antigravity.notification.seen"));
+
+        let backup = serde_json::json!({
+            database::AUTH_STATUS: "{}",
+            database::USER_SETTINGS: "{}",
+            database::NEW_STORAGE_MARKER: "1",
+            "antigravity.notification.seen": "[]",
+            "account_email": "user@example.com",
+        });
+        let auth_only = set.restore_keys(&backup, &[KeyGroup::Auth]);
+        assert_eq!(auth_only, vec![database::AUTH_STATUS, database::NEW_STORAGE_MARKER]);
+        assert!(set.restore_keys(&backup, &[KeyGroup::AgentState]).is_empty());
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_definitions() {
+        let mut file = builtin_definitions();
+        file.keys.push(KeyDefinition {
+            pattern: KeyPattern::Regex("(".to_string()),
+            group: None,
+            marker: false,
+            logout: false,
+            description: None,
+        });
+        assert!(KeySet::compile(file, KeySetSource::File).is_err());
+
+        let mut file = builtin_definitions();
+        file.keys.push(KeyDefinition {
+            pattern: KeyPattern::Glob("*".to_string()),
+            group: Some(KeyGroup::Settings),
+            marker: true,
+            logout: false,
+            description: None,
+        });
+        assert!(KeySet::compile(file, KeySetSource::File).is_err());
+
+        let mut file = builtin_definitions();
+        for path in ["globalStorage/ext", "globalStorage/ext/cache"] {
+            file.files.push(FileDefinition { path: path.to_string(), group: None, description: None });
+        }
+        assert!(KeySet::compile(file, KeySetSource::File).is_err());
+
+        let file = KeySetFile { version: KEY_SETS_VERSION + 1, ..builtin_definitions() };
+        assert!(KeySet::compile(file, KeySetSource::File).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// Antigravity 启动输出模块
+// 启动 Antigravity 时把子进程的 stdout / stderr 直接重定向到日志目录下的 antigravity-launch.log，
+// 每次启动写入带启动 ID 的起止标记，便于在切换后启动失败时查看原因。
+//
+// 输出直接写入文件而不经过管道：Antigravity 的生命周期长于本次调用，甚至长于本应用，
+// 管道在本应用退出后断开会导致其写输出失败。
+// 退出码监视线程在子进程退出时写入结束标记，启动后短时间内以非零状态退出的记为崩溃。
+
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::path_utils::AppPaths;
+use crate::utils::log_sanitizer::LogSanitizer;
+
+/// 启动输出日志文件名
+const LAUNCH_LOG_FILE: &str = "antigravity-launch.log";
+
+/// 日志文件超过此大小时在下次启动前轮转
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// 保留的轮转文件数量（antigravity-launch.log.1 ~ .3）
+const KEEP_ROTATED: usize = 3;
+
+/// 启动后在此时间内以非零状态退出的记为崩溃
+const EARLY_EXIT_WINDOW: Duration = Duration::from_secs(30);
+
+/// 读取最近一次启动输出的最大字节数（超出时保留末尾）
+const MAX_OUTPUT_BYTES: u64 = 64 * 1024;
+
+/// 最近一次启动
+static LAST_LAUNCH: Mutex<Option<LaunchRecord>> = Mutex::new(None);
+
+/// 启动记录
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchRecord {
+    /// 启动 ID（写入日志的起止标记）
+    pub id: String,
+    /// 启动的程序（已脱敏）
+    pub program: String,
+    pub pid: Option<u32>,
+    /// 启动时间（RFC3339）
+    pub started_at: String,
+    /// 退出码（被信号终止时为空）
+    pub exit_code: Option<i32>,
+    /// 退出时间（RFC3339），仍在运行时为空
+    pub exited_at: Option<String>,
+    /// 启动后短时间内以非零状态退出
+    pub crashed: bool,
+}
+
+/// 最近一次启动的输出
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchOutput {
+    pub launch: Option<LaunchRecord>,
+    /// 子进程输出（已脱敏）
+    pub output: String,
+    /// 输出过长，只保留了末尾部分
+    pub truncated: bool,
+}
+
+fn log_path() -> Result<PathBuf, String> {
+    AppPaths::log_dir()
+        .map(|dir| dir.join(LAUNCH_LOG_FILE))
+        .ok_or_else(|| "无法获取日志目录".to_string())
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), n))
+}
+
+/// 日志文件过大时轮转：.log -> .log.1 -> .log.2 ...，最旧的一份被覆盖
+fn rotate_if_needed(path: &Path) -> std::io::Result<()> {
+    if fs::metadata(path).map(|m| m.len()).unwrap_or(0) < MAX_LOG_BYTES {
+        return Ok(());
+    }
+    for n in (1..KEEP_ROTATED).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+fn open_log() -> Result<File, String> {
+    let path = log_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("创建日志目录失败: {}", e))?;
+    }
+    if let Err(e) = rotate_if_needed(&path) {
+        tracing::warn!(target: "antigravity::launch", error = %e, "启动日志轮转失败");
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("打开启动日志失败: {}", e))
+}
+
+fn start_marker(id: &str) -> String {
+    format!("=== launch {} started", id)
+}
+
+fn append_line(line: &str) {
+    let result = log_path().and_then(|path| {
+        OpenOptions::new()
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        tracing::warn!(target: "antigravity::launch", error = %e, "写入启动日志失败");
+    }
+}
+
+/// 启动子进程，stdout / stderr 写入启动日志
+///
+/// 日志文件无法打开时仍然启动（输出丢弃），不因诊断功能影响启动。
+pub fn spawn(command: &mut Command) -> std::io::Result<LaunchRecord> {
+    let now = chrono::Local::now();
+    let id = format!("{}-{:03}", now.format("%Y%m%d-%H%M%S"), now.timestamp_subsec_millis());
+    let program = LogSanitizer::new().sanitize(&command.get_program().to_string_lossy());
+
+    let log = match open_log() {
+        Ok(mut file) => {
+            let _ = writeln!(file, "{} {} {} ===", start_marker(&id), now.to_rfc3339(), program);
+            Some(file)
+        }
+        Err(e) => {
+            tracing::warn!(target: "antigravity::launch", error = %e, "无法记录启动输出");
+            None
+        }
+    };
+    let (stdout, stderr) = match log.as_ref().map(|file| (file.try_clone(), file.try_clone())) {
+        Some((Ok(out), Ok(err))) => (Stdio::from(out), Stdio::from(err)),
+        _ => (Stdio::null(), Stdio::null()),
+    };
+
+    let child = match command.stdin(Stdio::null()).stdout(stdout).stderr(stderr).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            if log.is_some() {
+                append_line(&format!("=== launch {} failed to spawn: {} ===", id, e));
+            }
+            return Err(e);
+        }
+    };
+
+    let record = LaunchRecord {
+        id,
+        program,
+        pid: Some(child.id()),
+        started_at: now.to_rfc3339(),
+        exit_code: None,
+        exited_at: None,
+        crashed: false,
+    };
+    tracing::info!(target: "antigravity::launch", launch_id = %record.id, pid = child.id(), "已启动 Antigravity 进程");
+    *LAST_LAUNCH.lock().unwrap() = Some(record.clone());
+    watch_exit(child, record.id.clone(), log.is_some());
+    Ok(record)
+}
+
+/// 监视子进程退出，记录退出码（同时回收子进程，避免残留僵尸进程）
+fn watch_exit(mut child: Child, id: String, logging: bool) {
+    let spawned = std::thread::Builder::new()
+        .name(format!("launch-watch-{}", id))
+        .spawn(move || {
+            let started = Instant::now();
+            let status = match child.wait() {
+                Ok(status) => status,
+                Err(e) => {
+                    tracing::warn!(target: "antigravity::launch", launch_id = %id, error = %e, "等待启动进程失败");
+                    return;
+                }
+            };
+            let elapsed = started.elapsed();
+            let crashed = elapsed < EARLY_EXIT_WINDOW && !status.success();
+
+            if logging {
+                let code = status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string());
+                append_line(&format!(
+                    "=== launch {} exited code={} after {:.1}s{} ===",
+                    id,
+                    code,
+                    elapsed.as_secs_f32(),
+                    if crashed { " (crashed)" } else { "" }
+                ));
+            }
+            if crashed {
+                tracing::warn!(
+                    target: "antigravity::launch",
+                    launch_id = %id,
+                    exit_code = ?status.code(),
+                    elapsed_secs = elapsed.as_secs(),
+                    "Antigravity 启动后很快异常退出"
+                );
+            } else {
+                tracing::debug!(target: "antigravity::launch", launch_id = %id, exit_code = ?status.code(), "启动进程已退出");
+            }
+
+            if let Some(record) = LAST_LAUNCH.lock().unwrap().as_mut().filter(|record| record.id == id) {
+                record.exit_code = status.code();
+                record.exited_at = Some(chrono::Local::now().to_rfc3339());
+                record.crashed = crashed;
+            }
+        });
+    if let Err(e) = spawned {
+        tracing::warn!(target: "antigravity::launch", error = %e, "无法启动退出码监视线程");
+    }
+}
+
+/// 最近一次启动记录
+pub fn last() -> Option<LaunchRecord> {
+    LAST_LAUNCH.lock().unwrap().clone()
+}
+
+/// 从日志末尾内容中截取指定启动的输出（从起始标记开始），未指定时取最后一次启动
+fn section_for<'a>(content: &'a str, id: Option<&str>) -> Option<&'a str> {
+    let start = match id {
+        Some(id) => content.rfind(&start_marker(id)),
+        None => content
+            .match_indices("=== launch ")
+            .map(|(start, _)| start)
+            .filter(|&start| content[start..].lines().next().is_some_and(|line| line.contains(" started ")))
+            .last(),
+    };
+    start.map(|start| &content[start..])
+}
+
+/// 读取日志文件末尾，返回内容与是否截断
+fn read_tail(path: &Path) -> Result<(String, bool), String> {
+    let mut file = File::open(path).map_err(|e| format!("读取启动日志失败: {}", e))?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let truncated = len > MAX_OUTPUT_BYTES;
+    if truncated {
+        file.seek(SeekFrom::Start(len - MAX_OUTPUT_BYTES))
+            .map_err(|e| format!("读取启动日志失败: {}", e))?;
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(|e| format!("读取启动日志失败: {}", e))?;
+    Ok((String::from_utf8_lossy(&bytes).into_owned(), truncated))
+}
+
+/// 读取最近一次启动的输出（已脱敏）
+///
+/// 本次运行尚未启动过 Antigravity 时返回日志中最后一次启动的输出。
+pub fn last_output() -> Result<LaunchOutput, String> {
+    let launch = last();
+    let path = log_path()?;
+    if !path.exists() {
+        return Ok(LaunchOutput { launch, output: String::new(), truncated: false });
+    }
+
+    let (content, mut truncated) = read_tail(&path)?;
+    let output = match section_for(&content, launch.as_ref().map(|record| record.id.as_str())) {
+        Some(section) => {
+            truncated = false;
+            section
+        }
+        // 起始标记已不在末尾范围内（输出过长），返回末尾部分
+        None => content.as_str(),
+    };
+    Ok(LaunchOutput {
+        launch,
+        output: LogSanitizer::new().sanitize(output),
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_and_rotation() {
+        let content = "=== launch a started t1 app ===\nold\n=== launch b started t2 app ===\nnew\n=== launch b exited code=1 ===\n";
+        let latest = "=== launch b started t2 app ===\nnew\n=== launch b exited code=1 ===\n";
+        assert_eq!(section_for(content, Some("b")), Some(latest));
+        assert_eq!(section_for(content, None), Some(latest));
+        assert!(section_for(content, Some("c")).is_none());
+
+        let dir = std::env::temp_dir().join(format!("ag-launch-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LAUNCH_LOG_FILE);
+        fs::write(&path, "small").unwrap();
+        rotate_if_needed(&path).unwrap();
+        assert!(path.exists());
+
+        for round in 0..KEEP_ROTATED + 1 {
+            fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize + round]).unwrap();
+            rotate_if_needed(&path).unwrap();
+            assert!(!path.exists());
+        }
+        assert!(rotated_path(&path, KEEP_ROTATED).exists());
+        assert!(!rotated_path(&path, KEEP_ROTATED + 1).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+// Antigravity 启动验证模块
+// starter 在 spawn 成功后立即返回，进程随后崩溃或启动后改写登录状态都不会被发现。
+// 此模块在启动后等待 Antigravity 进程出现并保持运行，再确认 antigravityAuthStatus 仍是目标账户。
+//
+// 最近一次验证结果保存在内存中，切换回滚后更新为 rolled_back，供前端查询。
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::antigravity::{account_index, active_account, launch_log};
+
+/// 等待进程出现的最长时间
+const APPEAR_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// 进程出现后需要持续运行的时间（Antigravity 启动时会读取并可能改写登录状态）
+const STABLE_PERIOD: Duration = Duration::from_secs(5);
+
+/// 进程检测间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 最近一次验证结果
+static LAST_VERIFICATION: Mutex<Option<LaunchVerification>> = Mutex::new(None);
+
+/// 启动验证状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchStatus {
+    /// 进程保持运行，且登录的是目标账户
+    Verified,
+    /// 进程未出现、启动后退出，或登录的不是目标账户
+    Failed,
+    /// 验证失败后已回滚到启动前的状态
+    RolledBack,
+}
+
+/// 启动验证结果
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchVerification {
+    pub status: LaunchStatus,
+    /// 期望登录的账户（为空时不检查账户）
+    pub expected_email: Option<String>,
+    /// 启动后数据库中登录的账户
+    pub actual_email: Option<String>,
+    /// 进程是否在稳定期内保持运行
+    pub process_alive: bool,
+    pub message: String,
+    /// 验证耗时（毫秒）
+    pub duration_ms: u64,
+    /// 验证完成时间（RFC3339）
+    pub checked_at: String,
+}
+
+/// 等待 Antigravity 进程出现并在稳定期内保持运行
+async fn wait_for_stable_process() -> Result<(), String> {
+    let started = Instant::now();
+    while !crate::platform::is_antigravity_running() {
+        if started.elapsed() >= APPEAR_TIMEOUT {
+            return Err(format!("Antigravity 进程在 {} 秒内未出现", APPEAR_TIMEOUT.as_secs()));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    let appeared = Instant::now();
+    while appeared.elapsed() < STABLE_PERIOD {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if !crate::platform::is_antigravity_running() {
+            return Err(format!(
+                "Antigravity 启动后 {:.1} 秒内退出",
+                appeared.elapsed().as_secs_f32()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 比较期望账户与实际登录的账户（邮箱不区分大小写）
+fn check_account(expected: Option<&str>, actual: Option<&str>) -> Result<String, String> {
+    let Some(expected) = expected else {
+        return Ok("Antigravity 已启动并保持运行".to_string());
+    };
+    match actual {
+        Some(actual) if account_index::normalize_email(actual) == account_index::normalize_email(expected) => {
+            Ok(format!("已确认 Antigravity 以 {} 登录", actual))
+        }
+        Some(actual) => Err(format!("Antigravity 启动后登录的是 {}，而不是 {}", actual, expected)),
+        None => Err(format!("Antigravity 启动后未登录（期望 {}）", expected)),
+    }
+}
+
+/// 验证 Antigravity 已启动并登录了期望的账户
+///
+/// # 参数
+/// - `expected_email`: 期望登录的账户，为空时只检查进程
+pub async fn verify(expected_email: Option<&str>) -> LaunchVerification {
+    let started = Instant::now();
+    let process = wait_for_stable_process().await;
+
+    let mut actual_email = None;
+    let result = match process {
+        Ok(()) => match active_account::read_live_auth() {
+            Ok(auth) => {
+                actual_email = auth
+                    .as_ref()
+                    .and_then(|auth| auth.get("email"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                check_account(expected_email, actual_email.as_deref())
+            }
+            Err(e) => Err(format!("读取登录状态失败: {}", e)),
+        },
+        Err(ref e) => Err(match launch_log::last().filter(|launch| launch.crashed) {
+            Some(launch) => format!(
+                "{}（启动进程退出码 {}，详见启动输出 {}）",
+                e,
+                launch.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "未知".to_string()),
+                launch.id
+            ),
+            None => e.clone(),
+        }),
+    };
+
+    let (status, message) = match result {
+        Ok(message) => (LaunchStatus::Verified, message),
+        Err(e) => (LaunchStatus::Failed, e),
+    };
+    let verification = LaunchVerification {
+        status,
+        expected_email: expected_email.map(|s| s.to_string()),
+        actual_email,
+        process_alive: process.is_ok(),
+        message,
+        duration_ms: started.elapsed().as_millis() as u64,
+        checked_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    match verification.status {
+        LaunchStatus::Verified => tracing::info!(target: "antigravity::launch", "启动验证通过"),
+        _ => tracing::warn!(
+            target: "antigravity::launch",
+            process_alive = verification.process_alive,
+            "启动验证失败"
+        ),
+    }
+    *LAST_VERIFICATION.lock().unwrap() = Some(verification.clone());
+    verification
+}
+
+/// 将最近一次验证标记为已回滚
+pub fn mark_rolled_back(message: &str) {
+    if let Some(verification) = LAST_VERIFICATION.lock().unwrap().as_mut() {
+        verification.status = LaunchStatus::RolledBack;
+        verification.message = format!("{}（{}）", verification.message, message);
+    }
+}
+
+/// 最近一次验证结果
+pub fn last() -> Option<LaunchVerification> {
+    LAST_VERIFICATION.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_account() {
+        assert!(check_account(None, None).is_ok());
+        assert!(check_account(Some("user@example.com"), Some("User@Example.com")).is_ok());
+        assert!(check_account(Some("user@example.com"), Some("other@example.com")).is_err());
+        assert!(check_account(Some("user@example.com"), None).is_err());
+    }
+}
@@ -1,5 +1,24 @@
+pub mod active_account;
+pub mod account_index;
+pub mod account_keys;
 pub mod backup;
 pub mod cleanup;
+pub mod credentials;
+pub mod db_consistency;
+pub mod db_health;
+pub mod db_snapshots;
+pub mod key_groups;
+pub mod key_sets;
+pub mod launch_log;
+pub mod launch_verify;
 pub mod path_config;
+pub mod quota_history;
 pub mod restore;
+pub mod secret_storage;
+pub mod snapshot_store;
 pub mod starter;
+pub mod switch_preview;
+pub mod switch_undo;
+pub mod user_files;
+pub mod user_status;
+pub mod vault;
@@ -0,0 +1,442 @@
+// Antigravity 配额历史模块
+// 按账户记录各模型配额的时间序列（剩余比例、重置时间、积分），用于图表展示与重置时间预测
+//
+// 数据保存在配置目录下的 quota_history.db（SQLite），以随机账户 ID 关联账户，不保存邮箱。
+// 该数据库不加密，主密码模式下同样以明文保存在保险库之外：其中只有账户 ID、模型名、
+// 配额比例、重置时间与积分，不含令牌；能读取配置目录的人可以看到各账户的使用情况。
+// 记录时机：数据库监控发现认证信息变化、执行账户备份。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::antigravity::account_index;
+use crate::antigravity::user_status::{self, Credits, ModelQuota};
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::secure_create_dir;
+
+/// 历史数据库文件名（位于配置目录中）
+const HISTORY_DB_FILE: &str = "quota_history.db";
+
+/// 样本保留天数
+const RETENTION_DAYS: i64 = 90;
+
+/// 配额未变化时的最小记录间隔（秒）
+const MIN_UNCHANGED_INTERVAL_SECS: i64 = 3600;
+
+/// 剩余比例回升超过该值视为一次重置
+const RESET_JUMP_THRESHOLD: f64 = 0.05;
+
+/// 串行化数据库访问
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// 样本来源
+#[derive(Debug, Clone, Copy)]
+pub enum SampleSource {
+    Monitor,
+    Backup,
+}
+
+impl SampleSource {
+    fn as_str(self) -> &'static str {
+        match self {
+            SampleSource::Monitor => "monitor",
+            SampleSource::Backup => "backup",
+        }
+    }
+}
+
+/// 单条配额样本
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaSample {
+    pub model: String,
+    /// 记录时间（RFC3339）
+    pub recorded_at: String,
+    pub remaining_fraction: f64,
+    /// 接口报告的重置时间（RFC3339）
+    pub reset_time: Option<String>,
+    pub prompt_credits: Option<i64>,
+    pub flow_credits: Option<i64>,
+    pub source: String,
+}
+
+/// 预测依据
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PredictionBasis {
+    /// 最新样本中报告的重置时间仍在未来
+    Reported,
+    /// 根据历史中观察到的重置周期推算
+    ObservedCycle,
+    /// 配额已满，没有待发生的重置
+    Full,
+    /// 数据不足
+    Unknown,
+}
+
+/// 单个模型的下次重置预测
+#[derive(Debug, Clone, Serialize)]
+pub struct ResetPrediction {
+    pub model: String,
+    pub remaining_fraction: f64,
+    pub last_recorded_at: String,
+    pub predicted_reset: Option<String>,
+    pub basis: PredictionBasis,
+    /// 观察到的重置周期（秒）
+    pub observed_cycle_secs: Option<i64>,
+}
+
+/// 预测计算使用的原始样本
+struct RawSample {
+    recorded_at: i64,
+    remaining_fraction: f64,
+    reset_time: Option<i64>,
+}
+
+fn db_path() -> Result<PathBuf, String> {
+    AppPaths::config_dir()
+        .map(|dir| dir.join(HISTORY_DB_FILE))
+        .ok_or_else(|| "无法获取配置目录".to_string())
+}
+
+fn open() -> Result<Connection, String> {
+    let path = db_path()?;
+    if let Some(parent) = path.parent() {
+        secure_create_dir(parent).map_err(|e| e.to_string())?;
+    }
+    let conn = Connection::open(&path).map_err(|e| format!("打开配额历史数据库失败: {}", e))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS quota_samples (
+            account_id TEXT NOT NULL,
+            model TEXT NOT NULL,
+            recorded_at INTEGER NOT NULL,
+            remaining_fraction REAL NOT NULL,
+            reset_time INTEGER,
+            prompt_credits INTEGER,
+            flow_credits INTEGER,
+            source TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_quota_samples_account
+            ON quota_samples (account_id, model, recorded_at);",
+    )
+    .map_err(|e| format!("初始化配额历史数据库失败: {}", e))?;
+    Ok(conn)
+}
+
+fn format_time(unix_secs: i64) -> String {
+    chrono::DateTime::from_timestamp(unix_secs, 0)
+        .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// 与最新样本相同且间隔较短时不重复记录
+fn is_duplicate(
+    conn: &Connection,
+    account_id: &str,
+    quota: &ModelQuota,
+    credits: &Credits,
+    now: i64,
+) -> Result<bool, String> {
+    let last = conn
+        .query_row(
+            "SELECT recorded_at, remaining_fraction, reset_time, prompt_credits, flow_credits
+             FROM quota_samples WHERE account_id = ?1 AND model = ?2
+             ORDER BY recorded_at DESC LIMIT 1",
+            params![account_id, quota.label],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, f64>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
+                    row.get::<_, Option<i64>>(4)?,
+                ))
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    Ok(last.is_some_and(|(at, fraction, reset, prompt, flow)| {
+        now - at < MIN_UNCHANGED_INTERVAL_SECS
+            && (fraction - quota.remaining_fraction).abs() < 1e-6
+            && reset == quota.reset_time
+            && prompt == credits.prompt_credits
+            && flow == credits.flow_credits
+    }))
+}
+
+/// 记录一组配额样本
+///
+/// # 返回
+/// - 实际写入的样本数（与最新样本相同的会被跳过）
+pub fn record(
+    account_id: &str,
+    quotas: &[ModelQuota],
+    credits: &Credits,
+    source: SampleSource,
+) -> Result<usize, String> {
+    if quotas.is_empty() {
+        return Ok(0);
+    }
+
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let mut conn = open()?;
+    let now = chrono::Utc::now().timestamp();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut inserted = 0;
+    for quota in quotas {
+        if is_duplicate(&tx, account_id, quota, credits, now)? {
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO quota_samples
+             (account_id, model, recorded_at, remaining_fraction, reset_time, prompt_credits, flow_credits, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                account_id,
+                quota.label,
+                now,
+                quota.remaining_fraction,
+                quota.reset_time,
+                credits.prompt_credits,
+                credits.flow_credits,
+                source.as_str()
+            ],
+        )
+        .map_err(|e| format!("写入配额样本失败: {}", e))?;
+        inserted += 1;
+    }
+
+    let cutoff = now - RETENTION_DAYS * 86400;
+    tx.execute("DELETE FROM quota_samples WHERE recorded_at < ?1", [cutoff])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    if inserted > 0 {
+        tracing::debug!(target: "quota::history", inserted, source = source.as_str(), "已记录配额样本");
+    }
+    Ok(inserted)
+}
+
+/// 从认证信息（antigravityAuthStatus）记录配额样本
+pub fn record_from_auth(account_id: &str, auth: &Value, source: SampleSource) -> Result<usize, String> {
+    let quotas = user_status::model_quotas_from_auth(auth);
+    let credits = user_status::credits_from_auth(auth);
+    record(account_id, &quotas, &credits, source)
+}
+
+/// 按认证信息中的邮箱查找已备份账户并记录配额
+///
+/// 账户尚未备份（没有账户 ID）或保险库未解锁时不记录。
+///
+/// # 返回
+/// - `Ok(Some((account_id, inserted)))`: 已记录；`Ok(None)`: 未找到对应账户
+pub fn record_for_current_auth(auth: &Value, source: SampleSource) -> Result<Option<(String, usize)>, String> {
+    let Some(email) = auth.get("email").and_then(|v| v.as_str()) else {
+        return Ok(None);
+    };
+    // 保险库锁定时无法读取邮箱索引，跳过本次记录
+    if crate::antigravity::vault::vault().ensure_unlocked().is_err() {
+        return Ok(None);
+    }
+    let index = account_index::load_or_rebuild()?;
+    let Some(summary) = index.find_by_email(email) else {
+        return Ok(None);
+    };
+    let inserted = record_from_auth(&summary.id, auth, source)?;
+    Ok(Some((summary.id.clone(), inserted)))
+}
+
+/// 查询账户的配额历史（按时间升序）
+pub fn history(
+    account_id: &str,
+    model: Option<&str>,
+    since: Option<i64>,
+    limit: usize,
+) -> Result<Vec<QuotaSample>, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let conn = open()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, recorded_at, remaining_fraction, reset_time, prompt_credits, flow_credits, source
+             FROM (
+                 SELECT * FROM quota_samples
+                 WHERE account_id = ?1 AND (?2 IS NULL OR model = ?2) AND recorded_at >= ?3
+                 ORDER BY recorded_at DESC LIMIT ?4
+             ) ORDER BY recorded_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(
+            params![account_id, model, since.unwrap_or(0), limit as i64],
+            |row| {
+                Ok(QuotaSample {
+                    model: row.get(0)?,
+                    recorded_at: format_time(row.get(1)?),
+                    remaining_fraction: row.get(2)?,
+                    reset_time: row.get::<_, Option<i64>>(3)?.map(format_time),
+                    prompt_credits: row.get(4)?,
+                    flow_credits: row.get(5)?,
+                    source: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("读取配额历史失败: {}", e))
+}
+
+/// 预测账户各模型的下次重置时间
+pub fn predict_resets(account_id: &str) -> Result<Vec<ResetPrediction>, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let conn = open()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, recorded_at, remaining_fraction, reset_time
+             FROM quota_samples WHERE account_id = ?1
+             ORDER BY model, recorded_at ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([account_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                RawSample {
+                    recorded_at: row.get(1)?,
+                    remaining_fraction: row.get(2)?,
+                    reset_time: row.get(3)?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("读取配额历史失败: {}", e))?;
+
+    let mut by_model: Vec<(String, Vec<RawSample>)> = Vec::new();
+    for (model, sample) in rows {
+        match by_model.last_mut() {
+            Some((current, samples)) if *current == model => samples.push(sample),
+            _ => by_model.push((model, vec![sample])),
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    Ok(by_model
+        .into_iter()
+        .filter_map(|(model, samples)| predict_model_reset(model, &samples, now))
+        .collect())
+}
+
+/// 根据单个模型的样本（时间升序）预测下次重置
+///
+/// 1. 最新样本报告的重置时间仍在未来时直接采用；
+/// 2. 否则以剩余比例明显回升的时刻作为重置点，取相邻重置点间隔的中位数作为周期，
+///    从最后一次重置起推算第一个晚于当前时间的时刻；
+/// 3. 配额已满且无法推算时视为没有待发生的重置。
+fn predict_model_reset(model: String, samples: &[RawSample], now: i64) -> Option<ResetPrediction> {
+    let latest = samples.last()?;
+
+    let resets: Vec<i64> = samples
+        .windows(2)
+        .filter(|pair| pair[1].remaining_fraction - pair[0].remaining_fraction >= RESET_JUMP_THRESHOLD)
+        .map(|pair| pair[1].recorded_at)
+        .collect();
+    let mut intervals: Vec<i64> = resets.windows(2).map(|pair| pair[1] - pair[0]).collect();
+    intervals.sort_unstable();
+    let cycle = intervals.get(intervals.len() / 2).copied().filter(|c| *c > 0);
+
+    let (predicted, basis) = match latest.reset_time {
+        Some(reset) if reset > now => (Some(reset), PredictionBasis::Reported),
+        _ => match (cycle, resets.last()) {
+            (Some(cycle), Some(&last_reset)) => {
+                let elapsed_cycles = (now - last_reset) / cycle + 1;
+                (Some(last_reset + elapsed_cycles * cycle), PredictionBasis::ObservedCycle)
+            }
+            _ if latest.remaining_fraction >= 1.0 => (None, PredictionBasis::Full),
+            _ => (None, PredictionBasis::Unknown),
+        },
+    };
+
+    Some(ResetPrediction {
+        model,
+        remaining_fraction: latest.remaining_fraction,
+        last_recorded_at: format_time(latest.recorded_at),
+        predicted_reset: predicted.map(format_time),
+        basis,
+        observed_cycle_secs: cycle,
+    })
+}
+
+/// 删除账户的全部配额历史
+pub fn delete_account(account_id: &str) -> Result<usize, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    if !db_path()?.exists() {
+        return Ok(0);
+    }
+    let conn = open()?;
+    conn.execute("DELETE FROM quota_samples WHERE account_id = ?1", [account_id])
+        .map_err(|e| format!("删除配额历史失败: {}", e))
+}
+
+/// 清空全部配额历史
+pub fn clear_all() -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().unwrap();
+    let path = db_path()?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("删除配额历史数据库失败: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(recorded_at: i64, remaining_fraction: f64, reset_time: Option<i64>) -> RawSample {
+        RawSample {
+            recorded_at,
+            remaining_fraction,
+            reset_time,
+        }
+    }
+
+    #[test]
+    fn test_predict_uses_reported_reset_time() {
+        let samples = [sample(100, 0.5, Some(5000))];
+        let prediction = predict_model_reset("m".into(), &samples, 1000).unwrap();
+        assert_eq!(prediction.basis, PredictionBasis::Reported);
+        assert_eq!(prediction.predicted_reset, Some(format_time(5000)));
+    }
+
+    #[test]
+    fn test_predict_from_observed_cycle() {
+        // 在 1000 与 19000 处观察到两次回升，周期 18000 秒
+        let samples = [
+            sample(0, 0.2, None),
+            sample(1000, 1.0, None),
+            sample(10000, 0.1, None),
+            sample(19000, 1.0, None),
+            sample(25000, 0.4, None),
+        ];
+        let prediction = predict_model_reset("m".into(), &samples, 30000).unwrap();
+        assert_eq!(prediction.basis, PredictionBasis::ObservedCycle);
+        assert_eq!(prediction.observed_cycle_secs, Some(18000));
+        assert_eq!(prediction.predicted_reset, Some(format_time(37000)));
+    }
+
+    #[test]
+    fn test_predict_full_and_unknown() {
+        let full = predict_model_reset("m".into(), &[sample(0, 1.0, Some(10))], 100).unwrap();
+        assert_eq!(full.basis, PredictionBasis::Full);
+        assert!(full.predicted_reset.is_none());
+
+        let unknown = predict_model_reset("m".into(), &[sample(0, 0.3, None)], 100).unwrap();
+        assert_eq!(unknown.basis, PredictionBasis::Unknown);
+    }
+}
@@ -5,12 +5,17 @@
 use rusqlite::{params, Connection, OptionalExtension};
 use serde_json::{json, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // 导入相关模块
 use crate::constants::database;
 use crate::platform;
 use crate::antigravity::backup::read_backup_file;
+use crate::antigravity::key_groups::{self, KeyGroup};
+use crate::antigravity::key_sets::{self, KeySet};
+use crate::antigravity::db_snapshots::{self, SnapshotReason};
+use crate::antigravity::secret_storage::{self, BackupOrigin, SecretRestorePolicy};
+use crate::antigravity::user_files;
 
 /// 从备份的 Marker 中获取 Key 对应的 flag (0 或 1)
 /// 如果找不到，回退到安全默认值
@@ -38,6 +43,50 @@ fn get_marker_flag_from_backup(backup_marker: &Option<&Value>, key: &str) -> i32
     default
 }
 
+/// 计算恢复后的 Marker：在当前 Marker 基础上写入已恢复字段的同步标记
+///
+/// 实际恢复与切换预览共用此逻辑，保证预览结果与恢复结果一致。
+///
+/// # 参数
+/// - `current_marker`: 数据库中现有的 Marker（JSON 字符串）
+/// - `backup_data`: 备份数据（其中的 Marker 作为参考源）
+/// - `restored_keys`: 本次恢复的字段
+pub fn merge_marker(
+    current_marker: Option<&str>,
+    backup_data: &Value,
+    restored_keys: &[&str],
+) -> serde_json::Map<String, Value> {
+    let mut current_marker_obj = match current_marker {
+        Some(s) => {
+            tracing::debug!(target: "restore::marker", "读取到现有 Marker");
+            serde_json::from_str::<serde_json::Map<String, Value>>(s).unwrap_or_default()
+        }
+        None => {
+            tracing::debug!(target: "restore::marker", "未找到现有 Marker，创建新的");
+            serde_json::Map::new()
+        }
+    };
+
+    tracing::debug!(target: "restore::marker", marker_fields_before = %current_marker_obj.len(), "合并前 Marker 状态");
+
+    // 获取备份文件中的 Marker（作为参考源）
+    let backup_marker = backup_data.get(database::TARGET_STORAGE_MARKER);
+    if backup_marker.is_some() {
+        tracing::debug!(target: "restore::marker", "从备份文件中读取到完整 Marker，将使用其中的值作为参考");
+    } else {
+        tracing::warn!(target: "restore::marker", "备份文件中没有 Marker，将使用默认值");
+    }
+
+    for key in restored_keys {
+        // 关键：从备份里读取它是 0 还是 1，而不是瞎猜
+        let flag = get_marker_flag_from_backup(&backup_marker, key);
+        current_marker_obj.insert(key.to_string(), json!(flag));
+    }
+
+    tracing::debug!(target: "restore::marker", marker_fields_after = %current_marker_obj.len(), "合并后 Marker 状态");
+    current_marker_obj
+}
+
 /// 通用数据库恢复方法（终极版 - 从备份 Marker 读取值）
 ///
 /// 执行精确的数据库恢复操作：
@@ -50,6 +99,8 @@ fn get_marker_flag_from_backup(backup_marker: &Option<&Value>, key: &str) -> i32
 /// - `db_path`: 数据库文件路径
 /// - `db_name`: 数据库名称（用于日志显示）
 /// - `backup_data`: 备份数据的 JSON 对象
+/// - `keys_to_restore`: 需要恢复的字段（按所选分组确定）
+/// - `key_set`: 字段定义（决定字段是否参与 Marker 合并）
 ///
 /// # 返回
 /// - `Ok(restored_count)`: 成功恢复的项目数量
@@ -58,19 +109,18 @@ fn restore_database(
     db_path: &PathBuf,
     db_name: &str,
     backup_data: &Value,
+    keys_to_restore: &[String],
+    key_set: &KeySet,
 ) -> Result<usize, String> {
     tracing::info!(target: "restore::database", db_name = %db_name, "开始恢复数据库");
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
 
-    // 使用常量定义需要恢复的字段列表（与备份列表一致）
-    let keys_to_restore = database::ALL_KEYS;
-
     let mut restored_count = 0;
     let mut restored_keys = Vec::new();
 
     // 1. 插入数据（Value 直接使用备份中的原始字符串）
     for key in keys_to_restore {
-        if let Some(val) = backup_data.get(*key) {
+        if let Some(val) = backup_data.get(key) {
             if let Some(val_str) = val.as_str() {
                 match conn.execute(
                     "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
@@ -79,9 +129,9 @@ fn restore_database(
                     Ok(_) => {
                         tracing::debug!(target: "restore::database", key = %key, "注入数据成功");
                         restored_count += 1;
-                        // 只有非特殊字段才需要在 Marker 中注册
-                        if key != &database::NEW_STORAGE_MARKER {
-                            restored_keys.push(key);
+                        // 只有参与同步的字段才需要在 Marker 中注册（存储标记、通知等除外）
+                        if key_set.participates_in_marker(key) {
+                            restored_keys.push(key.as_str());
                         }
                     }
                     Err(e) => {
@@ -96,42 +146,7 @@ fn restore_database(
         }
     }
 
-    // 2. 恢复通知字段（避免历史通知重复弹窗）
-    // if let Some(notification_keys_value) = backup_data.get("notification_keys") {
-    //     if let Some(notification_keys) = notification_keys_value.as_array() {
-    //         if !notification_keys.is_empty() {
-    //             tracing::debug!(target: "restore::database", notification_count = %notification_keys.len(), "开始恢复通知字段");
-    //             let mut notification_count = 0;
-    //
-    //             for notification_key_value in notification_keys {
-    //                 if let Some(notification_key) = notification_key_value.as_str() {
-    //                     // 查找对应的通知数据
-    //                     if let Some(notification_data) = backup_data.get(notification_key) {
-    //                         if let Some(notification_str) = notification_data.as_str() {
-    //                             match conn.execute(
-    //                                 "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
-    //                                 params![notification_key, notification_str],
-    //                             ) {
-    //                                 Ok(_) => {
-    //                                     tracing::debug!(target: "restore::database", key = %notification_key, "恢复通知成功");
-    //                                     notification_count += 1;
-    //                                     // 通知字段不添加到 restored_keys 中，因为它们通常不需要参与 Marker 同步
-    //                                 }
-    //                                 Err(e) => {
-    //                                     tracing::error!(target: "restore::database", key = %notification_key, error = %e, "恢复通知失败");
-    //                                 }
-    //                             }
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //
-    //             tracing::info!(target: "restore::database", notification_count = %notification_count, "成功恢复通知字段");
-    //         }
-    //     }
-    // }
-
-    // 3. 智能合并 Marker
+    // 2. 智能合并 Marker
     if !restored_keys.is_empty() {
         tracing::debug!(target: "restore::marker", "开始智能合并 Marker");
 
@@ -148,35 +163,9 @@ fn restore_database(
             .optional()
             .unwrap_or(None);
 
-        let mut current_marker_obj = match current_marker_str {
-            Some(s) => {
-                tracing::debug!(target: "restore::marker", "读取到现有 Marker");
-                serde_json::from_str::<serde_json::Map<String, Value>>(&s).unwrap_or_default()
-            }
-            None => {
-                tracing::debug!(target: "restore::marker", "未找到现有 Marker，创建新的");
-                serde_json::Map::new()
-            }
-        };
-
-        tracing::debug!(target: "restore::marker", marker_fields_before = %current_marker_obj.len(), "合并前 Marker 状态");
-
-        // B. 获取备份文件中的 Marker（作为参考源）
-        let backup_marker = backup_data.get("__$__targetStorageMarker");
-        if backup_marker.is_some() {
-            tracing::debug!(target: "restore::marker", "从备份文件中读取到完整 Marker，将使用其中的值作为参考");
-        } else {
-            tracing::warn!(target: "restore::marker", "备份文件中没有 Marker，将使用默认值");
-        }
-
-        // C. 将已恢复 Key 的 Marker 状态合并进去
-        for key in &restored_keys {
-            // 关键：从备份里读取它是 0 还是 1，而不是瞎猜
-            let flag = get_marker_flag_from_backup(&backup_marker, key);
-            current_marker_obj.insert(key.to_string(), json!(flag));
-        }
-
-        tracing::debug!(target: "restore::marker", marker_fields_after = %current_marker_obj.len(), "合并后 Marker 状态");
+        // B-C. 将已恢复 Key 的 Marker 状态（取自备份中的 Marker）合并进去
+        let current_marker_obj =
+            merge_marker(current_marker_str.as_deref(), backup_data, &restored_keys);
 
         // D. 写回 Marker
         let new_marker_str = serde_json::to_string(&current_marker_obj)
@@ -221,6 +210,22 @@ fn restore_database(
 /// - `Ok(message)`: 成功消息
 /// - `Err(message)`: 错误信息
 pub async fn restore_all_antigravity_data(backup_file_path: PathBuf) -> Result<String, String> {
+    restore_antigravity_data_with_groups(backup_file_path, key_groups::ALL_GROUPS, SecretRestorePolicy::Auto).await
+}
+
+/// 按所选字段分组恢复 Antigravity 数据
+///
+/// 未选中的分组保持数据库现有值不变，Marker 只合并实际恢复的字段。
+///
+/// # 参数
+/// - `backup_file_path`: 备份文件的完整路径
+/// - `groups`: 需要恢复的字段分组
+/// - `secrets`: 扩展密钥（secret://）的恢复策略
+pub async fn restore_antigravity_data_with_groups(
+    backup_file_path: PathBuf,
+    groups: &[KeyGroup],
+    secrets: SecretRestorePolicy,
+) -> Result<String, String> {
     println!("🚀 开始执行智能恢复（支持加密备份）...");
     println!("📂 备份文件: {}", backup_file_path.display());
 
@@ -233,6 +238,39 @@ pub async fn restore_all_antigravity_data(backup_file_path: PathBuf) -> Result<S
 
     println!("✅ 备份文件读取成功（已自动解密）");
 
+    restore_backup_data(&backup_data, groups, secrets)
+}
+
+/// 将已解密的备份数据写入主库与备份库
+///
+/// # 参数
+/// - `backup_data`: 备份数据（字段原始字符串及 Marker）
+/// - `groups`: 需要恢复的字段分组（字段归属由当前字段定义决定）
+/// - `secrets`: 扩展密钥（secret://）的恢复策略
+pub fn restore_backup_data(
+    backup_data: &Value,
+    groups: &[KeyGroup],
+    secrets: SecretRestorePolicy,
+) -> Result<String, String> {
+    if groups.is_empty() {
+        return Err("至少需要选择一个恢复分组".to_string());
+    }
+
+    // 写入前保存整库安全快照，恢复出错时可回滚
+    db_snapshots::take(SnapshotReason::Restore)
+        .map_err(|e| format!("创建数据库安全快照失败，已取消恢复: {}", e))?;
+
+    let key_set = key_sets::current();
+    let mut keys = key_set.restore_keys(backup_data, groups);
+    // 扩展密钥由系统钥匙串加密，来自其他设备时按策略跳过
+    let origin = secret_storage::origin(backup_data);
+    let skipped_secrets = secret_storage::apply_policy(&mut keys, origin, secrets);
+    let foreign_secrets = origin == BackupOrigin::OtherMachine
+        && keys.iter().any(|key| secret_storage::is_secret_key(key));
+    if !skipped_secrets.is_empty() {
+        tracing::info!(target: "restore::database", count = skipped_secrets.len(), "已跳过扩展密钥");
+    }
+    tracing::debug!(target: "restore::database", count = keys.len(), "按字段定义确定恢复字段");
     let app_data = match platform::get_antigravity_db_path() {
         Some(p) => p,
         None => {
@@ -251,25 +289,58 @@ pub async fn restore_all_antigravity_data(backup_file_path: PathBuf) -> Result<S
 
     let mut msg = String::new();
 
+    // 先替换账户文件（数据库位于 User/globalStorage 下），主库恢复失败时回滚
+    let applied_files = match app_data.parent().and_then(Path::parent) {
+        Some(user_dir) => Some(user_files::prepare(user_dir, backup_data, groups)?)
+            .filter(|prepared| !prepared.is_empty())
+            .map(|prepared| prepared.apply())
+            .transpose()?,
+        None => None,
+    };
+
     // 恢复主库
     println!("📊 步骤1: 恢复 state.vscdb 数据库");
-    match restore_database(&app_data, "state.vscdb", &backup_data) {
+    match restore_database(&app_data, "state.vscdb", backup_data, &keys, &key_set) {
         Ok(count) => {
             let status = format!("主库恢复 {} 项", count);
             println!("  ✅ {}", status);
             msg.push_str(&status);
         }
-        Err(e) => return Err(e),
+        Err(e) => {
+            if let Some(applied) = applied_files {
+                applied.rollback();
+            }
+            return Err(e);
+        }
+    }
+
+    if let Some(applied) = applied_files {
+        msg.push_str(&format!("; 账户文件恢复 {} 项", applied.count()));
+        applied.commit();
+    }
+
+    if !skipped_secrets.is_empty() {
+        msg.push_str(&format!("; 已跳过 {} 个扩展密钥", skipped_secrets.len()));
+    } else if foreign_secrets {
+        tracing::warn!(target: "restore::database", "已恢复来自其他设备的扩展密钥，可能无法解密");
+        msg.push_str("; 扩展密钥来自其他设备，可能无法解密，相关扩展需重新登录");
     }
 
     // 恢复备份库（如果有）
     println!("💾 步骤2: 恢复 state.vscdb.backup");
     let backup_db = app_data.with_extension("vscdb.backup");
     if backup_db.exists() {
-        if let Ok(count) = restore_database(&backup_db, "state.vscdb.backup", &backup_data) {
-            let status = format!("; 备份库恢复 {} 项", count);
-            println!("  ✅ {}", status);
-            msg.push_str(&status);
+        match restore_database(&backup_db, "state.vscdb.backup", backup_data, &keys, &key_set) {
+            Ok(count) => {
+                let status = format!("; 备份库恢复 {} 项", count);
+                println!("  ✅ {}", status);
+                msg.push_str(&status);
+            }
+            Err(e) => {
+                // 主库已恢复，不中断；两库不一致可通过一致性检查同步
+                tracing::warn!(target: "restore::database", error = %e, "备份库恢复失败");
+                msg.push_str("; 备份库恢复失败，建议执行数据库一致性检查");
+            }
         }
     } else {
         println!("  ℹ️ 备份数据库不存在，跳过");
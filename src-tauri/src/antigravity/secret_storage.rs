@@ -0,0 +1,151 @@
+// Antigravity 扩展密钥模块
+// Antigravity 基于 VS Code，扩展通过 SecretStorage 保存的密钥以 `secret://…` 字段存放在 ItemTable 中，
+// 值由 Electron safeStorage（系统钥匙串）加密，换机或换系统用户后无法解密。
+//
+// 备份时记录本机指纹（`backup_machine`），恢复时据此判断密钥能否在本机使用，
+// 并按设置决定是否跳过这些字段；导入、导出时提示不可迁移的密钥。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+
+use crate::utils::crypto;
+
+/// 扩展密钥字段前缀
+pub const SECRET_KEY_PREFIX: &str = "secret://";
+
+/// 备份中记录本机指纹的字段名
+pub const BACKUP_MACHINE_KEY: &str = "backup_machine";
+
+/// 恢复扩展密钥的策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretRestorePolicy {
+    /// 备份来自其他设备时跳过，来自本机或无法确定时恢复
+    #[default]
+    Auto,
+    /// 始终恢复
+    Include,
+    /// 始终跳过
+    Exclude,
+}
+
+/// 备份的来源设备
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupOrigin {
+    ThisMachine,
+    OtherMachine,
+    /// 旧版备份未记录来源，或无法获取本机标识
+    Unknown,
+}
+
+/// 备份中扩展密钥的可迁移情况
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretReport {
+    pub origin: BackupOrigin,
+    /// 扩展密钥字段
+    pub secret_keys: Vec<String>,
+    /// 密钥在本机是否可能无法解密
+    pub at_risk: bool,
+}
+
+/// 字段是否为扩展密钥
+pub fn is_secret_key(key: &str) -> bool {
+    key.starts_with(SECRET_KEY_PREFIX)
+}
+
+/// 本机指纹（获取失败时为空，会话内缓存）
+pub fn current_machine() -> Option<&'static str> {
+    static MACHINE: OnceLock<Option<String>> = OnceLock::new();
+    MACHINE
+        .get_or_init(|| match crypto::machine_fingerprint() {
+            Ok(fingerprint) => Some(fingerprint),
+            Err(e) => {
+                tracing::warn!(target: "backup::secrets", error = %e, "无法获取本机标识");
+                None
+            }
+        })
+        .as_deref()
+}
+
+/// 备份数据中的扩展密钥字段（按名称排序）
+pub fn secret_keys(backup: &Value) -> Vec<String> {
+    let mut keys: Vec<String> = backup
+        .as_object()
+        .map(|obj| obj.keys().filter(|key| is_secret_key(key)).cloned().collect())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+fn origin_for(backup: &Value, machine: Option<&str>) -> BackupOrigin {
+    match (backup.get(BACKUP_MACHINE_KEY).and_then(|v| v.as_str()), machine) {
+        (Some(source), Some(current)) if source == current => BackupOrigin::ThisMachine,
+        (Some(_), Some(_)) => BackupOrigin::OtherMachine,
+        _ => BackupOrigin::Unknown,
+    }
+}
+
+/// 备份的来源设备
+pub fn origin(backup: &Value) -> BackupOrigin {
+    origin_for(backup, current_machine())
+}
+
+/// 检查备份中的扩展密钥能否在本机使用
+pub fn inspect(backup: &Value) -> SecretReport {
+    let secret_keys = secret_keys(backup);
+    let origin = origin(backup);
+    SecretReport {
+        at_risk: !secret_keys.is_empty() && origin == BackupOrigin::OtherMachine,
+        origin,
+        secret_keys,
+    }
+}
+
+/// 按策略从恢复字段中去除扩展密钥，返回去除的字段
+pub fn apply_policy(keys: &mut Vec<String>, origin: BackupOrigin, policy: SecretRestorePolicy) -> Vec<String> {
+    let exclude = match policy {
+        SecretRestorePolicy::Include => false,
+        SecretRestorePolicy::Exclude => true,
+        SecretRestorePolicy::Auto => origin == BackupOrigin::OtherMachine,
+    };
+    if !exclude {
+        return Vec::new();
+    }
+    let (excluded, kept): (Vec<String>, Vec<String>) = keys.drain(..).partition(|key| is_secret_key(key));
+    *keys = kept;
+    excluded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_origin_and_policy() {
+        let backup = json!({
+            "antigravityAuthStatus": "{}",
+            "secret://{\"extensionId\":\"a.b\",\"key\":\"token\"}": "encrypted",
+            BACKUP_MACHINE_KEY: "m1",
+        });
+        assert_eq!(secret_keys(&backup).len(), 1);
+        assert_eq!(origin_for(&backup, Some("m1")), BackupOrigin::ThisMachine);
+        assert_eq!(origin_for(&backup, Some("m2")), BackupOrigin::OtherMachine);
+        assert_eq!(origin_for(&json!({}), Some("m1")), BackupOrigin::Unknown);
+
+        let all: Vec<String> = backup.as_object().unwrap().keys().cloned().collect();
+        let mut keys = all.clone();
+        assert!(apply_policy(&mut keys, BackupOrigin::ThisMachine, SecretRestorePolicy::Auto).is_empty());
+        assert_eq!(keys, all);
+
+        let excluded = apply_policy(&mut keys, BackupOrigin::OtherMachine, SecretRestorePolicy::Auto);
+        assert_eq!(excluded.len(), 1);
+        assert!(!keys.iter().any(|key| is_secret_key(key)));
+
+        let mut keys = all.clone();
+        assert!(apply_policy(&mut keys, BackupOrigin::OtherMachine, SecretRestorePolicy::Include).is_empty());
+        assert_eq!(apply_policy(&mut keys, BackupOrigin::Unknown, SecretRestorePolicy::Exclude).len(), 1);
+    }
+}
@@ -0,0 +1,557 @@
+// Antigravity 去重快照存储模块
+// 每个 ItemTable 值只以加密的内容寻址 blob 形式存储一次，快照只是“键 -> 哈希”的清单
+// 多次快照之间未变化的值（设置、命令配置、对话索引等）不再重复占用磁盘
+//
+// 目录结构（位于备份目录下）：
+//   store/blobs/<账户ID>/<前两位>/<哈希>.enc    - 加密的值
+//   store/snapshots/<账户ID>/<快照ID>.enc       - 加密的历史快照清单
+// 账户备份文件 <账户ID>.enc 本身保存最新一次快照的清单
+//
+// 所有文件都使用账户自己的数据密钥加密（见 account_keys），因此去重范围限定在单个账户内，
+// 销毁账户密钥即可使其全部快照不可恢复。内容哈希同样以数据密钥为盐，不能用于猜测明文。
+//
+// 早期版本的清单没有 account_id 字段，其 blob 位于所有账户共享的 store/blobs/<前两位>/ 下、
+// 由保险库密钥加密。这类清单仍可读取，垃圾回收在不再被引用后清理其 blob；
+// 账户下次备份时即以新格式写入。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+use crate::antigravity::{account_index, account_keys};
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{random_bytes, secure_create_dir, secure_write_file};
+
+/// 清单格式标识
+const MANIFEST_FORMAT: &str = "agstore-manifest";
+
+/// 当前清单格式版本
+const MANIFEST_VERSION: u32 = 1;
+
+/// 每个账户保留的历史快照数量
+pub const MAX_SNAPSHOTS_PER_ACCOUNT: usize = 20;
+
+/// 直接保存在清单中的元信息字段（不拆分为 blob）
+const INLINE_META_KEYS: &[&str] = &["account_email", "backup_time"];
+
+/// 串行化写入与垃圾回收，避免回收正在写入的 blob
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 快照清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    format: String,
+    version: u32,
+    /// 所属账户 ID（决定数据块目录与数据密钥），早期共享存储格式的清单为空
+    #[serde(default)]
+    pub account_id: String,
+    pub snapshot_id: String,
+    pub created_at: String,
+    /// 内联元信息（邮箱、备份时间）
+    #[serde(default)]
+    pub meta: Map<String, Value>,
+    /// 键 -> 内容哈希
+    pub entries: BTreeMap<String, String>,
+}
+
+/// 快照信息（供前端展示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub snapshot_id: String,
+    pub created_at: String,
+    pub key_count: usize,
+}
+
+/// 垃圾回收结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GcReport {
+    pub removed_blobs: usize,
+    pub freed_bytes: u64,
+    pub remaining_blobs: usize,
+    /// 无法读取而跳过的清单数量（其所属账户的数据块本次不回收）
+    #[serde(default)]
+    pub skipped_manifests: usize,
+}
+
+/// 存储统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreStats {
+    pub blob_count: usize,
+    pub blob_bytes: u64,
+    pub snapshot_count: usize,
+}
+
+fn store_dir() -> Result<PathBuf, String> {
+    AppPaths::backup_dir()
+        .map(|dir| dir.join("store"))
+        .ok_or_else(|| "无法获取备份目录".to_string())
+}
+
+/// 数据块路径（account_id 为空时为早期共享存储中的路径）
+fn blob_path(store: &Path, account_id: &str, hash: &str) -> PathBuf {
+    let mut path = store.join("blobs");
+    if !account_id.is_empty() {
+        path.push(account_id);
+    }
+    path.join(&hash[..2]).join(format!("{}.enc", hash))
+}
+
+/// 判断 blobs 下的子目录是否为早期共享存储的分片目录（账户 ID 为 32 位，分片为 2 位）
+fn is_legacy_shard(dir: &Path) -> bool {
+    dir.file_name().is_some_and(|n| n.len() == 2)
+}
+
+/// 列出全部数据块及其所属账户 ID（早期共享存储中的数据块账户 ID 为空）
+fn list_blobs(store: &Path) -> Vec<(String, PathBuf)> {
+    let mut blobs = Vec::new();
+    for dir in list_subdirs(&store.join("blobs")) {
+        if is_legacy_shard(&dir) {
+            blobs.extend(list_enc_files(&dir).into_iter().map(|blob| (String::new(), blob)));
+            continue;
+        }
+        let account_id = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        for shard in list_subdirs(&dir) {
+            blobs.extend(list_enc_files(&shard).into_iter().map(|blob| (account_id.clone(), blob)));
+        }
+    }
+    blobs
+}
+
+fn snapshots_dir(store: &Path, account_id: &str) -> PathBuf {
+    store.join("snapshots").join(account_id)
+}
+
+/// 判断备份内容是否为快照清单
+pub fn is_manifest(data: &Value) -> bool {
+    data.get("format").and_then(|v| v.as_str()) == Some(MANIFEST_FORMAT)
+}
+
+/// 由账户数据密钥派生内容哈希密钥
+fn hash_key(account_id: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let data_key = account_keys::data_key(account_id)?;
+    let mut hasher = Sha256::new();
+    hasher.update(b"agstore-content-hash");
+    hasher.update(*data_key);
+    Ok(Zeroizing::new(hasher.finalize().into()))
+}
+
+/// 计算带密钥的内容哈希
+fn content_hash(key: &[u8], content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(content);
+    hex::encode(hasher.finalize())
+}
+
+/// 将备份数据写入存储并生成一个新快照
+///
+/// 已存在的 blob 不会重复写入。清单同时保存到该账户的快照历史中。
+pub fn write_snapshot(account_id: &str, data: &Map<String, Value>) -> Result<SnapshotManifest, String> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let store = store_dir()?;
+    let key = hash_key(account_id)?;
+
+    let mut entries = BTreeMap::new();
+    let mut meta = Map::new();
+    let mut new_blobs = 0;
+
+    for (name, value) in data {
+        if INLINE_META_KEYS.contains(&name.as_str()) {
+            meta.insert(name.clone(), value.clone());
+            continue;
+        }
+
+        let content = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+        let hash = content_hash(&*key, &content);
+        let path = blob_path(&store, account_id, &hash);
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                secure_create_dir(parent).map_err(|e| e.to_string())?;
+            }
+            let encrypted = account_keys::encrypt(account_id, &content)?;
+            secure_write_file(&path, &encrypted).map_err(|e| e.to_string())?;
+            new_blobs += 1;
+        }
+        entries.insert(name.clone(), hash);
+    }
+
+    let now = chrono::Local::now();
+    let manifest = SnapshotManifest {
+        format: MANIFEST_FORMAT.to_string(),
+        version: MANIFEST_VERSION,
+        account_id: account_id.to_string(),
+        snapshot_id: format!("{}-{}", now.format("%Y%m%dT%H%M%S%3f"), hex::encode(random_bytes::<2>())),
+        created_at: now.to_rfc3339(),
+        meta,
+        entries,
+    };
+
+    let dir = snapshots_dir(&store, account_id);
+    secure_create_dir(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.enc", manifest.snapshot_id));
+    secure_write_file(&path, &encrypt_manifest(&manifest)?).map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        target: "backup::store",
+        snapshot_id = %manifest.snapshot_id,
+        key_count = manifest.entries.len(),
+        new_blobs = new_blobs,
+        "快照已写入"
+    );
+    Ok(manifest)
+}
+
+/// 序列化并使用账户数据密钥加密清单
+pub fn encrypt_manifest(manifest: &SnapshotManifest) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?;
+    account_keys::encrypt(&manifest.account_id, &json)
+}
+
+/// 将清单展开为完整的备份数据（与旧版整文件备份格式一致）
+pub fn expand_manifest(data: Value) -> Result<Value, String> {
+    let manifest: SnapshotManifest =
+        serde_json::from_value(data).map_err(|e| format!("快照清单解析失败: {}", e))?;
+    if manifest.version > MANIFEST_VERSION {
+        return Err(format!("不支持的快照清单版本: {}", manifest.version));
+    }
+
+    let store = store_dir()?;
+    let mut result = manifest.meta;
+    for (name, hash) in &manifest.entries {
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("快照数据 {} 的哈希无效", name));
+        }
+        let content = fs::read(blob_path(&store, &manifest.account_id, hash))
+            .map_err(|e| format!("读取快照数据 {} 失败: {}", name, e))?;
+        let decrypted = Zeroizing::new(account_keys::decrypt(&content)?);
+        let value: Value = serde_json::from_slice(&decrypted)
+            .map_err(|e| format!("快照数据 {} 解析失败: {}", name, e))?;
+        result.insert(name.clone(), value);
+    }
+    Ok(Value::Object(result))
+}
+
+/// 读取并解密清单文件
+fn read_manifest(path: &Path) -> Result<SnapshotManifest, String> {
+    let content = fs::read(path).map_err(|e| format!("读取快照清单失败: {}", e))?;
+    let decrypted = Zeroizing::new(account_keys::decrypt(&content)?);
+    serde_json::from_slice(&decrypted).map_err(|e| format!("快照清单解析失败: {}", e))
+}
+
+/// 列出账户的历史快照（最新的在前）
+pub fn list_snapshots(account_id: &str) -> Result<Vec<SnapshotInfo>, String> {
+    let dir = snapshots_dir(&store_dir()?, account_id);
+    let mut snapshots: Vec<SnapshotInfo> = list_enc_files(&dir)
+        .iter()
+        .filter_map(|path| match read_manifest(path) {
+            Ok(m) => Some(SnapshotInfo {
+                snapshot_id: m.snapshot_id,
+                created_at: m.created_at,
+                key_count: m.entries.len(),
+            }),
+            Err(e) => {
+                tracing::warn!(target: "backup::store", file = %path.display(), error = %e, "跳过无法读取的快照");
+                None
+            }
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.snapshot_id.cmp(&a.snapshot_id));
+    Ok(snapshots)
+}
+
+/// 获取历史快照清单文件路径
+pub fn snapshot_path(account_id: &str, snapshot_id: &str) -> Result<PathBuf, String> {
+    // 快照 ID 只允许字母数字和连字符，防止路径穿越
+    if snapshot_id.is_empty() || !snapshot_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("无效的快照 ID: {}", snapshot_id));
+    }
+    let path = snapshots_dir(&store_dir()?, account_id).join(format!("{}.enc", snapshot_id));
+    if !path.exists() {
+        return Err(format!("快照不存在: {}", snapshot_id));
+    }
+    Ok(path)
+}
+
+/// 删除单个历史快照（不立即回收 blob）
+pub fn delete_snapshot(account_id: &str, snapshot_id: &str) -> Result<(), String> {
+    let path = snapshot_path(account_id, snapshot_id)?;
+    fs::remove_file(&path).map_err(|e| format!("删除快照失败: {}", e))
+}
+
+/// 删除账户的全部历史快照与数据块
+///
+/// 调用方应先销毁账户数据密钥；此处删除文件只是释放空间。
+pub fn delete_account_data(account_id: &str) -> Result<(), String> {
+    let store = store_dir()?;
+    for dir in [snapshots_dir(&store, account_id), store.join("blobs").join(account_id)] {
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| format!("删除快照数据失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 仅保留最近的 `keep` 个快照
+///
+/// # 返回
+/// - 被删除的快照数量
+pub fn prune_snapshots(account_id: &str, keep: usize) -> Result<usize, String> {
+    let dir = snapshots_dir(&store_dir()?, account_id);
+    let mut files = list_enc_files(&dir);
+    // 快照 ID 以时间戳开头，文件名排序即时间顺序
+    files.sort();
+    let excess = files.len().saturating_sub(keep);
+    for path in &files[..excess] {
+        fs::remove_file(path).map_err(|e| format!("删除旧快照失败: {}", e))?;
+    }
+    Ok(excess)
+}
+
+/// 回收不再被任何清单引用的 blob
+///
+/// 引用来源包括账户备份文件（最新清单）与所有历史快照。
+/// 数据密钥已销毁的清单视为孤立文件直接删除；其他清单无法读取时跳过该文件，
+/// 并且本次不回收其所属账户的数据块，避免误删仍被引用的数据。
+pub fn collect_garbage() -> Result<GcReport, String> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let backup_dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    let store = backup_dir.join("store");
+    if !store.join("blobs").exists() {
+        return Ok(GcReport::default());
+    }
+
+    // (清单文件, 所属账户 ID)：账户备份文件以账户 ID 命名，历史快照位于账户 ID 目录下
+    let mut manifest_files: Vec<(PathBuf, String)> = list_enc_files(&backup_dir)
+        .into_iter()
+        .filter(|p| {
+            p.file_name().is_none_or(|n| {
+                n != account_index::INDEX_FILE_NAME && n != account_keys::KEYS_FILE_NAME
+            })
+        })
+        .map(|p| {
+            let owner = p.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            (p, owner)
+        })
+        .collect();
+    for dir in list_subdirs(&store.join("snapshots")) {
+        let owner = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        manifest_files.extend(list_enc_files(&dir).into_iter().map(|p| (p, owner.clone())));
+    }
+
+    let mut report = GcReport::default();
+    let mut referenced: BTreeSet<(String, String)> = BTreeSet::new();
+    let mut protected: BTreeSet<String> = BTreeSet::new();
+    for (path, owner) in &manifest_files {
+        match read_manifest_refs(path) {
+            Ok(Some(refs)) => referenced.extend(refs),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(
+                    target: "backup::store",
+                    file = %path.display(),
+                    error = %e,
+                    "清单无法读取，跳过该账户的数据块回收"
+                );
+                report.skipped_manifests += 1;
+                protected.insert(owner.clone());
+                // 无法确定是否为早期共享存储格式，共享数据块一并保留
+                protected.insert(String::new());
+            }
+        }
+    }
+
+    for (account_id, blob) in list_blobs(&store) {
+        let hash = blob.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        if protected.contains(&account_id) || referenced.contains(&(account_id, hash)) {
+            report.remaining_blobs += 1;
+            continue;
+        }
+        let size = fs::metadata(&blob).map(|m| m.len()).unwrap_or(0);
+        fs::remove_file(&blob).map_err(|e| format!("删除 blob 失败: {}", e))?;
+        report.removed_blobs += 1;
+        report.freed_bytes += size;
+    }
+
+    // 清理空的分片与账户目录
+    for dir in list_subdirs(&store.join("blobs")) {
+        for shard in list_subdirs(&dir) {
+            let _ = fs::remove_dir(&shard);
+        }
+        let _ = fs::remove_dir(&dir);
+    }
+
+    tracing::info!(
+        target: "backup::store",
+        removed = report.removed_blobs,
+        freed_bytes = report.freed_bytes,
+        remaining = report.remaining_blobs,
+        skipped_manifests = report.skipped_manifests,
+        "垃圾回收完成"
+    );
+    Ok(report)
+}
+
+/// 读取清单引用的 (账户 ID, 哈希)
+///
+/// 数据密钥已销毁的清单作为孤立文件删除，旧版整文件备份不引用 blob，均返回 None。
+fn read_manifest_refs(path: &Path) -> Result<Option<Vec<(String, String)>>, String> {
+    let content = fs::read(path).map_err(|e| format!("读取清单失败: {}", e))?;
+    if account_keys::is_shredded(&content)? {
+        fs::remove_file(path).map_err(|e| format!("删除孤立清单失败: {}", e))?;
+        return Ok(None);
+    }
+    let decrypted = Zeroizing::new(account_keys::decrypt(&content)?);
+    let value: Value = serde_json::from_slice(&decrypted).map_err(|e| format!("解析清单失败: {}", e))?;
+    if !is_manifest(&value) {
+        return Ok(None);
+    }
+
+    let account_id = value.get("account_id").and_then(|v| v.as_str()).unwrap_or_default();
+    Ok(Some(
+        value
+            .get("entries")
+            .and_then(|v| v.as_object())
+            .map(|entries| {
+                entries
+                    .values()
+                    .filter_map(|v| v.as_str())
+                    .map(|hash| (account_id.to_string(), hash.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    ))
+}
+
+/// 存储占用统计
+pub fn stats() -> Result<StoreStats, String> {
+    let store = store_dir()?;
+    let mut stats = StoreStats::default();
+    for (_, blob) in list_blobs(&store) {
+        stats.blob_count += 1;
+        stats.blob_bytes += fs::metadata(&blob).map(|m| m.len()).unwrap_or(0);
+    }
+    for dir in list_subdirs(&store.join("snapshots")) {
+        stats.snapshot_count += list_enc_files(&dir).len();
+    }
+    Ok(stats)
+}
+
+fn list_enc_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "enc"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn list_subdirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antigravity::vault::vault;
+    use crate::path_utils::use_test_config_dir;
+    use serde_json::json;
+
+    const ACCOUNT: &str = "0123456789abcdef0123456789abcdef";
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_identical_values_share_blobs() {
+        use_test_config_dir("store-dedup");
+        let first = write_snapshot(ACCOUNT, &fields(json!({ "a": "same", "b": "same", "c": "x", "backup_time": "t1" }))).unwrap();
+        assert_eq!(first.entries["a"], first.entries["b"]);
+        assert_eq!(first.meta["backup_time"], "t1");
+        assert_eq!(stats().unwrap().blob_count, 2);
+
+        let second = write_snapshot(ACCOUNT, &fields(json!({ "a": "same", "b": "same", "c": "y" }))).unwrap();
+        assert_eq!(second.entries["a"], first.entries["a"]);
+        let stats = stats().unwrap();
+        assert_eq!((stats.blob_count, stats.snapshot_count), (3, 2));
+
+        let expanded = expand_manifest(serde_json::to_value(&second).unwrap()).unwrap();
+        assert_eq!(expanded, json!({ "a": "same", "b": "same", "c": "y" }));
+    }
+
+    #[test]
+    fn test_gc_keeps_referenced_blobs() {
+        let dir = use_test_config_dir("store-gc");
+        let backup_dir = AppPaths::backup_dir().unwrap();
+        write_snapshot(ACCOUNT, &fields(json!({ "a": 1, "b": 2 }))).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let latest = write_snapshot(ACCOUNT, &fields(json!({ "a": 1, "b": 3 }))).unwrap();
+        secure_write_file(&backup_dir.join(format!("{}.enc", ACCOUNT)), &encrypt_manifest(&latest).unwrap()).unwrap();
+
+        assert_eq!(prune_snapshots(ACCOUNT, 1).unwrap(), 1);
+        let report = collect_garbage().unwrap();
+        assert_eq!((report.removed_blobs, report.remaining_blobs, report.skipped_manifests), (1, 2, 0));
+        expand_manifest(serde_json::to_value(&latest).unwrap()).unwrap();
+
+        // 无法读取的清单不会中止回收，但其所属账户的数据块保留
+        let other = "fedcba9876543210fedcba9876543210";
+        write_snapshot(other, &fields(json!({ "c": 4 }))).unwrap();
+        let snapshots = store_dir().unwrap().join("snapshots").join(other);
+        for file in list_enc_files(&snapshots) {
+            fs::write(file, b"corrupt").unwrap();
+        }
+        fs::remove_file(backup_dir.join(format!("{}.enc", ACCOUNT))).unwrap();
+        delete_snapshot(ACCOUNT, &latest.snapshot_id).unwrap();
+        let report = collect_garbage().unwrap();
+        assert_eq!((report.removed_blobs, report.remaining_blobs, report.skipped_manifests), (2, 1, 1));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reads_legacy_shared_store() {
+        use_test_config_dir("store-legacy");
+        let store = store_dir().unwrap();
+        let write_legacy_blob = |hash: &str, value: Value| {
+            let path = blob_path(&store, "", hash);
+            secure_create_dir(path.parent().unwrap()).unwrap();
+            fs::write(path, vault().encrypt(value.to_string().as_bytes()).unwrap()).unwrap();
+        };
+        let (kept, orphan) = ("ab".repeat(32), "cd".repeat(32));
+        write_legacy_blob(&kept, json!("v"));
+        write_legacy_blob(&orphan, json!("old"));
+
+        // 早期清单没有 account_id，由保险库密钥加密
+        let manifest = json!({
+            "format": MANIFEST_FORMAT,
+            "version": 1,
+            "snapshot_id": "20240101T000000000-0000",
+            "created_at": "2024-01-01T00:00:00+00:00",
+            "entries": { "k": kept },
+        });
+        assert_eq!(expand_manifest(manifest.clone()).unwrap(), json!({ "k": "v" }));
+
+        let dir = snapshots_dir(&store, ACCOUNT);
+        secure_create_dir(&dir).unwrap();
+        fs::write(dir.join("20240101T000000000-0000.enc"), vault().encrypt(manifest.to_string().as_bytes()).unwrap())
+            .unwrap();
+        let report = collect_garbage().unwrap();
+        assert_eq!((report.removed_blobs, report.remaining_blobs), (1, 1));
+        assert!(blob_path(&store, "", &kept).exists());
+    }
+}
@@ -2,8 +2,11 @@
 ///
 /// 提供跨平台的 Antigravity 应用程序启动功能
 /// 支持 Windows、macOS 和 Linux 系统
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+/// 子进程的输出写入启动日志（见 launch_log）
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::antigravity::launch_log;
 
 /// 启动 Antigravity 应用程序（主入口函数）
 ///
@@ -146,111 +149,81 @@ fn start_antigravity_linux() -> Result<String, String> {
         cmd.env("XAUTHORITY", xauthority);
     }
 
-    match cmd.spawn() {
+    match launch_log::spawn(&mut cmd) {
         Ok(_) => Ok("Antigravity 已启动".to_string()),
         Err(e) => Err(format!("启动 Antigravity 失败: {}", e)),
     }
 }
 
 /// 尝试从指定路径启动应用程序
-fn try_start_from_path(path: &PathBuf) -> Result<String, String> {
-    // macOS 需要特殊处理：使用 open 命令启动 .app 应用
+fn try_start_from_path(path: &Path) -> Result<String, String> {
+    // macOS：优先直接执行 .app bundle 内的可执行文件，使 Antigravity 本身成为子进程，
+    // 其输出写入启动日志、退出码可被监视（经 open 启动时子进程只是 open，日志为空、崩溃无从发现）
     #[cfg(target_os = "macos")]
     {
-        // 确保路径是 .app bundle 格式
-        let app_bundle_path = if path.to_str().unwrap_or("").contains(".app") {
-            path.clone()
+        let executable = if path.is_file() {
+            Some(path.to_path_buf())
         } else {
-            return Err(format!("路径不是有效的 .app bundle: {}", path.display()));
+            bundle_executable(path)
         };
-
-        // 方法1: 尝试不带 -n 参数的 open 命令（更兼容）
-        match Command::new("open")
-            .arg("-g") // 在后台启动应用
-            .arg(&app_bundle_path)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-        {
-            Ok(_) => {
-                return Ok("Antigravity 已启动".to_string());
-            }
-            Err(_e1) => {
-                // 方法2: 尝试直接执行可执行文件
-                let exec_names = ["Electron", "Antigravity", "antigravity"];
-                for exec_name in &exec_names {
-                    let exec_path = app_bundle_path.join("Contents/MacOS").join(exec_name);
-                    if exec_path.exists() {
-                        match Command::new(&exec_path)
-                            .stdout(std::process::Stdio::null())
-                            .stderr(std::process::Stdio::null())
-                            .spawn()
-                        {
-                            Ok(_) => {
-                                return Ok("Antigravity 已启动".to_string());
-                            }
-                            Err(_) => {
-                                continue; // 尝试下一个可执行文件
-                            }
-                        }
-                    }
-                }
-
-                // 方法3: 最后尝试不带任何参数的 open 命令
-                match Command::new("open")
-                    .arg(&app_bundle_path)
-                    .stdout(std::process::Stdio::null())
-                    .stderr(std::process::Stdio::null())
-                    .spawn()
-                {
-                    Ok(_) => {
-                        return Ok("Antigravity 已启动".to_string());
-                    }
-                    Err(_e3) => {
-                        return Err("启动 Antigravity 失败".to_string());
-                    }
+        if let Some(executable) = executable {
+            match launch_log::spawn(&mut Command::new(&executable)) {
+                Ok(_) => return Ok("Antigravity 已启动".to_string()),
+                Err(e) => {
+                    tracing::warn!(target: "antigravity::launch", error = %e, "直接启动可执行文件失败，改用 open 启动");
                 }
             }
         }
+
+        // 回退：经 LaunchServices 启动，启动日志中只有 open 本身的输出
+        let app_bundle_path = path
+            .ancestors()
+            .find(|p| p.extension().is_some_and(|ext| ext == "app"))
+            .ok_or_else(|| format!("路径不是有效的 .app bundle: {}", path.display()))?;
+        tracing::warn!(target: "antigravity::launch", "经 open 启动，无法记录 Antigravity 的输出与退出码");
+        launch_log::spawn(Command::new("open").arg("-g").arg(app_bundle_path))
+            .map_err(|e| format!("启动 Antigravity 失败: {}", e))?;
+        Ok("Antigravity 已启动".to_string())
     }
 
-    // Windows 和 Linux 直接执行二进制文件（静默启动）
+    // Windows 和 Linux 直接执行二进制文件（静默启动，输出写入启动日志）
     #[cfg(not(target_os = "macos"))]
     {
-        // Windows：重定向输出到 null 设备
-        #[cfg(target_os = "windows")]
-        {
-            Command::new(path)
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn()
-                .map_err(|e| format!("启动失败: {}", e))?;
-        }
-
-        // Linux：重定向输出到 null 设备
-        #[cfg(target_os = "linux")]
-        {
-            Command::new(path)
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .spawn()
-                .map_err(|e| format!("启动失败: {}", e))?;
-        }
+        launch_log::spawn(&mut Command::new(path)).map_err(|e| format!("启动失败: {}", e))?;
 
         Ok("Antigravity 已启动".to_string())
     }
 }
 
-/// 尝试从系统命令启动应用程序（静默启动）
+/// .app bundle 内的可执行文件：优先 Info.plist 中的 CFBundleExecutable，其次常见名称
+#[cfg(any(target_os = "macos", test))]
+fn bundle_executable(bundle: &Path) -> Option<PathBuf> {
+    let contents = bundle.join("Contents");
+    let declared = std::fs::read_to_string(contents.join("Info.plist"))
+        .ok()
+        .and_then(|plist| plist_string(&plist, "CFBundleExecutable"));
+    declared
+        .into_iter()
+        .chain(["Electron", "Antigravity", "antigravity"].map(String::from))
+        .map(|name| contents.join("MacOS").join(name))
+        .find(|path| path.is_file())
+}
+
+/// 从 XML 格式的 plist 中读取字符串值（二进制 plist 返回 None）
+#[cfg(any(target_os = "macos", test))]
+fn plist_string(plist: &str, key: &str) -> Option<String> {
+    let after_key = &plist[plist.find(&format!("<key>{}</key>", key))?..];
+    let value = &after_key[after_key.find("<string>")? + "<string>".len()..];
+    let value = value[..value.find("</string>")?].trim();
+    (!value.is_empty() && !value.contains(['/', '\\'])).then(|| value.to_string())
+}
+
+/// 尝试从系统命令启动应用程序（静默启动，输出写入启动日志）
 fn try_start_from_commands(commands: Vec<&str>) -> Result<String, String> {
     let mut errors = Vec::new();
 
     for cmd in commands {
-        match Command::new(cmd)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-        {
+        match launch_log::spawn(&mut Command::new(cmd)) {
             Ok(_) => {
                 return Ok("Antigravity 已启动".to_string());
             }
@@ -284,3 +257,34 @@ pub fn detect_antigravity_executable() -> Option<PathBuf> {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_executable() {
+        let bundle = std::env::temp_dir().join(format!("ag-starter-{}/Antigravity.app", std::process::id()));
+        let _ = std::fs::remove_dir_all(&bundle);
+        let macos_dir = bundle.join("Contents/MacOS");
+        std::fs::create_dir_all(&macos_dir).unwrap();
+        assert!(bundle_executable(&bundle).is_none());
+
+        // 没有 Info.plist 时按常见名称查找
+        std::fs::write(macos_dir.join("Electron"), b"").unwrap();
+        assert_eq!(bundle_executable(&bundle), Some(macos_dir.join("Electron")));
+
+        // Info.plist 声明的可执行文件优先
+        std::fs::write(macos_dir.join("Antigravity Helper"), b"").unwrap();
+        std::fs::write(
+            bundle.join("Contents/Info.plist"),
+            "<plist><dict>\n  <key>CFBundleName</key>\n  <string>Antigravity</string>\n  \
+             <key>CFBundleExecutable</key>\n  <string>Antigravity Helper</string>\n</dict></plist>",
+        )
+        .unwrap();
+        assert_eq!(bundle_executable(&bundle), Some(macos_dir.join("Antigravity Helper")));
+
+        assert_eq!(plist_string("<key>CFBundleExecutable</key><string>../x</string>", "CFBundleExecutable"), None);
+        assert_eq!(plist_string("<key>Other</key><string>x</string>", "CFBundleExecutable"), None);
+    }
+}
@@ -0,0 +1,448 @@
+// Antigravity 切换预览模块
+// 在切换/恢复前比较实时数据库与目标备份（或比较两个备份），列出每个字段的增删改
+//
+// 预览按恢复的实际规则模拟结果：只写入所选分组中备份存在的字段，Marker 使用与恢复相同的合并逻辑。
+// 返回的值均经过脱敏：令牌类字段与长串不透明数据只显示占位符。
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+use crate::antigravity::key_groups::KeyGroup;
+use crate::antigravity::key_sets::{self, KeySet};
+use crate::antigravity::{credentials, restore, secret_storage};
+use crate::constants::database;
+
+/// 预览中单个值的最大长度
+const PREVIEW_MAX_CHARS: usize = 300;
+
+/// 不透明字符串（令牌、protobuf 等）的最小长度
+const OPAQUE_MIN_CHARS: usize = 40;
+
+/// 字段名包含这些片段时视为敏感字段
+const SECRET_NAME_PARTS: &[&str] = &["token", "apikey", "api_key", "secret", "password", "credential", "cookie"];
+
+/// 字段变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// 单个字段的变化
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyChange {
+    pub key: String,
+    pub kind: ChangeKind,
+    /// 变化前的值（已脱敏）
+    pub before: Option<String>,
+    /// 变化后的值（已脱敏）
+    pub after: Option<String>,
+    /// 两侧均为 JSON 对象时，不同的顶层子字段
+    pub changed_fields: Vec<String>,
+}
+
+/// 切换预览结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SwitchPreview {
+    /// 变化前登录的账户
+    pub from_account: Option<String>,
+    /// 变化后登录的账户
+    pub to_account: Option<String>,
+    pub changes: Vec<KeyChange>,
+    /// 摘要，如 "账户 a → b，用户设置有 4 项不同"
+    pub summary: String,
+}
+
+/// 读取实时数据库中参与备份的字段（原始字符串）
+fn read_live_values(db_path: &Path, key_set: &KeySet) -> Result<Map<String, Value>, String> {
+    let conn = Connection::open(db_path).map_err(|e| format!("连接数据库失败: {}", e))?;
+    let existing = key_sets::list_db_keys(&conn)?;
+    let mut keys = key_set.resolve(&existing);
+    keys.push(database::TARGET_STORAGE_MARKER.to_string());
+
+    let mut values = Map::new();
+    for key in keys {
+        let value: Option<String> = conn
+            .query_row("SELECT value FROM ItemTable WHERE key = ?", [&key], |row| row.get(0))
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some(value) = value {
+            values.insert(key, Value::String(value));
+        }
+    }
+    Ok(values)
+}
+
+/// 模拟恢复：在实时数据上覆盖备份中所选字段，并按恢复规则合并 Marker
+fn simulate_restore(
+    live: &Map<String, Value>,
+    backup: &Value,
+    keys: &[String],
+    key_set: &KeySet,
+) -> Map<String, Value> {
+    let mut result = live.clone();
+    let mut restored_keys = Vec::new();
+    for key in keys {
+        if let Some(value) = backup.get(key).and_then(|v| v.as_str()) {
+            result.insert(key.clone(), Value::String(value.to_string()));
+            if key_set.participates_in_marker(key) {
+                restored_keys.push(key.as_str());
+            }
+        }
+    }
+
+    if !restored_keys.is_empty() {
+        let current_marker = live.get(database::TARGET_STORAGE_MARKER).and_then(|v| v.as_str());
+        let merged = restore::merge_marker(current_marker, backup, &restored_keys);
+        result.insert(
+            database::TARGET_STORAGE_MARKER.to_string(),
+            Value::String(Value::Object(merged).to_string()),
+        );
+    }
+    result
+}
+
+/// 备份中的字段统一为原始字符串（Marker 在备份中以对象保存）
+fn backup_values(backup: &Value, key_set: &KeySet) -> Map<String, Value> {
+    let existing: Vec<String> = backup
+        .as_object()
+        .map(|obj| obj.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut keys = key_set.resolve(&existing);
+    keys.push(database::TARGET_STORAGE_MARKER.to_string());
+
+    let mut values = Map::new();
+    for key in keys {
+        match backup.get(&key) {
+            Some(Value::String(s)) => {
+                values.insert(key, Value::String(s.clone()));
+            }
+            Some(other) => {
+                values.insert(key, Value::String(other.to_string()));
+            }
+            None => {}
+        }
+    }
+    values
+}
+
+fn is_secret_name(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    SECRET_NAME_PARTS.iter().any(|part| lower.contains(part))
+}
+
+fn is_opaque(s: &str) -> bool {
+    s.len() >= OPAQUE_MIN_CHARS && !s.contains(char::is_whitespace)
+}
+
+/// 递归脱敏 JSON：敏感字段名与不透明长串替换为占位符（空值与布尔标记保留，如 hasApiKey）
+fn redact_json(value: Value) -> Value {
+    match value {
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .map(|(k, v)| {
+                    let v = if is_secret_name(&k) && !v.is_null() && !v.is_boolean() {
+                        Value::String("[REDACTED]".to_string())
+                    } else {
+                        redact_json(v)
+                    };
+                    (k, v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(redact_json).collect()),
+        Value::String(s) if is_opaque(&s) => Value::String(format!("[REDACTED:{} chars]", s.len())),
+        other => other,
+    }
+}
+
+/// 对数据库中的单个值（已解析）脱敏
+///
+/// 扩展密钥（secret://）与名称敏感的字段整体替换，认证信息去除令牌，其余递归脱敏。
+pub(crate) fn redact_entry(key: &str, value: Value) -> Value {
+    if secret_storage::is_secret_key(key) || is_secret_name(key) {
+        return Value::String("[REDACTED]".to_string());
+    }
+    let value = if key == database::AUTH_STATUS {
+        credentials::redact_auth_status(value)
+    } else {
+        value
+    };
+    redact_json(value)
+}
+
+/// 对数据库中的单个值脱敏（不截断）
+pub(crate) fn redact_value(key: &str, raw: &str) -> String {
+    match serde_json::from_str::<Value>(raw) {
+        Ok(json) => redact_entry(key, json).to_string(),
+        Err(_) => match redact_entry(key, Value::String(raw.to_string())) {
+            Value::String(s) => s,
+            other => other.to_string(),
+        },
+    }
+}
+
+/// 生成单个值的脱敏预览
+pub(crate) fn redacted_preview(key: &str, raw: &str) -> String {
+    let text = redact_value(key, raw);
+    if text.chars().count() > PREVIEW_MAX_CHARS {
+        let truncated: String = text.chars().take(PREVIEW_MAX_CHARS).collect();
+        format!("{}…", truncated)
+    } else {
+        text
+    }
+}
+
+/// 两侧均为 JSON 对象时，列出不同的顶层子字段
+pub(crate) fn changed_object_fields(before: &str, after: &str) -> Vec<String> {
+    let (Ok(Value::Object(a)), Ok(Value::Object(b))) =
+        (serde_json::from_str::<Value>(before), serde_json::from_str::<Value>(after))
+    else {
+        return Vec::new();
+    };
+    let mut fields: Vec<String> = a
+        .keys()
+        .chain(b.keys().filter(|k| !a.contains_key(*k)))
+        .filter(|k| a.get(*k) != b.get(*k))
+        .cloned()
+        .collect();
+    fields.sort();
+    fields
+}
+
+/// 两个字符串解析为 JSON 后是否相同（忽略字段顺序与空白）
+pub(crate) fn same_json(a: &str, b: &str) -> bool {
+    match (serde_json::from_str::<Value>(a), serde_json::from_str::<Value>(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn account_email(values: &Map<String, Value>) -> Option<String> {
+    values
+        .get(database::AUTH_STATUS)
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str::<Value>(s).ok())
+        .and_then(|auth| auth.get("email").and_then(|v| v.as_str()).map(|s| s.to_string()))
+}
+
+/// 字段的展示名称（摘要用）
+fn key_label(key: &str) -> &str {
+    match key {
+        database::AUTH_STATUS => "认证信息",
+        database::USER_SETTINGS => "用户设置",
+        database::ONBOARDING => "新手引导",
+        database::COMMAND_CONFIGS => "命令模型配置",
+        database::AGENT_STATE => "Agent 状态",
+        database::CHAT_SESSION => "聊天会话索引",
+        database::TARGET_STORAGE_MARKER => "同步标记",
+        _ => key,
+    }
+}
+
+/// 比较两组字段值
+///
+/// 字段按 `before` 中的顺序排列，`after` 中新增的字段排在其后，Marker 始终在最后。
+pub fn diff_values(before: &Map<String, Value>, after: &Map<String, Value>) -> SwitchPreview {
+    let mut keys: Vec<&str> = before
+        .keys()
+        .chain(after.keys().filter(|k| !before.contains_key(*k)))
+        .map(String::as_str)
+        .filter(|k| *k != database::TARGET_STORAGE_MARKER)
+        .collect();
+    keys.push(database::TARGET_STORAGE_MARKER);
+
+    let mut changes = Vec::new();
+    for key in keys {
+        let old = before.get(key).and_then(|v| v.as_str());
+        let new = after.get(key).and_then(|v| v.as_str());
+        let (kind, changed_fields) = match (old, new) {
+            (None, Some(_)) => (ChangeKind::Added, Vec::new()),
+            (Some(_), None) => (ChangeKind::Removed, Vec::new()),
+            (Some(a), Some(b)) if a != b && !same_json(a, b) => {
+                (ChangeKind::Changed, changed_object_fields(a, b))
+            }
+            _ => continue,
+        };
+        changes.push(KeyChange {
+            key: key.to_string(),
+            kind,
+            before: old.map(|v| redacted_preview(key, v)),
+            after: new.map(|v| redacted_preview(key, v)),
+            changed_fields,
+        });
+    }
+
+    let from_account = account_email(before);
+    let to_account = account_email(after);
+    let mut parts = Vec::new();
+    if from_account != to_account {
+        parts.push(format!(
+            "账户 {} → {}",
+            from_account.as_deref().unwrap_or("未登录"),
+            to_account.as_deref().unwrap_or("未登录")
+        ));
+    }
+    for change in &changes {
+        if change.key == database::AUTH_STATUS && from_account != to_account {
+            continue;
+        }
+        parts.push(match (change.kind, change.changed_fields.len()) {
+            (ChangeKind::Added, _) => format!("新增{}", key_label(&change.key)),
+            (ChangeKind::Removed, _) => format!("移除{}", key_label(&change.key)),
+            (ChangeKind::Changed, 0) => format!("{}不同", key_label(&change.key)),
+            (ChangeKind::Changed, n) => format!("{}有 {} 项不同", key_label(&change.key), n),
+        });
+    }
+    let summary = if parts.is_empty() {
+        "没有变化".to_string()
+    } else {
+        parts.join("，")
+    };
+
+    SwitchPreview {
+        from_account,
+        to_account,
+        changes,
+        summary,
+    }
+}
+
+/// 预览将备份中所选分组恢复到实时数据库后的变化
+pub fn preview_restore(db_path: &Path, backup: &Value, groups: &[KeyGroup]) -> Result<SwitchPreview, String> {
+    let key_set = key_sets::current();
+    let live = if db_path.exists() {
+        read_live_values(db_path, &key_set)?
+    } else {
+        Map::new()
+    };
+    let keys = key_set.restore_keys(backup, groups);
+    let after = simulate_restore(&live, backup, &keys, &key_set);
+    Ok(diff_values(&live, &after))
+}
+
+/// 比较两个备份
+pub fn compare_backups(a: &Value, b: &Value) -> SwitchPreview {
+    let key_set = key_sets::current();
+    diff_values(&backup_values(a, &key_set), &backup_values(b, &key_set))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(entries: &[(&str, &str)]) -> Map<String, Value> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect()
+    }
+
+    fn auth(email: &str, token: &str) -> String {
+        json!({ "email": email, "name": "User", "apiKey": token }).to_string()
+    }
+
+    #[test]
+    fn test_redact_value_hides_tokens() {
+        let redacted = redact_value(database::AUTH_STATUS, &auth("a@example.com", "ya29.secret-token"));
+        assert!(!redacted.contains("ya29.secret-token"));
+        assert!(redacted.contains("a@example.com"));
+        assert!(redacted.contains("\"hasApiKey\":true"));
+
+        // 名称敏感的嵌套字段整体替换，空值保留
+        let nested = json!({ "oauth": { "refresh_token": "r1", "clientSecret": null }, "theme": "dark" });
+        let redacted: Value = serde_json::from_str(&redact_value("settings", &nested.to_string())).unwrap();
+        assert_eq!(redacted["oauth"]["refresh_token"], "[REDACTED]");
+        assert_eq!(redacted["oauth"]["clientSecret"], Value::Null);
+        assert_eq!(redacted["theme"], "dark");
+
+        // 字段名本身敏感、扩展密钥
+        assert_eq!(redact_value("some.authToken", "short"), "[REDACTED]");
+        assert_eq!(redact_value("secret://{\"key\":\"x\"}", "{\"data\":[1]}"), "\"[REDACTED]\"");
+    }
+
+    #[test]
+    fn test_redact_value_hides_opaque_strings() {
+        let opaque = "Q".repeat(OPAQUE_MIN_CHARS);
+        assert_eq!(redact_value("state", &opaque), format!("[REDACTED:{} chars]", opaque.len()));
+
+        let in_json = json!({ "blob": opaque, "list": [opaque.clone(), "ok"] }).to_string();
+        let redacted: Value = serde_json::from_str(&redact_value("state", &in_json)).unwrap();
+        assert_eq!(redacted["blob"], format!("[REDACTED:{} chars]", OPAQUE_MIN_CHARS));
+        assert_eq!(redacted["list"][1], "ok");
+
+        // 含空白的普通长文本与短串不脱敏
+        let sentence = "this is a fairly long sentence that is clearly not a token";
+        assert_eq!(redact_value("note", sentence), sentence);
+        assert_eq!(redact_value("note", "plain"), "plain");
+    }
+
+    #[test]
+    fn test_redacted_preview_truncates() {
+        let long = format!("\"{}\"", "word ".repeat(100));
+        let preview = redacted_preview("note", &long);
+        assert!(preview.ends_with('…'));
+        assert_eq!(preview.chars().count(), PREVIEW_MAX_CHARS + 1);
+    }
+
+    #[test]
+    fn test_diff_values_classifies_changes() {
+        let before = values(&[
+            (database::AUTH_STATUS, &auth("a@example.com", "token-a")),
+            (database::USER_SETTINGS, r#"{"theme":"dark","font":12,"zoom":1}"#),
+            (database::ONBOARDING, "done"),
+            ("same.json", r#"{"a":1,"b":2}"#),
+        ]);
+        let after = values(&[
+            (database::AUTH_STATUS, &auth("b@example.com", "token-b")),
+            (database::USER_SETTINGS, r#"{"theme":"light","font":12,"extra":true}"#),
+            ("same.json", r#"{ "b": 2, "a": 1 }"#),
+            (database::COMMAND_CONFIGS, "{}"),
+        ]);
+
+        let preview = diff_values(&before, &after);
+        let kinds: Vec<(&str, ChangeKind)> =
+            preview.changes.iter().map(|c| (c.key.as_str(), c.kind)).collect();
+        // before 中的字段在前（Map 按键名排序），after 中新增的字段在后
+        assert_eq!(
+            kinds,
+            vec![
+                (database::AUTH_STATUS, ChangeKind::Changed),
+                (database::ONBOARDING, ChangeKind::Removed),
+                (database::USER_SETTINGS, ChangeKind::Changed),
+                (database::COMMAND_CONFIGS, ChangeKind::Added),
+            ]
+        );
+
+        let settings = &preview.changes[2];
+        assert_eq!(settings.changed_fields, vec!["extra", "theme", "zoom"]);
+        let removed = &preview.changes[1];
+        assert_eq!((removed.before.as_deref(), removed.after.as_deref()), (Some("done"), None));
+        let added = &preview.changes[3];
+        assert!(added.before.is_none() && added.after.is_some());
+
+        // 认证信息变化只在摘要中显示账户，预览值不含令牌
+        let auth_change = &preview.changes[0];
+        assert!(!auth_change.before.as_ref().unwrap().contains("token-a"));
+        assert!(!auth_change.after.as_ref().unwrap().contains("token-b"));
+        assert_eq!(preview.from_account.as_deref(), Some("a@example.com"));
+        assert_eq!(preview.to_account.as_deref(), Some("b@example.com"));
+        assert_eq!(
+            preview.summary,
+            "账户 a@example.com → b@example.com，移除新手引导，用户设置有 3 项不同，新增命令模型配置"
+        );
+    }
+
+    #[test]
+    fn test_diff_values_without_changes() {
+        let before = values(&[(database::USER_SETTINGS, r#"{"a":1}"#)]);
+        let preview = diff_values(&before, &before.clone());
+        assert!(preview.changes.is_empty());
+        assert_eq!(preview.summary, "没有变化");
+    }
+}
@@ -0,0 +1,398 @@
+// Antigravity 切换撤销模块
+// 每次切换账户前保存当前数据库中的账户字段（撤销快照），
+// 撤销时原样写回并删除切换后新增的字段，使数据库回到切换前的状态
+//
+// 撤销栈保存在备份目录的 undo/ 下，按当前保险库模式加密，应用重启后仍然可用。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::antigravity::backup::read_account_data;
+use crate::antigravity::vault::vault;
+use crate::antigravity::secret_storage::SecretRestorePolicy;
+use crate::antigravity::{account_index, key_groups, key_sets, restore};
+use crate::constants::database;
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{secure_create_dir, secure_delete_file, secure_write_file};
+
+/// 撤销栈目录名（位于备份目录中）
+const UNDO_DIR_NAME: &str = "undo";
+
+/// 撤销栈最多保留的条目数
+const MAX_UNDO_ENTRIES: usize = 10;
+
+/// 当前撤销快照格式版本
+const UNDO_VERSION: u32 = 1;
+
+/// 串行化撤销栈的读写
+static UNDO_LOCK: Mutex<()> = Mutex::new(());
+
+/// 撤销快照
+#[derive(Serialize, Deserialize)]
+struct UndoEntry {
+    version: u32,
+    id: String,
+    created_at: String,
+    /// 切换前登录的账户（未登录时为空）
+    from_email: Option<String>,
+    /// 切换的目标账户
+    to_email: String,
+    /// 切换前的字段原始值及 Marker
+    data: serde_json::Map<String, Value>,
+    /// 切换前不存在的字段（撤销时删除）
+    absent_keys: Vec<String>,
+}
+
+/// 按账户清理的结果
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PurgeReport {
+    /// 删除的条目数
+    pub removed: usize,
+    /// 无法读取、未能确认是否涉及该账户的条目数（已保留）
+    pub unchecked: usize,
+}
+
+/// 撤销栈条目摘要（供前端展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoInfo {
+    pub id: String,
+    pub created_at: String,
+    pub from_email: Option<String>,
+    pub to_email: String,
+}
+
+impl From<&UndoEntry> for UndoInfo {
+    fn from(entry: &UndoEntry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            created_at: entry.created_at.clone(),
+            from_email: entry.from_email.clone(),
+            to_email: entry.to_email.clone(),
+        }
+    }
+}
+
+fn undo_dir() -> Result<PathBuf, String> {
+    AppPaths::backup_dir()
+        .map(|dir| dir.join(UNDO_DIR_NAME))
+        .ok_or_else(|| "无法获取备份目录".to_string())
+}
+
+fn state_db_path() -> Result<PathBuf, String> {
+    AppPaths::antigravity_data_dir()
+        .map(|dir| dir.join("state.vscdb"))
+        .ok_or_else(|| "未找到数据库路径".to_string())
+}
+
+/// 撤销栈文件（按创建时间升序，ID 为毫秒时间戳，可直接按文件名排序）
+fn entry_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("读取撤销栈失败: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("enc"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn read_entry(path: &Path) -> Result<UndoEntry, String> {
+    let content = fs::read(path).map_err(|e| format!("读取撤销快照失败: {}", e))?;
+    let decrypted = zeroize::Zeroizing::new(vault().decrypt(&content)?);
+    let entry: UndoEntry =
+        serde_json::from_slice(&decrypted).map_err(|e| format!("撤销快照解析失败: {}", e))?;
+    if entry.version > UNDO_VERSION {
+        return Err(format!("不支持的撤销快照版本: {}", entry.version));
+    }
+    Ok(entry)
+}
+
+/// 切换前保存当前账户状态到撤销栈
+///
+/// # 参数
+/// - `to_email`: 切换的目标账户
+pub fn push_pre_switch(to_email: &str) -> Result<UndoInfo, String> {
+    vault().ensure_unlocked()?;
+    let db_path = state_db_path()?;
+    if !db_path.exists() {
+        return Err(format!("数据库文件不存在: {}", db_path.display()));
+    }
+
+    let data = read_account_data(&db_path)?;
+    let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let absent_keys = key_sets::current()
+        .resolve(&key_sets::list_db_keys(&conn)?)
+        .into_iter()
+        .filter(|key| !data.contains_key(key))
+        .collect();
+    drop(conn);
+    let from_email = data
+        .get(database::AUTH_STATUS)
+        .and_then(|v| v.as_str())
+        .and_then(|s| serde_json::from_str::<Value>(s).ok())
+        .and_then(|auth| auth.get("email").and_then(|v| v.as_str()).map(account_index::normalize_email));
+
+    let now = chrono::Local::now();
+    let entry = UndoEntry {
+        version: UNDO_VERSION,
+        id: format!("{:020}", now.timestamp_millis()),
+        created_at: now.to_rfc3339(),
+        from_email,
+        to_email: account_index::normalize_email(to_email),
+        data,
+        absent_keys,
+    };
+
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let dir = undo_dir()?;
+    secure_create_dir(&dir).map_err(|e| e.to_string())?;
+    let json = zeroize::Zeroizing::new(serde_json::to_vec(&entry).map_err(|e| e.to_string())?);
+    let encrypted = vault().encrypt(&json)?;
+    secure_write_file(&dir.join(format!("{}.enc", entry.id)), &encrypted).map_err(|e| e.to_string())?;
+
+    // 超出保留数量时删除最旧的条目
+    let files = entry_files(&dir)?;
+    if files.len() > MAX_UNDO_ENTRIES {
+        for path in &files[..files.len() - MAX_UNDO_ENTRIES] {
+            secure_delete_file(path).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tracing::info!(target: "account::undo", "已保存切换前撤销快照");
+    Ok(UndoInfo::from(&entry))
+}
+
+/// 列出撤销栈（最新的在前）
+pub fn list() -> Result<Vec<UndoInfo>, String> {
+    vault().ensure_unlocked()?;
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let mut infos = Vec::new();
+    for path in entry_files(&undo_dir()?)?.iter().rev() {
+        match read_entry(path) {
+            Ok(entry) => infos.push(UndoInfo::from(&entry)),
+            Err(e) => tracing::warn!(target: "account::undo", error = %e, "撤销快照无法读取，跳过"),
+        }
+    }
+    Ok(infos)
+}
+
+/// 删除数据库中切换前不存在的字段（主库与备份库）
+///
+/// 按当前数据库的字段列表解析字段定义，通配规则匹配到的字段同样处理；
+/// 凡是受管理但不在快照中的字段都删除，另外删除快照中记录的缺失字段。
+fn remove_absent_keys(entry: &UndoEntry) -> Result<(), String> {
+    let key_set = key_sets::current();
+    let db_path = state_db_path()?;
+    for path in [db_path.clone(), db_path.with_extension("vscdb.backup")] {
+        if !path.exists() {
+            continue;
+        }
+        let conn = rusqlite::Connection::open(&path).map_err(|e| e.to_string())?;
+        let tracked = key_set.resolve(&key_sets::list_db_keys(&conn)?);
+        for key in tracked.iter().chain(&entry.absent_keys) {
+            if entry.data.contains_key(key) {
+                continue;
+            }
+            conn.execute("DELETE FROM ItemTable WHERE key = ?", [key])
+                .map_err(|e| format!("删除字段失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 将最近一次撤销快照写回数据库，成功后从撤销栈中移除
+///
+/// 调用方负责在写回前关闭、写回后重启 Antigravity。
+pub fn restore_last() -> Result<UndoInfo, String> {
+    vault().ensure_unlocked()?;
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let path = entry_files(&undo_dir()?)?
+        .pop()
+        .ok_or("没有可撤销的切换")?;
+    let entry = read_entry(&path)?;
+
+    // 撤销快照取自本机，扩展密钥始终恢复
+    restore::restore_backup_data(
+        &Value::Object(entry.data.clone()),
+        key_groups::ALL_GROUPS,
+        SecretRestorePolicy::Include,
+    )?;
+    remove_absent_keys(&entry)?;
+    secure_delete_file(&path).map_err(|e| e.to_string())?;
+
+    tracing::info!(target: "account::undo", "已撤销最近一次切换");
+    Ok(UndoInfo::from(&entry))
+}
+
+/// 丢弃最近一次撤销快照（切换在修改数据库前被取消或失败时调用）
+pub fn discard_last() -> Result<UndoInfo, String> {
+    vault().ensure_unlocked()?;
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let path = entry_files(&undo_dir()?)?
+        .pop()
+        .ok_or("没有可丢弃的撤销快照")?;
+    let entry = read_entry(&path)?;
+    secure_delete_file(&path).map_err(|e| e.to_string())?;
+
+    tracing::info!(target: "account::undo", "已丢弃未使用的撤销快照");
+    Ok(UndoInfo::from(&entry))
+}
+
+/// 删除涉及指定账户的撤销快照（删除账户备份时调用，避免令牌残留）
+///
+/// 需要保险库已解锁；无法读取的条目不删除，计入 `unchecked` 由调用方提示用户。
+pub fn purge_account(email: &str) -> Result<PurgeReport, String> {
+    vault().ensure_unlocked()?;
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let email = account_index::normalize_email(email);
+    let mut report = PurgeReport::default();
+    for path in entry_files(&undo_dir()?)? {
+        match read_entry(&path) {
+            Ok(entry) if entry.from_email.as_deref() == Some(email.as_str()) => {
+                secure_delete_file(&path).map_err(|e| e.to_string())?;
+                report.removed += 1;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(target: "account::undo", error = %e, "撤销快照无法读取，未确认是否涉及该账户");
+                report.unchecked += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// 清空撤销栈
+pub fn clear() -> Result<(), String> {
+    let _guard = UNDO_LOCK.lock().unwrap();
+    let dir = undo_dir()?;
+    for path in entry_files(&dir)? {
+        secure_delete_file(&path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antigravity::secret_storage::SECRET_KEY_PREFIX;
+    use crate::path_utils::use_test_config_dir;
+    use rusqlite::Connection;
+
+    /// 在测试数据目录中创建登录了指定账户的数据库
+    fn write_state_db(email: &str, extra: &[(&str, &str)]) -> PathBuf {
+        let db_path = state_db_path().unwrap();
+        fs::create_dir_all(db_path.parent().unwrap()).unwrap();
+        let _ = fs::remove_file(&db_path);
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch("CREATE TABLE ItemTable (key TEXT UNIQUE ON CONFLICT REPLACE, value BLOB)")
+            .unwrap();
+        let auth = serde_json::json!({ "email": email, "apiKey": "token" }).to_string();
+        conn.execute("INSERT INTO ItemTable VALUES (?, ?)", [database::AUTH_STATUS, auth.as_str()])
+            .unwrap();
+        for (key, value) in extra {
+            conn.execute("INSERT INTO ItemTable VALUES (?, ?)", [key, value]).unwrap();
+        }
+        db_path
+    }
+
+    fn db_value(db_path: &Path, key: &str) -> Option<String> {
+        let conn = Connection::open(db_path).unwrap();
+        conn.query_row("SELECT value FROM ItemTable WHERE key = ?", [key], |row| row.get(0))
+            .ok()
+    }
+
+    #[test]
+    fn test_push_trims_to_max_entries() {
+        use_test_config_dir("undo-trim");
+        write_state_db("a@example.com", &[]);
+
+        let mut pushed = Vec::new();
+        for _ in 0..MAX_UNDO_ENTRIES + 3 {
+            pushed.push(push_pre_switch("B@example.com").unwrap());
+            // ID 为毫秒时间戳
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let entries = list().unwrap();
+        assert_eq!(entries.len(), MAX_UNDO_ENTRIES);
+        // 最新的在前，最旧的三条已删除
+        assert_eq!(entries[0].id, pushed.last().unwrap().id);
+        assert_eq!(entries.last().unwrap().id, pushed[3].id);
+        assert_eq!(entries[0].from_email.as_deref(), Some("a@example.com"));
+        assert_eq!(entries[0].to_email, "b@example.com");
+    }
+
+    #[test]
+    fn test_restore_last_removes_absent_keys() {
+        use_test_config_dir("undo-restore");
+        let db_path = write_state_db("a@example.com", &[]);
+        push_pre_switch("b@example.com").unwrap();
+
+        // 切换写入了目标账户的认证信息及切换前不存在的字段
+        write_state_db(
+            "b@example.com",
+            &[(database::USER_SETTINGS, "{\"theme\":\"dark\"}")],
+        );
+
+        let undone = restore_last().unwrap();
+        assert_eq!(undone.from_email.as_deref(), Some("a@example.com"));
+        assert!(db_value(&db_path, database::USER_SETTINGS).is_none());
+        let auth = db_value(&db_path, database::AUTH_STATUS).unwrap();
+        assert!(auth.contains("a@example.com"));
+        assert!(list().unwrap().is_empty());
+        assert!(restore_last().is_err());
+    }
+
+    #[test]
+    fn test_restore_last_removes_pattern_keys() {
+        use_test_config_dir("undo-restore-pattern");
+        let kept_secret = format!("{}{{\"extensionId\":\"kept\"}}", SECRET_KEY_PREFIX);
+        let new_secret = format!("{}{{\"extensionId\":\"new\"}}", SECRET_KEY_PREFIX);
+        let db_path = write_state_db("a@example.com", &[(kept_secret.as_str(), "{\"data\":[1]}")]);
+        push_pre_switch("b@example.com").unwrap();
+
+        // 切换写入了匹配通配规则的新字段
+        write_state_db(
+            "b@example.com",
+            &[
+                (new_secret.as_str(), "{\"data\":[2]}"),
+                ("antigravity.notificationState", "{}"),
+                ("unrelated.key", "kept"),
+            ],
+        );
+
+        restore_last().unwrap();
+        assert!(db_value(&db_path, &new_secret).is_none());
+        assert!(db_value(&db_path, "antigravity.notificationState").is_none());
+        assert_eq!(db_value(&db_path, &kept_secret).as_deref(), Some("{\"data\":[1]}"));
+        assert_eq!(db_value(&db_path, "unrelated.key").as_deref(), Some("kept"));
+    }
+
+    #[test]
+    fn test_purge_account_reports_unreadable_entries() {
+        use_test_config_dir("undo-purge");
+        write_state_db("a@example.com", &[]);
+        push_pre_switch("b@example.com").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        write_state_db("b@example.com", &[]);
+        push_pre_switch("a@example.com").unwrap();
+
+        // 损坏的条目无法确认归属，保留并计入报告
+        let corrupt = undo_dir().unwrap().join("00000000000000000001.enc");
+        fs::write(&corrupt, b"garbage").unwrap();
+
+        let report = purge_account("A@example.com").unwrap();
+        assert_eq!(report, PurgeReport { removed: 1, unchecked: 1 });
+        assert!(corrupt.exists());
+        let remaining = list().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].from_email.as_deref(), Some("b@example.com"));
+    }
+}
@@ -0,0 +1,425 @@
+// Antigravity 账户文件模块
+// 部分账户状态保存在 ItemTable 之外，例如扩展的 globalStorage/<扩展 ID>/ 目录和 User/settings.json，
+// 按字段定义（key_sets 的 `files`）将这些文件一起写入加密备份，并在恢复时原子地替换
+//
+// 备份格式：
+// - `user_files`：根路径 -> { kind: file/dir/absent, group }，absent 表示备份时不存在（恢复时删除）
+// - `userfile:<根路径>[/<子路径>]`：单个文件内容（base64），每个文件单独去重存储
+//
+// 恢复时先把备份中的文件写入 User 目录下的临时目录，再逐个用重命名替换目标，
+// 任一步失败或随后数据库恢复失败都会把原文件移回原处。
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::antigravity::key_groups::KeyGroup;
+use crate::antigravity::key_sets::FileDefinition;
+
+/// 备份中文件索引的字段名
+pub const FILES_INDEX_KEY: &str = "user_files";
+
+/// 备份中单个文件的字段名前缀
+pub const FILE_KEY_PREFIX: &str = "userfile:";
+
+/// 单个文件的大小上限
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 单个根路径下文件总大小上限（超出时跳过该根路径）
+const MAX_ROOT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 恢复时使用的临时目录前缀（位于 User 目录下，保证与目标在同一文件系统）
+const STAGING_PREFIX: &str = ".agent-restore-";
+
+/// 根路径的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RootKind {
+    File,
+    Dir,
+    /// 备份时不存在，恢复时删除目标
+    Absent,
+}
+
+/// 文件索引中的根路径条目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRoot {
+    pub kind: RootKind,
+    #[serde(default)]
+    pub group: Option<KeyGroup>,
+}
+
+/// 规范化并校验相对 User 目录的路径
+///
+/// 只允许普通路径段（不允许绝对路径、`..`），不允许指向状态数据库。
+pub fn normalize_root(path: &str) -> Result<String, String> {
+    let parts = relative_parts(path).ok_or_else(|| format!("文件路径无效: {}", path))?;
+    let root = parts.join("/");
+    let lower = root.to_ascii_lowercase();
+    if lower.starts_with("globalstorage/state.vscdb") || lower.starts_with(STAGING_PREFIX) {
+        return Err(format!("文件路径不能指向状态数据库: {}", path));
+    }
+    Ok(root)
+}
+
+/// 两个根路径是否相同或互相包含
+pub fn roots_overlap(a: &str, b: &str) -> bool {
+    a == b || a.starts_with(&format!("{}/", b)) || b.starts_with(&format!("{}/", a))
+}
+
+fn relative_parts(path: &str) -> Option<Vec<String>> {
+    let normalized = path.replace('\\', "/");
+    let mut parts = Vec::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?.to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then_some(parts)
+}
+
+fn file_key(root: &str, sub: Option<&str>) -> String {
+    match sub {
+        Some(sub) => format!("{}{}/{}", FILE_KEY_PREFIX, root, sub),
+        None => format!("{}{}", FILE_KEY_PREFIX, root),
+    }
+}
+
+fn encode_file(content: &[u8]) -> Value {
+    serde_json::json!({ "data": STANDARD.encode(content) })
+}
+
+fn decode_file(value: &Value) -> Result<Vec<u8>, String> {
+    let data = value.get("data").and_then(|v| v.as_str()).ok_or("文件数据格式无效")?;
+    STANDARD.decode(data).map_err(|e| format!("文件数据解码失败: {}", e))
+}
+
+/// 递归收集目录中的文件（跳过符号链接）
+fn collect_dir(dir: &Path, prefix: &str, out: &mut Vec<(String, PathBuf)>) -> Result<(), String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("读取目录 {} 失败: {}", dir.display(), e))?
+        .flatten()
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let sub = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+        let meta = fs::symlink_metadata(entry.path()).map_err(|e| e.to_string())?;
+        if meta.is_dir() {
+            collect_dir(&entry.path(), &sub, out)?;
+        } else if meta.is_file() {
+            out.push((sub, entry.path()));
+        }
+    }
+    Ok(())
+}
+
+/// 读取根路径下的文件（子路径 -> 内容），超出大小限制时返回错误
+fn read_root(path: &Path, files: &[(String, PathBuf)]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut total = 0u64;
+    let mut result = Vec::new();
+    for (sub, file) in files {
+        let size = fs::metadata(file).map_err(|e| e.to_string())?.len();
+        total += size;
+        if size > MAX_FILE_BYTES || total > MAX_ROOT_BYTES {
+            return Err(format!("{} 超出备份大小限制", path.display()));
+        }
+        let content = fs::read(file).map_err(|e| format!("读取文件 {} 失败: {}", file.display(), e))?;
+        result.push((sub.clone(), content));
+    }
+    Ok(result)
+}
+
+/// 读取定义中的账户文件，生成备份字段
+///
+/// 单个根路径读取失败或超出大小限制时只记录警告并跳过（恢复时保持目标不变）。
+///
+/// # 参数
+/// - `user_dir`: Antigravity 的 User 目录
+/// - `definitions`: 文件定义
+pub fn capture(user_dir: &Path, definitions: &[FileDefinition]) -> Map<String, Value> {
+    let mut data = Map::new();
+    if definitions.is_empty() {
+        return data;
+    }
+
+    let mut index = BTreeMap::new();
+    for def in definitions {
+        let root = match normalize_root(&def.path) {
+            Ok(root) => root,
+            Err(e) => {
+                tracing::warn!(target: "backup::files", error = %e, "跳过无效的文件定义");
+                continue;
+            }
+        };
+        let path = user_dir.join(&root);
+        let captured = match fs::symlink_metadata(&path) {
+            Err(_) => Ok((RootKind::Absent, Vec::new())),
+            Ok(meta) if meta.is_file() => {
+                read_root(&path, &[(String::new(), path.clone())]).map(|files| (RootKind::File, files))
+            }
+            Ok(meta) if meta.is_dir() => {
+                let mut files = Vec::new();
+                collect_dir(&path, "", &mut files)
+                    .and_then(|_| read_root(&path, &files))
+                    .map(|files| (RootKind::Dir, files))
+            }
+            Ok(_) => Err(format!("{} 不是普通文件或目录", path.display())),
+        };
+
+        match captured {
+            Ok((kind, files)) => {
+                for (sub, content) in files {
+                    let sub = (!sub.is_empty()).then_some(sub.as_str());
+                    data.insert(file_key(&root, sub), encode_file(&content));
+                }
+                index.insert(root, FileRoot { kind, group: def.group });
+            }
+            Err(e) => tracing::warn!(target: "backup::files", root = %root, error = %e, "跳过账户文件"),
+        }
+    }
+
+    tracing::debug!(target: "backup::files", roots = index.len(), files = data.len(), "已读取账户文件");
+    data.insert(
+        FILES_INDEX_KEY.to_string(),
+        serde_json::to_value(&index).unwrap_or_default(),
+    );
+    data
+}
+
+/// 备份中的文件索引
+fn backup_index(backup: &Value) -> BTreeMap<String, FileRoot> {
+    backup
+        .get(FILES_INDEX_KEY)
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// 已写入临时目录、等待替换的文件
+pub struct PreparedFiles {
+    user_dir: PathBuf,
+    staging: PathBuf,
+    roots: Vec<(String, RootKind)>,
+}
+
+/// 已替换的文件（可提交或回滚）
+pub struct AppliedFiles {
+    prepared: PreparedFiles,
+    /// 已处理的根路径及是否移走了原文件
+    done: Vec<(String, bool)>,
+}
+
+/// 将备份中属于所选分组的文件写入临时目录
+///
+/// # 参数
+/// - `user_dir`: Antigravity 的 User 目录
+/// - `backup`: 备份数据
+/// - `groups`: 需要恢复的分组
+pub fn prepare(user_dir: &Path, backup: &Value, groups: &[KeyGroup]) -> Result<PreparedFiles, String> {
+    let staging = user_dir.join(format!("{}{}", STAGING_PREFIX, chrono::Local::now().timestamp_millis()));
+    let mut prepared = PreparedFiles {
+        user_dir: user_dir.to_path_buf(),
+        staging,
+        roots: Vec::new(),
+    };
+
+    for (root, entry) in backup_index(backup) {
+        if entry.group.is_some_and(|group| !groups.contains(&group)) {
+            continue;
+        }
+        // 索引来自解密后的备份，仍重新校验路径以防越界写入
+        let root = normalize_root(&root)?;
+        let new_root = prepared.staging.join("new").join(&root);
+        let result = match entry.kind {
+            RootKind::Absent => Ok(()),
+            RootKind::File => backup
+                .get(file_key(&root, None))
+                .ok_or_else(|| format!("备份中缺少文件 {}", root))
+                .and_then(decode_file)
+                .and_then(|content| write_staged(&new_root, &content)),
+            RootKind::Dir => {
+                fs::create_dir_all(&new_root).map_err(|e| format!("创建临时目录失败: {}", e))?;
+                stage_dir(backup, &root, &new_root)
+            }
+        };
+        if let Err(e) = result {
+            let _ = fs::remove_dir_all(&prepared.staging);
+            return Err(e);
+        }
+        prepared.roots.push((root, entry.kind));
+    }
+    Ok(prepared)
+}
+
+fn write_staged(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建临时目录失败: {}", e))?;
+    }
+    fs::write(path, content).map_err(|e| format!("写入临时文件失败: {}", e))
+}
+
+fn stage_dir(backup: &Value, root: &str, new_root: &Path) -> Result<(), String> {
+    let prefix = format!("{}{}/", FILE_KEY_PREFIX, root);
+    let Some(obj) = backup.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in obj.iter().filter(|(key, _)| key.starts_with(&prefix)) {
+        let sub = &key[prefix.len()..];
+        let parts = relative_parts(sub).ok_or_else(|| format!("备份中的文件路径无效: {}", sub))?;
+        let target = parts.iter().fold(new_root.to_path_buf(), |path, part| path.join(part));
+        write_staged(&target, &decode_file(value)?)?;
+    }
+    Ok(())
+}
+
+impl PreparedFiles {
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// 用临时目录中的文件替换目标，失败时还原已替换的部分
+    pub fn apply(self) -> Result<AppliedFiles, String> {
+        let mut applied = AppliedFiles { prepared: self, done: Vec::new() };
+        let roots = applied.prepared.roots.clone();
+        for (root, kind) in roots {
+            if let Err(e) = applied.replace(&root, kind) {
+                applied.rollback();
+                return Err(format!("替换账户文件 {} 失败: {}", root, e));
+            }
+        }
+        Ok(applied)
+    }
+}
+
+impl AppliedFiles {
+    fn replace(&mut self, root: &str, kind: RootKind) -> std::io::Result<()> {
+        let target = self.prepared.user_dir.join(root);
+        let old = self.prepared.staging.join("old").join(root);
+
+        let had_old = fs::symlink_metadata(&target).is_ok();
+        if had_old {
+            if let Some(parent) = old.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&target, &old)?;
+        }
+        self.done.push((root.to_string(), had_old));
+
+        if kind != RootKind::Absent {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(self.prepared.staging.join("new").join(root), &target)?;
+        }
+        Ok(())
+    }
+
+    /// 已替换的根路径数量
+    pub fn count(&self) -> usize {
+        self.done.len()
+    }
+
+    /// 确认替换，删除原文件
+    pub fn commit(self) {
+        if !self.prepared.staging.exists() {
+            return;
+        }
+        if let Err(e) = fs::remove_dir_all(&self.prepared.staging) {
+            tracing::warn!(target: "restore::files", error = %e, "删除临时目录失败");
+        }
+    }
+
+    /// 撤销替换，把原文件移回原处
+    pub fn rollback(self) {
+        for (root, had_old) in self.done.iter().rev() {
+            let target = self.prepared.user_dir.join(root);
+            if let Err(e) = remove_path(&target) {
+                tracing::error!(target: "restore::files", root = %root, error = %e, "回滚时删除文件失败");
+                continue;
+            }
+            if *had_old {
+                if let Err(e) = fs::rename(self.prepared.staging.join("old").join(root), &target) {
+                    tracing::error!(target: "restore::files", root = %root, error = %e, "回滚时还原文件失败");
+                }
+            }
+        }
+        tracing::warn!(target: "restore::files", count = self.done.len(), "账户文件已回滚");
+        let _ = fs::remove_dir_all(&self.prepared.staging);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_root() {
+        assert_eq!(normalize_root("globalStorage\\ext.id/").unwrap(), "globalStorage/ext.id");
+        assert_eq!(normalize_root("./settings.json").unwrap(), "settings.json");
+        assert!(normalize_root("../secrets").is_err());
+        assert!(normalize_root("/etc/passwd").is_err());
+        assert!(normalize_root("globalStorage/state.vscdb").is_err());
+        assert!(roots_overlap("globalStorage/ext", "globalStorage/ext/sub"));
+        assert!(!roots_overlap("globalStorage/ext", "globalStorage/ext2"));
+    }
+
+    #[test]
+    fn test_capture_and_restore_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("ag-user-files-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("globalStorage/ext/nested")).unwrap();
+        fs::write(dir.join("settings.json"), b"{\"a\":1}").unwrap();
+        fs::write(dir.join("globalStorage/ext/nested/state.json"), b"old-user").unwrap();
+
+        let defs = vec![
+            FileDefinition { path: "settings.json".into(), group: Some(KeyGroup::Settings), description: None },
+            FileDefinition { path: "globalStorage/ext".into(), group: None, description: None },
+            FileDefinition { path: "missing".into(), group: None, description: None },
+        ];
+        let backup = Value::Object(capture(&dir, &defs));
+        assert_eq!(backup_index(&backup).len(), 3);
+
+        // 切换后其他用户的文件
+        fs::write(dir.join("settings.json"), b"{\"a\":2}").unwrap();
+        fs::write(dir.join("globalStorage/ext/nested/state.json"), b"new-user").unwrap();
+        fs::write(dir.join("globalStorage/ext/extra.json"), b"x").unwrap();
+        fs::write(dir.join("missing"), b"x").unwrap();
+
+        // 只恢复认证分组：settings.json 保持不变，无分组的根路径被替换
+        let applied = prepare(&dir, &backup, &[KeyGroup::Auth]).unwrap().apply().unwrap();
+        assert_eq!(applied.count(), 2);
+        applied.commit();
+        assert_eq!(fs::read(dir.join("settings.json")).unwrap(), b"{\"a\":2}");
+        assert_eq!(fs::read(dir.join("globalStorage/ext/nested/state.json")).unwrap(), b"old-user");
+        assert!(!dir.join("globalStorage/ext/extra.json").exists());
+        assert!(!dir.join("missing").exists());
+
+        // 回滚恢复替换前的文件
+        let applied = prepare(&dir, &backup, &[KeyGroup::Settings]).unwrap().apply().unwrap();
+        assert_eq!(fs::read(dir.join("settings.json")).unwrap(), b"{\"a\":1}");
+        applied.rollback();
+        assert_eq!(fs::read(dir.join("settings.json")).unwrap(), b"{\"a\":2}");
+        assert!(fs::read_dir(&dir).unwrap().flatten().all(|e| !e
+            .file_name()
+            .to_string_lossy()
+            .starts_with(STAGING_PREFIX)));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,270 @@
+// Antigravity 用户状态解析模块
+// 解析认证信息中的 userStatusProtoBinaryBase64（protobuf 编码），提取各模型的配额
+//
+// 没有公开的 .proto 定义，这里按 wire format 遍历整棵消息树：
+// 模型配置消息包含一个字符串字段（模型名称）和第 15 号子消息（配额），
+// 配额消息的第 1 号字段为 float32 剩余比例，第 2 号字段为重置时间（Timestamp）。
+// 前端旧版解析器查找的 `7a 0d 0d` 标记即为该结构的编码。
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+
+/// 配额子消息的字段号
+const QUOTA_FIELD: u64 = 15;
+
+/// 子消息的最大递归深度
+const MAX_DEPTH: usize = 8;
+
+/// 单个模型的配额
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelQuota {
+    pub label: String,
+    pub remaining_fraction: f64,
+    /// 重置时间（Unix 秒）
+    pub reset_time: Option<i64>,
+}
+
+/// 账户积分（认证信息中存在时记录）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Credits {
+    pub prompt_credits: Option<i64>,
+    pub flow_credits: Option<i64>,
+}
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Fixed32([u8; 4]),
+    Fixed64,
+    Bytes(&'a [u8]),
+}
+
+struct Field<'a> {
+    number: u64,
+    value: FieldValue<'a>,
+}
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos)?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// 按 wire format 解析一层消息；任何字段不合法时返回 None（说明不是消息）
+fn parse_message(bytes: &[u8]) -> Option<Vec<Field<'_>>> {
+    let mut fields = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let tag = read_varint(bytes, &mut pos)?;
+        let number = tag >> 3;
+        if number == 0 {
+            return None;
+        }
+        let value = match tag & 7 {
+            0 => FieldValue::Varint(read_varint(bytes, &mut pos)?),
+            1 => {
+                bytes.get(pos..pos + 8)?;
+                pos += 8;
+                FieldValue::Fixed64
+            }
+            2 => {
+                let len = usize::try_from(read_varint(bytes, &mut pos)?).ok()?;
+                let end = pos.checked_add(len)?;
+                let data = bytes.get(pos..end)?;
+                pos = end;
+                FieldValue::Bytes(data)
+            }
+            5 => {
+                let data: [u8; 4] = bytes.get(pos..pos + 4)?.try_into().ok()?;
+                pos += 4;
+                FieldValue::Fixed32(data)
+            }
+            _ => return None,
+        };
+        fields.push(Field { number, value });
+    }
+    Some(fields)
+}
+
+/// 判断字节串是否像模型名称（可打印文本且包含字母）
+fn as_label(data: &[u8]) -> Option<&str> {
+    let text = std::str::from_utf8(data).ok()?;
+    let valid = (2..=80).contains(&text.len())
+        && text.chars().all(|c| !c.is_control())
+        && text.chars().any(|c| c.is_ascii_alphabetic());
+    valid.then_some(text)
+}
+
+/// 解析配额子消息：剩余比例与重置时间
+fn parse_quota(data: &[u8]) -> Option<(f64, Option<i64>)> {
+    let fields = parse_message(data)?;
+    let mut fraction = None;
+    let mut reset_time = None;
+    for field in fields {
+        match (field.number, field.value) {
+            (1, FieldValue::Fixed32(raw)) => fraction = Some(f64::from(f32::from_le_bytes(raw))),
+            (2, FieldValue::Bytes(timestamp)) => {
+                reset_time = parse_message(timestamp).and_then(|ts| {
+                    ts.into_iter().find_map(|f| match (f.number, f.value) {
+                        (1, FieldValue::Varint(seconds)) => i64::try_from(seconds).ok(),
+                        _ => None,
+                    })
+                });
+            }
+            _ => {}
+        }
+    }
+    // 配额已用尽时剩余比例字段可能被省略（proto3 默认值）
+    Some((fraction.unwrap_or(0.0), reset_time))
+}
+
+fn collect_models(bytes: &[u8], depth: usize, models: &mut Vec<ModelQuota>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let Some(fields) = parse_message(bytes) else {
+        return;
+    };
+
+    let mut label = None;
+    let mut quota = None;
+    for field in &fields {
+        if let FieldValue::Bytes(data) = field.value {
+            if field.number == QUOTA_FIELD {
+                quota = quota.or_else(|| parse_quota(data));
+            } else if label.is_none() {
+                label = as_label(data);
+            }
+        }
+    }
+
+    if let (Some(label), Some((remaining_fraction, reset_time))) = (label, quota) {
+        if !models.iter().any(|m| m.label == label) {
+            models.push(ModelQuota {
+                label: label.to_string(),
+                remaining_fraction,
+                reset_time,
+            });
+        }
+        return;
+    }
+
+    for field in fields {
+        if let FieldValue::Bytes(data) = field.value {
+            collect_models(data, depth + 1, models);
+        }
+    }
+}
+
+/// 解析 protobuf 二进制，提取各模型配额
+pub fn parse_model_quotas(bytes: &[u8]) -> Vec<ModelQuota> {
+    let mut models = Vec::new();
+    collect_models(bytes, 0, &mut models);
+    models
+}
+
+/// 从认证信息（antigravityAuthStatus）中提取各模型配额
+pub fn model_quotas_from_auth(auth: &Value) -> Vec<ModelQuota> {
+    auth.get("userStatusProtoBinaryBase64")
+        .and_then(|v| v.as_str())
+        .and_then(|encoded| STANDARD.decode(encoded).ok())
+        .map(|bytes| parse_model_quotas(&bytes))
+        .unwrap_or_default()
+}
+
+fn find_number(value: &Value, key: &str) -> Option<i64> {
+    match value {
+        Value::Object(obj) => obj
+            .get(key)
+            .and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+            .or_else(|| obj.values().find_map(|v| find_number(v, key))),
+        Value::Array(items) => items.iter().find_map(|v| find_number(v, key)),
+        _ => None,
+    }
+}
+
+/// 从认证信息中提取积分（字段可能嵌套在 planStatus 等对象中）
+pub fn credits_from_auth(auth: &Value) -> Credits {
+    Credits {
+        prompt_credits: find_number(auth, "availablePromptCredits"),
+        flow_credits: find_number(auth, "availableFlowCredits"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return out;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn bytes_field(number: u64, data: &[u8]) -> Vec<u8> {
+        let mut out = varint(number << 3 | 2);
+        out.extend(varint(data.len() as u64));
+        out.extend_from_slice(data);
+        out
+    }
+
+    fn model_config(label: &str, fraction: f32, reset: u64) -> Vec<u8> {
+        let timestamp = [varint(1 << 3), varint(reset)].concat();
+        let mut quota = vec![0x0d];
+        quota.extend(fraction.to_le_bytes());
+        quota.extend(bytes_field(2, &timestamp));
+        [bytes_field(1, label.as_bytes()), bytes_field(QUOTA_FIELD, &quota)].concat()
+    }
+
+    #[test]
+    fn test_parse_nested_model_quotas() {
+        let models = [
+            bytes_field(2, &model_config("Gemini 3 Pro (High)", 0.25, 1_760_000_000)),
+            bytes_field(2, &model_config("Claude Sonnet 4.5", 1.0, 1_760_003_600)),
+        ]
+        .concat();
+        let status = [
+            bytes_field(1, b"someone@example.com"),
+            bytes_field(7, &bytes_field(3, &models)),
+        ]
+        .concat();
+
+        let quotas = parse_model_quotas(&status);
+        assert_eq!(quotas.len(), 2);
+        assert_eq!(quotas[0].label, "Gemini 3 Pro (High)");
+        assert_eq!(quotas[0].remaining_fraction, 0.25);
+        assert_eq!(quotas[0].reset_time, Some(1_760_000_000));
+        assert_eq!(quotas[1].label, "Claude Sonnet 4.5");
+        assert_eq!(quotas[1].remaining_fraction, 1.0);
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert!(parse_model_quotas(&[0xff, 0xff, 0xff]).is_empty());
+        assert!(parse_model_quotas(b"").is_empty());
+    }
+
+    #[test]
+    fn test_credits_from_nested_auth() {
+        let auth = serde_json::json!({
+            "email": "someone@example.com",
+            "planStatus": { "availablePromptCredits": 500, "availableFlowCredits": "120" }
+        });
+        let credits = credits_from_auth(&auth);
+        assert_eq!(credits.prompt_credits, Some(500));
+        assert_eq!(credits.flow_credits, Some(120));
+    }
+}
@@ -0,0 +1,615 @@
+// Antigravity 备份保险库模块
+// 负责管理备份加密模式：机器绑定（默认）或主密码保险库（可选）
+// 主密码模式下密钥由 Argon2id 派生，仅在解锁会话期间保存在内存中
+// 机器密钥同样只派生一次，缓存在会话内存中（Zeroizing，释放时清零）
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+use crate::antigravity::account_keys;
+use crate::path_utils::AppPaths;
+use crate::utils::crypto::{
+    decrypt_machine_data_with_key, decrypt_vault_data, derive_key_from_password,
+    derive_machine_key_v2, encrypt_machine_data_with_key, encrypt_vault_data, is_data_key_encrypted, is_encrypted,
+    is_vault_encrypted, random_bytes, secure_create_dir, secure_delete_file, secure_write_file,
+    validate_password_strength,
+};
+
+/// 保险库配置文件名（位于备份目录中，随备份一起迁移）
+const VAULT_CONFIG_FILE: &str = "vault.meta";
+
+/// 用于校验主密码的已知明文
+const VERIFIER_PLAINTEXT: &[u8] = b"antigravity-agent-vault-verifier";
+
+/// 默认空闲自动锁定时间（15 分钟）
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 15 * 60;
+
+/// 保险库锁定时返回的错误信息（前端据此弹出解锁对话框）
+pub const VAULT_LOCKED_ERROR: &str = "保险库已锁定，请先输入主密码解锁";
+
+/// 备份加密模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultMode {
+    /// 绑定机器 ID 和用户名（默认）
+    Machine,
+    /// 使用主密码派生的密钥
+    Password,
+}
+
+/// 保险库配置（持久化到 vault.meta，不包含任何密钥材料）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultConfig {
+    version: u32,
+    mode: VaultMode,
+    /// 主密码 salt（hex 编码）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    /// 加密后的校验数据（hex 编码），用于验证主密码是否正确
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    verifier: Option<String>,
+    /// 空闲自动锁定时间（秒），0 表示不自动锁定
+    idle_timeout_secs: u64,
+}
+
+impl Default for VaultConfig {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            mode: VaultMode::Machine,
+            salt: None,
+            verifier: None,
+            idle_timeout_secs: DEFAULT_IDLE_TIMEOUT_SECS,
+        }
+    }
+}
+
+/// 保险库状态（返回给前端）
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub mode: VaultMode,
+    pub unlocked: bool,
+    pub idle_timeout_secs: u64,
+    /// 距离自动锁定的剩余秒数（仅在解锁时有值）
+    pub seconds_until_lock: Option<u64>,
+}
+
+/// 已解锁的会话
+struct VaultSession {
+    key: Zeroizing<[u8; 32]>,
+    last_used: Instant,
+}
+
+/// 保险库管理器
+pub struct VaultManager {
+    session: Mutex<Option<VaultSession>>,
+    /// 缓存的机器密钥（V2 Argon2id 派生结果）
+    machine_key: Mutex<Option<Zeroizing<[u8; 32]>>>,
+}
+
+static VAULT: LazyLock<VaultManager> = LazyLock::new(|| VaultManager {
+    session: Mutex::new(None),
+    machine_key: Mutex::new(None),
+});
+
+/// 获取全局保险库管理器
+pub fn vault() -> &'static VaultManager {
+    &VAULT
+}
+
+impl VaultManager {
+    /// 获取当前状态
+    pub fn status(&self) -> Result<VaultStatus, String> {
+        let config = load_config()?;
+        self.lock_if_idle();
+
+        let session = self.session.lock().unwrap();
+        let unlocked = config.mode == VaultMode::Machine || session.is_some();
+        let seconds_until_lock = match (&*session, config.idle_timeout_secs) {
+            (Some(s), timeout) if timeout > 0 => {
+                Some(timeout.saturating_sub(s.last_used.elapsed().as_secs()))
+            }
+            _ => None,
+        };
+
+        Ok(VaultStatus {
+            mode: config.mode,
+            unlocked,
+            idle_timeout_secs: config.idle_timeout_secs,
+            seconds_until_lock,
+        })
+    }
+
+    /// 当前是否处于主密码模式
+    ///
+    /// 配置无法读取时返回错误而不是按机器模式处理，避免在主密码模式下误用机器密钥加密。
+    pub fn is_password_mode(&self) -> Result<bool, String> {
+        load_config().map(|c| c.mode == VaultMode::Password)
+    }
+
+    /// 确保保险库可用（机器模式总是可用，主密码模式需已解锁）
+    pub fn ensure_unlocked(&self) -> Result<(), String> {
+        if !self.is_password_mode()? {
+            return Ok(());
+        }
+        self.lock_if_idle();
+        if self.session.lock().unwrap().is_some() {
+            Ok(())
+        } else {
+            Err(VAULT_LOCKED_ERROR.to_string())
+        }
+    }
+
+    /// 使用主密码解锁
+    pub fn unlock(&self, password: &str) -> Result<(), String> {
+        let config = load_config()?;
+        if config.mode != VaultMode::Password {
+            return Err("当前未启用主密码保险库".to_string());
+        }
+
+        let key = verify_password(&config, password)?;
+        *self.session.lock().unwrap() = Some(VaultSession {
+            key,
+            last_used: Instant::now(),
+        });
+
+        tracing::info!(target: "vault::session", "🔓 保险库已解锁");
+        Ok(())
+    }
+
+    /// 立即锁定（清除内存中的密钥）
+    pub fn lock(&self) {
+        if self.session.lock().unwrap().take().is_some() {
+            account_keys::clear_cache();
+            tracing::info!(target: "vault::session", "🔒 保险库已锁定");
+        }
+    }
+
+    /// 如果空闲超时则锁定，返回本次是否执行了锁定
+    pub fn lock_if_idle(&self) -> bool {
+        let timeout = match load_config() {
+            Ok(config) if config.idle_timeout_secs > 0 => config.idle_timeout_secs,
+            _ => return false,
+        };
+
+        let mut session = self.session.lock().unwrap();
+        let expired = session
+            .as_ref()
+            .is_some_and(|s| s.last_used.elapsed() >= Duration::from_secs(timeout));
+
+        if expired {
+            session.take();
+            account_keys::clear_cache();
+            tracing::info!(target: "vault::session", idle_timeout_secs = timeout, "🔒 空闲超时，保险库已自动锁定");
+        }
+        expired
+    }
+
+    /// 设置空闲自动锁定时间（秒），0 表示不自动锁定
+    pub fn set_idle_timeout(&self, secs: u64) -> Result<(), String> {
+        let mut config = load_config()?;
+        config.idle_timeout_secs = secs;
+        save_config(&config)?;
+        tracing::info!(target: "vault::config", idle_timeout_secs = secs, "已更新保险库空闲超时");
+        Ok(())
+    }
+
+    /// 加密备份数据（根据当前模式选择密钥）
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        if !self.is_password_mode()? {
+            let key = self.machine_key()?;
+            return encrypt_machine_data_with_key(plaintext, &key)
+                .map_err(|e| format!("加密失败: {}", e));
+        }
+        self.with_session_key(|key| {
+            encrypt_vault_data(plaintext, key).map_err(|e| format!("加密失败: {}", e))
+        })
+    }
+
+    /// 解密备份数据（根据数据头自动选择密钥）
+    pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, String> {
+        // 配置损坏时拒绝解密，与加密保持一致
+        self.is_password_mode()?;
+        if is_vault_encrypted(encrypted) {
+            return self.with_session_key(|key| {
+                decrypt_vault_data(encrypted, key).map_err(|e| format!("解密失败: {}", e))
+            });
+        }
+        let key = self.machine_key()?;
+        decrypt_machine_data_with_key(encrypted, &key).map_err(|e| format!("解密失败: {}", e))
+    }
+
+    /// 启用主密码保险库：把所有机器绑定的备份转换为主密码加密
+    pub fn enable_password_mode(&self, password: &str) -> Result<usize, String> {
+        validate_password_strength(password).map_err(|e| e.to_string())?;
+
+        let mut config = load_config()?;
+        if config.mode == VaultMode::Password {
+            return Err("主密码保险库已启用".to_string());
+        }
+
+        let salt: [u8; 16] = random_bytes();
+        let key = derive_key_from_password(password, &salt).map_err(|e| e.to_string())?;
+        let verifier =
+            encrypt_vault_data(VERIFIER_PLAINTEXT, &key).map_err(|e| e.to_string())?;
+
+        config.mode = VaultMode::Password;
+        config.salt = Some(hex::encode(salt));
+        config.verifier = Some(hex::encode(verifier));
+
+        let machine_key = self.machine_key()?;
+        let converted = reencrypt_backup_files(
+            &config,
+            |data| decrypt_machine_data_with_key(data, &machine_key).map_err(|e| e.to_string()),
+            |data| encrypt_vault_data(data, &key).map_err(|e| e.to_string()),
+        )?;
+
+        *self.session.lock().unwrap() = Some(VaultSession {
+            key,
+            last_used: Instant::now(),
+        });
+
+        tracing::info!(target: "vault::convert", converted = converted, "✅ 已切换到主密码保险库模式");
+        Ok(converted)
+    }
+
+    /// 关闭主密码保险库：把所有备份转换回机器绑定加密
+    pub fn disable_password_mode(&self, password: &str) -> Result<usize, String> {
+        let mut config = load_config()?;
+        if config.mode != VaultMode::Password {
+            return Err("当前未启用主密码保险库".to_string());
+        }
+
+        let key = verify_password(&config, password)?;
+        let machine_key = self.machine_key()?;
+
+        config.mode = VaultMode::Machine;
+        config.salt = None;
+        config.verifier = None;
+
+        let converted = reencrypt_backup_files(
+            &config,
+            |data| {
+                if is_vault_encrypted(data) {
+                    decrypt_vault_data(data, &key).map_err(|e| e.to_string())
+                } else {
+                    decrypt_machine_data_with_key(data, &machine_key).map_err(|e| e.to_string())
+                }
+            },
+            |data| encrypt_machine_data_with_key(data, &machine_key).map_err(|e| e.to_string()),
+        )?;
+        self.lock();
+
+        tracing::info!(target: "vault::convert", converted = converted, "✅ 已切换回机器绑定模式");
+        Ok(converted)
+    }
+
+    /// 获取机器密钥（首次调用时派生并缓存）
+    fn machine_key(&self) -> Result<Zeroizing<[u8; 32]>, String> {
+        let mut cached = self.machine_key.lock().unwrap();
+        if let Some(key) = cached.as_ref() {
+            return Ok(key.clone());
+        }
+
+        let key = derive_machine_key_v2().map_err(|e| e.to_string())?;
+        tracing::debug!(target: "vault::session", "已派生并缓存机器密钥");
+        *cached = Some(key.clone());
+        Ok(key)
+    }
+
+    /// 使用会话密钥执行操作，并刷新空闲计时
+    fn with_session_key<T>(
+        &self,
+        f: impl FnOnce(&[u8; 32]) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.lock_if_idle();
+        let mut session = self.session.lock().unwrap();
+        match session.as_mut() {
+            Some(s) => {
+                s.last_used = Instant::now();
+                f(&s.key)
+            }
+            None => Err(VAULT_LOCKED_ERROR.to_string()),
+        }
+    }
+}
+
+/// 校验主密码并返回派生密钥
+fn verify_password(config: &VaultConfig, password: &str) -> Result<Zeroizing<[u8; 32]>, String> {
+    let salt = config
+        .salt
+        .as_deref()
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or("保险库配置损坏：缺少 salt")?;
+    let verifier = config
+        .verifier
+        .as_deref()
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or("保险库配置损坏：缺少校验数据")?;
+
+    let key = derive_key_from_password(password, &salt).map_err(|e| e.to_string())?;
+    match decrypt_vault_data(&verifier, &key) {
+        Ok(plain) if plain == VERIFIER_PLAINTEXT => Ok(key),
+        _ => Err("主密码错误".to_string()),
+    }
+}
+
+fn config_path() -> Result<PathBuf, String> {
+    AppPaths::backup_dir()
+        .map(|dir| dir.join(VAULT_CONFIG_FILE))
+        .ok_or_else(|| "无法获取备份目录".to_string())
+}
+
+fn load_config() -> Result<VaultConfig, String> {
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(VaultConfig::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取保险库配置失败: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("解析保险库配置失败: {}", e))
+}
+
+fn save_config(config: &VaultConfig) -> Result<(), String> {
+    let path = config_path()?;
+    if let Some(parent) = path.parent() {
+        secure_create_dir(parent).map_err(|e| e.to_string())?;
+    }
+    secure_write_file(&path, &serialize_config(config)?).map_err(|e| e.to_string())
+}
+
+fn serialize_config(config: &VaultConfig) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(config).map_err(|e| format!("序列化保险库配置失败: {}", e))
+}
+
+/// 已重新加密、等待替换的备份文件
+struct StagedFile {
+    tmp: PathBuf,
+    target: PathBuf,
+    /// 同名的明文旧备份，替换成功后删除
+    legacy: Option<PathBuf>,
+}
+
+/// 重新加密备份目录中的所有账户备份，并写入新的保险库配置
+///
+/// 先将全部结果写入临时文件，全部成功后再统一替换；
+/// 新配置同样先写入临时文件，在所有备份替换完成后最后替换，作为提交点。
+/// 任一步失败时撤销已替换的文件并清理临时文件，备份与配置保持原状。
+fn reencrypt_backup_files(
+    config: &VaultConfig,
+    decrypt: impl Fn(&[u8]) -> Result<Vec<u8>, String>,
+    encrypt: impl Fn(&[u8]) -> Result<Vec<u8>, String>,
+) -> Result<usize, String> {
+    let dir = AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    secure_create_dir(&dir).map_err(|e| e.to_string())?;
+
+    let mut staged = Vec::new();
+    let config_path = dir.join(VAULT_CONFIG_FILE);
+    let config_tmp = dir.join(format!("{}.tmp", VAULT_CONFIG_FILE));
+    let result = stage_reencrypted_files(&dir, &decrypt, &encrypt, &mut staged).and_then(|()| {
+        secure_write_file(&config_tmp, &serialize_config(config)?)
+            .map_err(|e| format!("写入保险库配置失败: {}", e))
+    });
+    if let Err(e) = result {
+        for file in &staged {
+            let _ = fs::remove_file(&file.tmp);
+        }
+        let _ = fs::remove_file(&config_tmp);
+        return Err(e);
+    }
+
+    swap_staged(&staged, &config_tmp, &config_path)?;
+    Ok(staged.len())
+}
+
+/// 替换前原文件的暂存路径
+fn original_path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_owned();
+    name.push(".orig");
+    PathBuf::from(name)
+}
+
+/// 把重新加密的文件替换到位，最后替换保险库配置
+///
+/// 原文件先改名保留，配置替换成功后才删除；任一步失败时按相反顺序恢复原文件。
+fn swap_staged(staged: &[StagedFile], config_tmp: &Path, config_path: &Path) -> Result<(), String> {
+    let mut swapped: Vec<(&StagedFile, Option<PathBuf>)> = Vec::new();
+    let mut swap = || -> std::io::Result<()> {
+        for file in staged {
+            let original = file.target.exists().then(|| original_path(&file.target));
+            if let Some(original) = &original {
+                fs::rename(&file.target, original)?;
+            }
+            if let Err(e) = fs::rename(&file.tmp, &file.target) {
+                if let Some(original) = &original {
+                    let _ = fs::rename(original, &file.target);
+                }
+                return Err(e);
+            }
+            swapped.push((file, original));
+        }
+        fs::rename(config_tmp, config_path)
+    };
+
+    if let Err(e) = swap() {
+        for (file, original) in swapped.iter().rev() {
+            let restored = match original {
+                Some(original) => fs::rename(original, &file.target),
+                None => fs::remove_file(&file.target),
+            };
+            if let Err(restore_error) = restored {
+                tracing::error!(
+                    target: "vault::convert",
+                    file = %file.target.display(),
+                    error = %restore_error,
+                    "恢复原备份文件失败"
+                );
+            }
+        }
+        for file in staged {
+            let _ = fs::remove_file(&file.tmp);
+        }
+        let _ = fs::remove_file(config_tmp);
+        return Err(format!("替换备份文件失败: {}", e));
+    }
+
+    for (file, original) in &swapped {
+        for old in original.iter().chain(file.legacy.iter()) {
+            if let Err(e) = secure_delete_file(old) {
+                tracing::warn!(target: "vault::convert", error = %e, "删除旧备份文件失败");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stage_reencrypted_files(
+    dir: &Path,
+    decrypt: &impl Fn(&[u8]) -> Result<Vec<u8>, String>,
+    encrypt: &impl Fn(&[u8]) -> Result<Vec<u8>, String>,
+    staged: &mut Vec<StagedFile>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("读取备份目录失败: {}", e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // 快照存储（store/ 下的 blob 与清单）同样需要转换
+        if path.is_dir() {
+            stage_reencrypted_files(&path, decrypt, encrypt, staged)?;
+            continue;
+        }
+
+        let ext = path.extension().and_then(|e| e.to_str());
+        if !matches!(ext, Some("enc") | Some("json")) {
+            continue;
+        }
+
+        let Some(stem) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let target = dir.join(format!("{}.enc", stem));
+
+        // 同名的加密文件会单独处理，这里只清理重复的明文文件
+        let legacy = if ext == Some("json") {
+            if target.exists() {
+                continue;
+            }
+            Some(path.clone())
+        } else {
+            None
+        };
+
+        let content = fs::read(&path).map_err(|e| format!("读取备份 {} 失败: {}", stem, e))?;
+        // 账户数据密钥加密的文件无需转换，其密钥随密钥表一起重新加密
+        if is_data_key_encrypted(&content) {
+            continue;
+        }
+        let plaintext = if is_encrypted(&content) {
+            decrypt(&content).map_err(|e| format!("解密备份 {} 失败: {}", stem, e))?
+        } else {
+            content
+        };
+        let plaintext = Zeroizing::new(plaintext);
+
+        let encrypted = encrypt(&plaintext)?;
+        let tmp = dir.join(format!("{}.enc.tmp", stem));
+        secure_write_file(&tmp, &encrypted).map_err(|e| e.to_string())?;
+        staged.push(StagedFile { tmp, target, legacy });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path_utils::use_test_config_dir;
+
+    const PASSWORD: &str = "Correct-Horse-42";
+
+    fn manager() -> VaultManager {
+        VaultManager {
+            session: Mutex::new(None),
+            machine_key: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn test_enable_unlock_disable() {
+        use_test_config_dir("vault-modes");
+        let vault = manager();
+        let dir = AppPaths::backup_dir().unwrap();
+        secure_create_dir(&dir).unwrap();
+        fs::write(dir.join("a.enc"), vault.encrypt(b"account-a").unwrap()).unwrap();
+        fs::write(dir.join("b.json"), b"account-b").unwrap();
+
+        assert_eq!(vault.enable_password_mode(PASSWORD).unwrap(), 2);
+        assert!(vault.is_password_mode().unwrap());
+        assert!(!dir.join("b.json").exists());
+        let a = fs::read(dir.join("a.enc")).unwrap();
+        assert!(is_vault_encrypted(&a));
+        assert_eq!(vault.decrypt(&a).unwrap(), b"account-a");
+
+        vault.lock();
+        assert_eq!(vault.ensure_unlocked().unwrap_err(), VAULT_LOCKED_ERROR);
+        assert_eq!(vault.decrypt(&a).unwrap_err(), VAULT_LOCKED_ERROR);
+        assert!(vault.unlock("Wrong-Horse-42!").is_err());
+        vault.unlock(PASSWORD).unwrap();
+        assert_eq!(vault.decrypt(&fs::read(dir.join("b.enc")).unwrap()).unwrap(), b"account-b");
+
+        assert!(vault.disable_password_mode("Wrong-Horse-42!").is_err());
+        assert_eq!(vault.disable_password_mode(PASSWORD).unwrap(), 2);
+        assert!(!vault.is_password_mode().unwrap());
+        let a = fs::read(dir.join("a.enc")).unwrap();
+        assert!(!is_vault_encrypted(&a));
+        assert_eq!(vault.decrypt(&a).unwrap(), b"account-a");
+        assert!(fs::read_dir(&dir).unwrap().flatten().all(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            !name.ends_with(".tmp") && !name.ends_with(".orig")
+        }));
+    }
+
+    #[test]
+    fn test_unreadable_config_fails_closed() {
+        use_test_config_dir("vault-corrupt");
+        let vault = manager();
+        let dir = AppPaths::backup_dir().unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(VAULT_CONFIG_FILE), b"{ not json").unwrap();
+
+        assert!(vault.is_password_mode().is_err());
+        assert!(vault.ensure_unlocked().is_err());
+        assert!(vault.encrypt(b"secret").is_err());
+    }
+
+    #[test]
+    fn test_failed_swap_restores_originals() {
+        let dir = use_test_config_dir("vault-swap");
+        fs::write(dir.join("a.enc"), b"old-a").unwrap();
+        fs::write(dir.join("a.enc.tmp"), b"new-a").unwrap();
+        fs::write(dir.join("b.json"), b"plain-b").unwrap();
+        fs::write(dir.join("b.enc.tmp"), b"new-b").unwrap();
+        let config_tmp = dir.join("vault.meta.tmp");
+        fs::write(&config_tmp, b"{}").unwrap();
+        // 配置路径是非空目录，最后一步替换必然失败
+        let config_path = dir.join(VAULT_CONFIG_FILE);
+        fs::create_dir_all(config_path.join("blocker")).unwrap();
+
+        let staged = vec![
+            StagedFile { tmp: dir.join("a.enc.tmp"), target: dir.join("a.enc"), legacy: None },
+            StagedFile { tmp: dir.join("b.enc.tmp"), target: dir.join("b.enc"), legacy: Some(dir.join("b.json")) },
+        ];
+        assert!(swap_staged(&staged, &config_tmp, &config_path).is_err());
+
+        assert_eq!(fs::read(dir.join("a.enc")).unwrap(), b"old-a");
+        assert!(!dir.join("b.enc").exists());
+        assert_eq!(fs::read(dir.join("b.json")).unwrap(), b"plain-b");
+        assert!(!dir.join("a.enc.tmp").exists());
+        assert!(!dir.join("a.enc.orig").exists());
+        assert!(!config_tmp.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use tauri::AppHandle;
 use tauri::Manager;
 
+use crate::antigravity::key_groups::KeyGroup;
+use crate::antigravity::secret_storage::SecretRestorePolicy;
 use crate::config_manager::ConfigManager;
 
 /// 应用程序设置
@@ -14,6 +17,15 @@ pub struct AppSettings {
     pub system_tray_enabled: bool,
     /// 是否启用静默启动（启动时最小化到托盘或后台）
     pub silent_start_enabled: bool,
+    /// 自定义 CloudCode 接口地址（为空时使用默认地址）
+    #[serde(default)]
+    pub cloudcode_base_url: Option<String>,
+    /// 各账户（按账户 ID）切换/恢复时默认恢复的字段分组，未设置的账户恢复全部分组
+    #[serde(default, deserialize_with = "crate::antigravity::key_groups::deserialize_saved")]
+    pub restore_groups: HashMap<String, Vec<KeyGroup>>,
+    /// 恢复备份时如何处理扩展密钥（secret://）
+    #[serde(default)]
+    pub secret_restore_policy: SecretRestorePolicy,
 }
 
 impl Default for AppSettings {
@@ -21,6 +33,9 @@ impl Default for AppSettings {
         Self {
             system_tray_enabled: false,  // 默认不启用，避免打扰用户
             silent_start_enabled: false, // 默认不启用静默启动，让用户看到应用界面
+            cloudcode_base_url: None,
+            restore_groups: HashMap::new(),
+            secret_restore_policy: SecretRestorePolicy::default(),
         }
     }
 }
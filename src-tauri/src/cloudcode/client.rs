@@ -0,0 +1,597 @@
+//! CloudCode API 客户端
+//! 支持可配置的接口地址、超时、带退避的重试和类型化响应
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest;
+
+use crate::utils::secret::SecretString;
+
+/// 默认 API 地址（沙盒环境）
+pub const DEFAULT_BASE_URL: &str = "https://daily-cloudcode-pa.sandbox.googleapis.com";
+
+/// Code Assist 项目 ID 缓存有效期
+const PROJECT_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 项目 ID 缓存（键为接口地址与令牌的哈希，不保存令牌本身）
+static PROJECT_CACHE: LazyLock<Mutex<HashMap<String, (Instant, String)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 允许使用 HTTP 的本机调试地址
+const LOCAL_HOSTS: &[&str] = &["localhost", "127.0.0.1", "[::1]"];
+
+/// 校验自定义接口地址
+///
+/// 令牌会发往该地址，只允许 HTTPS；本机调试地址（主机名精确匹配）可使用 HTTP。
+pub fn validate_base_url(base_url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(base_url).map_err(|e| format!("接口地址格式错误: {}", e))?;
+    let is_local = url.host_str().is_some_and(|host| LOCAL_HOSTS.contains(&host));
+    match url.scheme() {
+        "https" => Ok(()),
+        "http" if is_local => Ok(()),
+        _ => Err("接口地址必须使用 HTTPS".to_string()),
+    }
+}
+
+/// 客户端配置
+#[derive(Debug, Clone)]
+pub struct CloudCodeConfig {
+    pub base_url: String,
+    /// 单次请求总超时
+    pub timeout: Duration,
+    /// 建立连接超时
+    pub connect_timeout: Duration,
+    /// 失败后的最大重试次数（不含首次请求）
+    pub max_retries: u32,
+    /// 首次重试前的等待时间，之后按指数增长
+    pub initial_backoff: Duration,
+    /// 单次重试等待时间上限
+    pub max_backoff: Duration,
+}
+
+impl Default for CloudCodeConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: Duration::from_secs(20),
+            connect_timeout: Duration::from_secs(10),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+/// CloudCode 请求错误
+#[derive(Debug)]
+pub enum CloudCodeError {
+    /// 网络错误（连接失败等）
+    Network(String),
+    /// 请求超时
+    Timeout,
+    /// 非成功的 HTTP 状态码
+    Http { status: u16, message: String },
+    /// 接口返回的业务错误
+    Api { code: Option<i64>, message: String },
+    /// 响应解析失败
+    Decode(String),
+}
+
+impl CloudCodeError {
+    /// 是否值得重试（网络问题、超时、限流与服务端错误）
+    fn is_retryable(&self) -> bool {
+        match self {
+            CloudCodeError::Network(_) | CloudCodeError::Timeout => true,
+            CloudCodeError::Http { status, .. } => *status == 429 || *status >= 500,
+            CloudCodeError::Api { .. } | CloudCodeError::Decode(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for CloudCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudCodeError::Network(msg) => write!(f, "请求 CloudCode 失败: {}", msg),
+            CloudCodeError::Timeout => write!(f, "请求 CloudCode 超时"),
+            CloudCodeError::Http { status, message } => {
+                write!(f, "CloudCode 返回 HTTP {}: {}", status, message)
+            }
+            CloudCodeError::Api { code, message } => match code {
+                Some(code) => write!(f, "CloudCode 返回错误 ({}): {}", code, message),
+                None => write!(f, "CloudCode 返回错误: {}", message),
+            },
+            CloudCodeError::Decode(msg) => write!(f, "解析 CloudCode 响应失败: {}", msg),
+        }
+    }
+}
+
+impl From<CloudCodeError> for String {
+    fn from(e: CloudCodeError) -> Self {
+        e.to_string()
+    }
+}
+
+/// loadCodeAssist 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadCodeAssistResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloudaicompanion_project: Option<String>,
+    /// 其他未建模字段原样保留
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// 模型配额信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaInfo {
+    #[serde(default)]
+    pub remaining_fraction: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_time: Option<String>,
+}
+
+/// 单个模型信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_info: Option<QuotaInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// fetchAvailableModels 响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchAvailableModelsResponse {
+    #[serde(default)]
+    pub models: BTreeMap<String, ModelInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_agent_model_id: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// 接口错误响应体
+#[derive(Debug, Deserialize)]
+struct ApiErrorBody {
+    error: ApiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorDetail {
+    #[serde(default)]
+    code: Option<i64>,
+    #[serde(default)]
+    message: String,
+}
+
+/// 与当前平台一致的 User-Agent（格式同 Antigravity 客户端：antigravity/<os>/<arch>）
+pub fn platform_user_agent() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        other => other,
+    };
+    format!("antigravity/{}/{}", os, arch)
+}
+
+/// CloudCode API 客户端
+pub struct CloudCodeClient {
+    http: reqwest::Client,
+    config: CloudCodeConfig,
+}
+
+impl Default for CloudCodeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CloudCodeClient {
+    pub fn new() -> Self {
+        Self::with_config(CloudCodeConfig::default())
+    }
+
+    pub fn with_config(config: CloudCodeConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .user_agent(platform_user_agent())
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .build()
+            .unwrap_or_else(|e| {
+                tracing::warn!(target: "cloudcode::client", error = %e, "创建 HTTP 客户端失败，使用默认配置");
+                reqwest::Client::new()
+            });
+        Self { http, config }
+    }
+
+    /// 加载 Code Assist 信息（包含 cloudaicompanionProject）
+    pub async fn load_code_assist(
+        &self,
+        token: &SecretString,
+    ) -> Result<LoadCodeAssistResponse, CloudCodeError> {
+        self.post(
+            "/v1internal:loadCodeAssist",
+            token,
+            &json!({ "metadata": { "ideType": "ANTIGRAVITY" } }),
+        )
+        .await
+    }
+
+    /// 获取令牌对应的 Code Assist 项目 ID（带缓存）
+    pub async fn project_for(&self, token: &SecretString) -> Result<String, CloudCodeError> {
+        let cache_key = {
+            let mut hasher = Sha256::new();
+            hasher.update(self.config.base_url.as_bytes());
+            hasher.update([0u8]);
+            hasher.update(token.expose_secret().as_bytes());
+            hex::encode(hasher.finalize())
+        };
+
+        if let Some((fetched_at, project)) = PROJECT_CACHE.lock().unwrap().get(&cache_key) {
+            if fetched_at.elapsed() < PROJECT_CACHE_TTL {
+                return Ok(project.clone());
+            }
+        }
+
+        let project = self
+            .load_code_assist(token)
+            .await?
+            .cloudaicompanion_project
+            .unwrap_or_default();
+        // 未分配项目时不缓存，下次重新获取
+        if !project.is_empty() {
+            PROJECT_CACHE
+                .lock()
+                .unwrap()
+                .insert(cache_key, (Instant::now(), project.clone()));
+        }
+        Ok(project)
+    }
+
+    /// 获取可用模型列表（包含各模型的配额信息）
+    pub async fn fetch_available_models(
+        &self,
+        token: &SecretString,
+        project: &str,
+    ) -> Result<FetchAvailableModelsResponse, CloudCodeError> {
+        self.post(
+            "/v1internal:fetchAvailableModels",
+            token,
+            &json!({ "project": project }),
+        )
+        .await
+    }
+
+    /// 发送 POST 请求，对可重试的错误按指数退避重试
+    async fn post<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        token: &SecretString,
+        body: &Value,
+    ) -> Result<T, CloudCodeError> {
+        let mut attempt = 0;
+        loop {
+            let (result, retry_after) = self.post_once(endpoint, token, body).await;
+            match result {
+                Err(e) if e.is_retryable() && attempt < self.config.max_retries => {
+                    let delay = retry_after
+                        .unwrap_or_else(|| self.backoff(attempt))
+                        .min(self.config.max_backoff);
+                    tracing::warn!(
+                        target: "cloudcode::client",
+                        endpoint = %endpoint,
+                        attempt = attempt + 1,
+                        delay_ms = delay.as_millis(),
+                        error = %e,
+                        "请求失败，准备重试"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// 第 n 次重试的等待时间（指数退避 + 随机抖动）
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.config.max_backoff);
+        let jitter_ms = rand::random::<u64>() % (base.as_millis() as u64 / 4 + 1);
+        base + Duration::from_millis(jitter_ms)
+    }
+
+    /// 单次请求；同时返回服务端建议的重试等待时间（Retry-After）
+    async fn post_once<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        token: &SecretString,
+        body: &Value,
+    ) -> (Result<T, CloudCodeError>, Option<Duration>) {
+        let response = match self
+            .http
+            .post(format!("{}{}", self.config.base_url.trim_end_matches('/'), endpoint))
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .bearer_auth(token.expose_secret())
+            .body(body.to_string())
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => return (Err(CloudCodeError::Timeout), None),
+            Err(e) => return (Err(CloudCodeError::Network(e.without_url().to_string())), None),
+        };
+
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(e) if e.is_timeout() => return (Err(CloudCodeError::Timeout), retry_after),
+            Err(e) => return (Err(CloudCodeError::Network(e.without_url().to_string())), retry_after),
+        };
+
+        // 错误响应体（无论 HTTP 状态码）优先解析为接口错误信息
+        let api_error = serde_json::from_slice::<ApiErrorBody>(&bytes).ok();
+        if !status.is_success() {
+            let message = api_error
+                .map(|e| e.error.message)
+                .unwrap_or_else(|| String::from_utf8_lossy(&bytes).chars().take(200).collect());
+            return (
+                Err(CloudCodeError::Http {
+                    status: status.as_u16(),
+                    message,
+                }),
+                retry_after,
+            );
+        }
+        if let Some(e) = api_error {
+            return (
+                Err(CloudCodeError::Api {
+                    code: e.error.code,
+                    message: e.error.message,
+                }),
+                None,
+            );
+        }
+
+        (
+            serde_json::from_slice(&bytes).map_err(|e| CloudCodeError::Decode(e.to_string())),
+            None,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 本地模拟服务器：按顺序返回预设响应，并记录收到的原始请求
+    async fn spawn_mock_server(responses: Vec<(u16, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+
+                // 读取请求头与请求体
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    if let Some(header_end) = text.find("\r\n\r\n") {
+                        let content_length = text[..header_end]
+                            .lines()
+                            .find_map(|l| {
+                                let (name, value) = l.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if buf.len() >= header_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                }
+                recorded.lock().unwrap().push(String::from_utf8_lossy(&buf).to_string());
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        (format!("http://{}", addr), requests)
+    }
+
+    fn test_client(base_url: String) -> CloudCodeClient {
+        CloudCodeClient::with_config(CloudCodeConfig {
+            base_url,
+            timeout: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(5),
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_available_models_typed() {
+        let body = r#"{"models":{"gemini-3-pro-high":{"displayName":"Gemini 3 Pro (High)","quotaInfo":{"remainingFraction":0.75,"resetTime":"2025-01-01T00:00:00Z"},"model":"MODEL_X"}},"defaultAgentModelId":"gemini-3-pro-high","tabModelIds":["a"]}"#;
+        let (base_url, requests) = spawn_mock_server(vec![(200, body)]).await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("test-token");
+        let response = client.fetch_available_models(&token, "proj-1").await.unwrap();
+
+        let model = &response.models["gemini-3-pro-high"];
+        assert_eq!(model.display_name.as_deref(), Some("Gemini 3 Pro (High)"));
+        let quota = model.quota_info.as_ref().unwrap();
+        assert_eq!(quota.remaining_fraction, 0.75);
+        assert_eq!(quota.reset_time.as_deref(), Some("2025-01-01T00:00:00Z"));
+        assert_eq!(response.default_agent_model_id.as_deref(), Some("gemini-3-pro-high"));
+        // 未建模字段原样保留
+        assert!(response.extra.contains_key("tabModelIds"));
+        assert!(model.extra.contains_key("model"));
+
+        let request = requests.lock().unwrap()[0].to_lowercase();
+        assert!(request.starts_with("post /v1internal:fetchavailablemodels"));
+        assert!(request.contains("authorization: bearer test-token"));
+        assert!(request.contains(&format!("user-agent: {}", platform_user_agent())));
+        assert!(request.contains("\"project\":\"proj-1\""));
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_server_error() {
+        let (base_url, requests) = spawn_mock_server(vec![
+            (503, r#"{"error":{"code":503,"message":"unavailable"}}"#),
+            (429, "rate limited"),
+            (200, r#"{"cloudaicompanionProject":"proj-2"}"#),
+        ])
+        .await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("test-token");
+        let response = client.load_code_assist(&token).await.unwrap();
+        assert_eq!(response.cloudaicompanion_project.as_deref(), Some("proj-2"));
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_no_retry_on_client_error() {
+        let (base_url, requests) = spawn_mock_server(vec![
+            (401, r#"{"error":{"code":401,"message":"invalid credentials"}}"#),
+            (200, "{}"),
+        ])
+        .await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("bad-token");
+        let err = client.load_code_assist(&token).await.unwrap_err();
+        match err {
+            CloudCodeError::Http { status, message } => {
+                assert_eq!(status, 401);
+                assert_eq!(message, "invalid credentials");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retries_exhausted() {
+        let (base_url, requests) =
+            spawn_mock_server(vec![(500, "oops"), (500, "oops"), (500, "oops")]).await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("test-token");
+        let err = client.load_code_assist(&token).await.unwrap_err();
+        assert!(matches!(err, CloudCodeError::Http { status: 500, .. }));
+        // 首次请求 + 2 次重试
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_api_error_in_success_response() {
+        let (base_url, _) = spawn_mock_server(vec![(
+            200,
+            r#"{"error":{"code":7,"message":"permission denied","status":"PERMISSION_DENIED"}}"#,
+        )])
+        .await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("test-token");
+        let err = client.load_code_assist(&token).await.unwrap_err();
+        assert!(matches!(err, CloudCodeError::Api { code: Some(7), .. }));
+    }
+
+    #[tokio::test]
+    async fn test_empty_project_not_cached() {
+        let (base_url, requests) = spawn_mock_server(vec![
+            (200, "{}"),
+            (200, r#"{"cloudaicompanionProject":"proj-3"}"#),
+        ])
+        .await;
+        let client = test_client(base_url);
+
+        let token = SecretString::from("project-token");
+        assert_eq!(client.project_for(&token).await.unwrap(), "");
+        assert_eq!(client.project_for(&token).await.unwrap(), "proj-3");
+        assert_eq!(client.project_for(&token).await.unwrap(), "proj-3");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_base_url() {
+        for url in [
+            "https://cloudcode.example.com",
+            "http://localhost:8080",
+            "http://127.0.0.1:3000/api",
+            "http://[::1]:9000",
+        ] {
+            assert!(validate_base_url(url).is_ok(), "{}", url);
+        }
+        for url in [
+            "http://localhost.evil.com",
+            "http://127.0.0.1.attacker.net",
+            "http://localhost@evil.com",
+            "http://example.com",
+            "ftp://localhost",
+            "localhost:8080",
+        ] {
+            assert!(validate_base_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_platform_user_agent() {
+        let ua = platform_user_agent();
+        assert!(ua.starts_with("antigravity/"));
+        assert_eq!(ua.split('/').count(), 3);
+        assert!(!ua.contains("x86_64") && !ua.contains("macos"));
+    }
+}
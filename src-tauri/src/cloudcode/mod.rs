@@ -0,0 +1,6 @@
+//! CloudCode API 模块
+//! 在 Rust 侧携带访问令牌调用 CloudCode 接口，令牌不经过前端
+
+pub mod client;
+
+pub use client::CloudCodeClient;
@@ -3,20 +3,31 @@
 
 use rusqlite::{Connection, Result as SqlResult};
 use serde_json::{Value, from_str};
-use tauri::State;
+use tauri::{Manager, State};
 use tracing::instrument;
-use std::fs;
-use std::path::PathBuf;
-use chrono::{DateTime, Local};
 
-// 导入加密备份读取函数
-use crate::antigravity::backup::read_backup_file;
+// 导入账户摘要索引
+use crate::antigravity::account_index::{self, AccountSummary};
+use crate::antigravity::cleanup::{self, LogoutPlan, LogoutProfile, LogoutReport};
+use crate::antigravity::credentials;
+use crate::antigravity::secret_storage::{self, SecretReport, SecretRestorePolicy};
+use crate::antigravity::key_groups::{self, KeyGroup};
+use crate::antigravity::launch_verify::{self, LaunchStatus};
+use crate::operations::{self, OperationContext, OperationKind};
+
+/// 切换流程的步骤数（保存快照、关闭进程、恢复数据、启动进程、验证启动）
+const SWITCH_STEPS: usize = 5;
 
 /// 切换 Antigravity 账户
+///
+/// 目标账户令牌已过期时与 `switch_to_antigravity_account` 一样返回以 `令牌已过期` 开头的错误，
+/// 确认后以 `allow_expired = true` 重新调用。
 #[tauri::command]
 #[instrument(fields(account_id = %account_id))]
 pub async fn switch_antigravity_account(
+    app: tauri::AppHandle,
     account_id: String,
+    allow_expired: Option<bool>,
     _state: State<'_, crate::AppState>,
 ) -> Result<String, String> {
   tracing::info!(target: "account::switch_legacy", account_id = %account_id, "开始切换 Antigravity 账户");
@@ -64,7 +75,7 @@ pub async fn switch_antigravity_account(
 
         tracing::info!(target: "account::switch_legacy", original_id = %account_id, resolved_name = %account_name, "解析账户名称");
 
-        let switch_result = switch_to_antigravity_account(account_name).await?;
+        let switch_result = switch_to_antigravity_account(app, account_name, allow_expired, None, None).await?;
 
         Ok(format!(
             "已切换到账户: {} (详情: {})",
@@ -105,57 +116,21 @@ pub async fn get_antigravity_accounts(
     let start_time = std::time::Instant::now();
 
     let result = async {
-        let mut accounts = Vec::new();
+        // 主密码模式下未解锁时直接返回锁定错误，由前端提示解锁
+        crate::antigravity::vault::vault().ensure_unlocked()?;
 
         // 获取备份目录路径
         let antigravity_dir = state.config_dir.join("antigravity-accounts");
 
         if !antigravity_dir.exists() {
             tracing::info!("📂 备份目录不存在，返回空列表");
-            return Ok(accounts);
+            return Ok(Vec::new());
         }
 
-        // 读取目录中的所有备份文件（支持 .enc 加密文件和 .json 明文文件）
-        let entries = fs::read_dir(&antigravity_dir)
-            .map_err(|e| format!("读取备份目录失败: {}", e))?;
-
-        for entry in entries {
-            let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-            let path = entry.path();
-
-            // 处理 .enc（加密）和 .json（明文）文件
-            let ext = path.extension().and_then(|e| e.to_str());
-            if !matches!(ext, Some("enc") | Some("json")) {
-                continue;
-            }
-
-            let file_name = match path.file_stem() {
-                Some(name) => name.to_string_lossy().to_string(),
-                None => continue,
-            };
-
-            tracing::debug!("📄 正在解析备份文件: {} ({})", file_name, ext.unwrap_or("unknown"));
-
-            // 使用统一的备份读取函数（自动处理加密/明文）
-            let backup_data: Value = match read_backup_file(&path) {
-                Ok(data) => data,
-                Err(e) => {
-                    tracing::warn!("⚠️ 读取备份文件失败 {}: {}", file_name, e);
-                    continue;
-                }
-            };
-
-            // 提取账户信息
-            match parse_backup_to_account(&backup_data, &file_name, &path) {
-                Ok(account) => {
-                    accounts.push(account);
-                    tracing::info!("✅ 成功解析账户: {}", file_name);
-                }
-                Err(e) => {
-                    tracing::warn!("⚠️ 解析账户失败 {}: {}", file_name, e);
-                }
-            }
-        }
+        // 从加密摘要索引读取（索引缺失或过期时自动重建）
+        let index = account_index::load_or_rebuild()?;
+        let mut accounts: Vec<crate::AntigravityAccount> =
+            index.accounts().map(summary_to_account).collect();
 
         // 按最后修改时间排序（最新的在前）
         accounts.sort_by(|a, b| b.last_switched.cmp(&a.last_switched));
@@ -190,111 +165,67 @@ pub async fn get_antigravity_accounts(
     }
 }
 
-/// 将备份数据解析为账户对象
-fn parse_backup_to_account(
-    backup_data: &Value,
-    file_name: &str,
-    file_path: &PathBuf,
-) -> Result<crate::AntigravityAccount, String> {
-    // 提取邮箱
-    let email = backup_data
-        .get("account_email")
-        .and_then(|v| v.as_str())
-        .unwrap_or(file_name)
-        .to_string();
-
-    // 提取备份时间（如果存在）
-    let backup_time_str = backup_data
-        .get("backup_time")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-
-    // 从文件修改时间获取 last_switched
-    let metadata = fs::metadata(file_path)
-        .map_err(|e| format!("获取文件元数据失败: {}", e))?;
-    let modified_time = metadata.modified()
-        .map_err(|e| format!("获取修改时间失败: {}", e))?;
-    let datetime: DateTime<Local> = DateTime::from(modified_time);
-    let last_switched = datetime.format("%Y-%m-%d %H:%M:%S").to_string();
-
-    // 从备份数据中提取认证状态信息
-    let auth_status = backup_data
-        .get("antigravityAuthStatus")
-        .and_then(|v| v.as_str());
-
-    // 解析认证状态 JSON（如果存在）
-    let (name, api_key, user_status_proto) = if let Some(auth_json) = auth_status {
-        match from_str::<Value>(auth_json) {
-            Ok(auth_data) => {
-                let name = auth_data
-                    .get("name")
-                    .or_else(|| auth_data.get("email"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(&email.split('@').next().unwrap_or(&email))
-                    .to_string();
-
-                let api_key = auth_data
-                    .get("apiKey")
-                    .or_else(|| auth_data.get("accessToken"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                // 提取用户状态 protobuf 数据（包含配额信息）
-                let user_status_proto = auth_data
-                    .get("userStatusProtoBinaryBase64")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-
-                (name, api_key, user_status_proto)
-            }
-            Err(_) => {
-                // 解析失败，使用默认值
-                let name = email.split('@').next().unwrap_or(&email).to_string();
-                (name, "".to_string(), None)
-            }
-        }
+/// 将索引中的账户摘要转换为账户对象
+fn summary_to_account(summary: &AccountSummary) -> crate::AntigravityAccount {
+    // 创建时间（使用备份时间或最后切换时间）
+    let created_at = if !summary.backup_time.is_empty() {
+        summary.backup_time.clone()
     } else {
-        // 没有认证信息，使用默认值
-        let name = email.split('@').next().unwrap_or(&email).to_string();
-        (name, "".to_string(), None)
+        summary.last_switched.clone()
     };
 
-    // 提取用户设置
-    let user_settings = backup_data
-        .get("antigravityUserSettings.allUserSettings")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-
-    // 提取头像 URL
-    let profile_url = backup_data
-        .get("antigravity.profileUrl")
-        .and_then(|v| v.as_str())
-        .unwrap_or("")
-        .to_string();
-
-    // 生成 ID（使用邮箱的哈希或直接使用邮箱）
-    let id = format!("account_{}", email);
-
-    // 创建时间（使用备份时间或文件时间）
-    let created_at = if !backup_time_str.is_empty() {
-        backup_time_str.to_string()
-    } else {
-        last_switched.clone()
+    crate::AntigravityAccount {
+        // 生成 ID（使用邮箱的哈希或直接使用邮箱）
+        id: format!("account_{}", summary.email),
+        name: summary.name.clone(),
+        email: summary.email.clone(),
+        handle: summary.id.clone(),
+        profile_url: summary.profile_url.clone(),
+        created_at,
+        last_switched: summary.last_switched.clone(),
+        user_status_proto: summary.user_status_proto.clone(),
+        token_expires_at: summary.token_expires_at.map(format_expiry),
+        token_status: credentials::token_status(
+            summary.token_expires_at,
+            chrono::Utc::now().timestamp(),
+        ),
+        secret_count: summary.secret_keys.len(),
+        secrets_at_risk: !summary.secret_keys.is_empty()
+            && summary.backup_machine.is_some()
+            && summary.backup_machine.as_deref() != secret_storage::current_machine(),
+    }
+}
+
+/// 将过期时间（Unix 秒）格式化为本地 RFC3339
+fn format_expiry(unix_secs: i64) -> String {
+    chrono::DateTime::from_timestamp(unix_secs, 0)
+        .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// 切换前检查目标账户的令牌是否已过期
+fn ensure_token_not_expired(account_name: &str) -> Result<(), String> {
+    let index = account_index::load_or_rebuild()?;
+    let Some(summary) = index.find_by_email(account_name) else {
+        return Ok(());
     };
+    let now = chrono::Utc::now().timestamp();
+    if credentials::token_status(summary.token_expires_at, now) != credentials::TokenStatus::Expired {
+        return Ok(());
+    }
 
-    Ok(crate::AntigravityAccount {
-        id,
-        name,
-        email,
-        api_key,
-        profile_url,
-        user_settings,
-        created_at,
-        last_switched,
-        user_status_proto,
-    })
+    let expired_at = summary
+        .token_expires_at
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    tracing::warn!(target: "account::switch", "目标账户的访问令牌已过期，拒绝切换");
+    Err(format!(
+        "{}: 账户 {} 备份的登录状态已于 {} 过期，切换后需要重新登录。确认后可强制切换",
+        credentials::TOKEN_EXPIRED_ERROR_PREFIX,
+        summary.email,
+        expired_at
+    ))
 }
 
 /// 获取当前 Antigravity 信息
@@ -344,7 +275,8 @@ pub async fn get_current_antigravity_info() -> Result<Value, String> {
                         // 添加数据库路径信息
                         auth_data["db_path"] =
                             Value::String(app_data.to_string_lossy().to_string());
-                        Ok(auth_data)
+                        // 令牌不返回给前端
+                        Ok(crate::antigravity::credentials::redact_auth_status(auth_data))
                     }
                     Err(e) => Err(format!("解析认证信息失败: {}", e)),
                 }
@@ -376,13 +308,13 @@ pub async fn get_current_antigravity_info() -> Result<Value, String> {
 
 /// 备份当前 Antigravity 账户
 #[tauri::command]
-#[instrument]
-pub async fn backup_antigravity_current_account() -> Result<String, String> {
+#[instrument(skip(app))]
+pub async fn backup_antigravity_current_account(app: tauri::AppHandle) -> Result<String, String> {
   tracing::info!("📥 开始备份当前账户");
 
   let start_time = std::time::Instant::now();
 
-  let result = async {
+  let result = operations::run(&app, OperationKind::Backup, None, async {
 
         // 尝试获取 Antigravity 状态数据库路径
         let app_data = match crate::platform::get_antigravity_db_path() {
@@ -452,7 +384,7 @@ pub async fn backup_antigravity_current_account() -> Result<String, String> {
                 Err("未检测到已登录用户".to_string())
             }
         }
-  }.await;
+  }).await;
 
   let duration = start_time.elapsed();
 
@@ -463,6 +395,10 @@ pub async fn backup_antigravity_current_account() -> Result<String, String> {
                 result_message = %message,
                 "账户备份操作完成"
             );
+      // 备份已与实时会话一致，刷新活动账户状态
+      if let Err(e) = crate::antigravity::active_account::refresh(&app) {
+          tracing::debug!(target: "account::active", error = %e, "活动账户检测跳过");
+      }
       Ok(message)
     }
     Err(e) => {
@@ -478,96 +414,508 @@ pub async fn backup_antigravity_current_account() -> Result<String, String> {
 
 /// 清除所有 Antigravity 数据
 #[tauri::command]
-pub async fn clear_all_antigravity_data() -> Result<String, String> {
-    crate::antigravity::cleanup::clear_all_antigravity_data().await
+pub async fn clear_all_antigravity_data(app: tauri::AppHandle) -> Result<String, String> {
+    operations::run(&app, OperationKind::Logout, None, cleanup::clear_all_antigravity_data()).await
+}
+
+/// 预览指定深度的注销将删除的字段、文件与缓存（默认标准注销）
+#[tauri::command]
+pub async fn preview_logout(profile: Option<LogoutProfile>) -> Result<LogoutPlan, String> {
+    cleanup::preview_logout(profile.unwrap_or_default())
+}
+
+/// 按指定深度注销 Antigravity 账户，返回实际删除的内容（默认标准注销）
+#[tauri::command]
+pub async fn logout_antigravity(
+    app: tauri::AppHandle,
+    profile: Option<LogoutProfile>,
+) -> Result<LogoutReport, String> {
+    crate::log_async_command!("logout_antigravity", async {
+        let profile = profile.unwrap_or_default();
+        operations::run(&app, OperationKind::Logout, None, cleanup::clear_antigravity_data(profile)).await
+    })
+}
+
+/// 确定本次恢复的字段分组：显式指定 > 账户默认设置 > 全部分组
+fn resolve_restore_groups(
+    app: &tauri::AppHandle,
+    account_name: &str,
+    key_groups: Option<Vec<KeyGroup>>,
+) -> Result<Vec<KeyGroup>, String> {
+    key_groups::resolve(key_groups, || {
+        let account_id = account_index::load_or_rebuild()?
+            .find_by_email(account_name)
+            .map(|summary| summary.id.clone());
+        Ok(account_id.and_then(|id| {
+            app.state::<crate::app_settings::AppSettingsManager>()
+                .get_settings()
+                .restore_groups
+                .get(&id)
+                .cloned()
+        }))
+    })
+}
+
+/// 确定扩展密钥的恢复策略：显式指定 > 应用设置
+fn resolve_secret_policy(app: &tauri::AppHandle, secret_policy: Option<SecretRestorePolicy>) -> SecretRestorePolicy {
+    secret_policy.unwrap_or_else(|| {
+        app.state::<crate::app_settings::AppSettingsManager>()
+            .get_settings()
+            .secret_restore_policy
+    })
+}
+
+/// 检查账户备份中的扩展密钥（secret://）能否在本机解密
+#[tauri::command]
+pub async fn inspect_account_secrets(account_name: String) -> Result<SecretReport, String> {
+    let backup_file = account_index::resolve_backup_path(&account_name)?
+        .filter(|path| path.exists())
+        .ok_or_else(|| format!("备份文件不存在: {}", account_name))?;
+    let backup_data = crate::antigravity::backup::read_backup_file(&backup_file)?;
+    Ok(secret_storage::inspect(&backup_data))
 }
 
 /// 恢复 Antigravity 账户
+///
+/// `key_groups` 为空时使用账户的默认恢复分组（未设置时恢复全部字段），
+/// `secret_policy` 为空时使用设置中的扩展密钥恢复策略。
 #[tauri::command]
-pub async fn restore_antigravity_account(account_name: String) -> Result<String, String> {
+pub async fn restore_antigravity_account(
+    app: tauri::AppHandle,
+    account_name: String,
+    key_groups: Option<Vec<KeyGroup>>,
+    secret_policy: Option<SecretRestorePolicy>,
+) -> Result<String, String> {
     tracing::debug!(target: "account::restore", account_name = %account_name, "调用 restore_antigravity_account");
+    operations::run(
+        &app,
+        OperationKind::Restore,
+        Some(&account_name),
+        restore_account(&app, &account_name, key_groups, secret_policy),
+    )
+    .await
+}
 
-    // 1. 构建备份文件路径（优先使用加密文件）
-    let config_dir = dirs::config_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."))
-        .join(".antigravity-agent")
-        .join("antigravity-accounts");
-    
-    // 优先查找加密文件 (.enc)，如果不存在则查找明文文件 (.json)
-    let encrypted_file = config_dir.join(format!("{}.enc", account_name));
-    let legacy_file = config_dir.join(format!("{}.json", account_name));
-    
-    let backup_file = if encrypted_file.exists() {
-        tracing::debug!(target: "account::restore", "使用加密备份文件");
-        encrypted_file
-    } else if legacy_file.exists() {
-        tracing::warn!(target: "account::restore", "使用明文备份文件（建议重新备份以加密）");
-        legacy_file
-    } else {
-        return Err(format!("备份文件不存在: {}", account_name));
+/// 恢复账户数据（调用方需已持有操作执行权）
+async fn restore_account(
+    app: &tauri::AppHandle,
+    account_name: &str,
+    key_groups: Option<Vec<KeyGroup>>,
+    secret_policy: Option<SecretRestorePolicy>,
+) -> Result<String, String> {
+
+    // 1. 通过加密索引查找账户 ID 对应的备份文件（旧版文件会在此自动迁移）
+    let backup_file = account_index::resolve_backup_path(account_name)?
+        .filter(|path| path.exists())
+        .ok_or_else(|| format!("备份文件不存在: {}", account_name))?;
+
+    // 2. 按所选分组调用统一的恢复函数
+    let groups = resolve_restore_groups(app, account_name, key_groups)?;
+    tracing::debug!(target: "account::restore", groups = ?groups, "恢复分组");
+    let secrets = resolve_secret_policy(app, secret_policy);
+    let result =
+        crate::antigravity::restore::restore_antigravity_data_with_groups(backup_file, &groups, secrets).await?;
+
+    // 3. 记录切换时间到账户索引
+    if let Err(e) = account_index::update(|index| {
+        if let Some(summary) = index.get_mut_by_email(account_name) {
+            summary.last_switched = account_index::now_display_time();
+        }
+    }) {
+        tracing::warn!(target: "account::restore", error = %e, "更新账户索引失败");
+    }
+
+    Ok(result)
+}
+
+/// 关闭 Antigravity 进程（未运行时视为成功）
+async fn stop_antigravity_for_switch() -> Result<String, String> {
+    let kill_result = match crate::platform::kill_antigravity_processes() {
+        Ok(result) => {
+            if result.contains("not found") || result.contains("未找到") {
+                tracing::debug!(target: "account::switch::step1", "Antigravity 进程未运行，跳过关闭步骤");
+                "Antigravity 进程未运行".to_string()
+            } else {
+                tracing::debug!(target: "account::switch::step1", result = %result, "进程关闭完成");
+                result
+            }
+        }
+        Err(e) => {
+            if e.contains("not found") || e.contains("未找到") {
+                tracing::debug!(target: "account::switch::step1", "Antigravity 进程未运行，跳过关闭步骤");
+                "Antigravity 进程未运行".to_string()
+            } else {
+                tracing::error!(target: "account::switch::step1", error = %e, "关闭进程时发生错误");
+                return Err(format!("关闭进程时发生错误: {}", e));
+            }
+        }
     };
 
-    // 2. 调用统一的恢复函数
-    crate::antigravity::restore::restore_all_antigravity_data(backup_file).await
+    // 等待一秒确保进程完全关闭
+    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    Ok(kill_result)
 }
 
-/// 切换到 Antigravity 账户（调用 restore_antigravity_account）
-#[tauri::command]
-pub async fn switch_to_antigravity_account(account_name: String) -> Result<String, String> {
-    crate::log_async_command!("switch_to_antigravity_account", async {
-        // 1. 关闭 Antigravity 进程 (如果存在)
-        let kill_result = match crate::platform::kill_antigravity_processes() {
-            Ok(result) => {
-                if result.contains("not found") || result.contains("未找到") {
-                    tracing::debug!(target: "account::switch::step1", "Antigravity 进程未运行，跳过关闭步骤");
-                    "Antigravity 进程未运行".to_string()
-                } else {
-                    tracing::debug!(target: "account::switch::step1", result = %result, "进程关闭完成");
-                    result
+/// 重新启动 Antigravity 进程
+async fn launch_antigravity() -> Result<String, String> {
+    // 等待一秒确保数据库操作完成
+    tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+
+    crate::antigravity::starter::start_antigravity()
+        .inspect(|result| tracing::debug!(target: "account::switch::step3", result = %result, "Antigravity 启动成功"))
+        .inspect_err(|e| tracing::warn!(target: "account::switch::step3", error = %e, "Antigravity 启动失败"))
+}
+
+/// 重新启动 Antigravity 进程（启动失败不视为切换失败）
+async fn start_antigravity_after_switch() -> String {
+    launch_antigravity()
+        .await
+        .unwrap_or_else(|e| format!("启动失败: {}", e))
+}
+
+/// 切换前保存当前账户：写入撤销栈，未备份或会话已刷新的账户同时更新其备份
+fn snapshot_before_switch(account_name: &str) -> Result<String, String> {
+    use crate::antigravity::active_account::{self, SessionState};
+
+    match active_account::detect() {
+        Ok(status) if matches!(status.state, SessionState::Unsaved | SessionState::Newer) => {
+            if let Some(email) = status.email.as_deref() {
+                match crate::antigravity::backup::smart_backup_antigravity_account(email) {
+                    Ok(_) => tracing::info!(target: "account::switch::step0", "切换前已备份当前账户"),
+                    Err(e) => tracing::warn!(target: "account::switch::step0", error = %e, "切换前备份当前账户失败"),
                 }
             }
-            Err(e) => {
-                if e.contains("not found") || e.contains("未找到") {
-                    tracing::debug!(target: "account::switch::step1", "Antigravity 进程未运行，跳过关闭步骤");
-                    "Antigravity 进程未运行".to_string()
-                } else {
-                    tracing::error!(target: "account::switch::step1", error = %e, "关闭进程时发生错误");
-                    return Err(format!("关闭进程时发生错误: {}", e));
-                }
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(target: "account::switch::step0", error = %e, "检测当前账户失败"),
+    }
+
+    // 撤销快照是切换的安全网，保存失败时不继续切换
+    crate::antigravity::switch_undo::push_pre_switch(account_name)
+        .map(|_| "已保存切换前快照".to_string())
+        .map_err(|e| format!("保存切换前快照失败，已取消切换: {}", e))
+}
+
+/// 切换中断时需要回滚的内容
+#[derive(Debug, Default, Clone, Copy)]
+struct SwitchRollback {
+    /// 已写入撤销快照
+    undo_saved: bool,
+    /// 已开始写入数据库
+    db_written: bool,
+    /// 切换关闭了正在运行的 Antigravity
+    restart: bool,
+    /// 切换后已启动 Antigravity（回滚前需先关闭）
+    launched: bool,
+    /// 已开始启动验证
+    verifying: bool,
+}
+
+/// 回滚动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RollbackAction {
+    /// 关闭切换后启动的 Antigravity
+    StopLaunched,
+    /// 写回撤销快照
+    RestoreUndo,
+    /// 丢弃未使用的撤销快照
+    DiscardUndo,
+    /// 重新启动被切换关闭的 Antigravity
+    Restart,
+}
+
+impl SwitchRollback {
+    /// 回滚需要执行的动作（按执行顺序）；撤销快照未保存时无需回滚
+    fn actions(&self) -> Vec<RollbackAction> {
+        if !self.undo_saved {
+            return Vec::new();
+        }
+        let mut actions = Vec::new();
+        if self.launched {
+            actions.push(RollbackAction::StopLaunched);
+        }
+        actions.push(if self.db_written {
+            RollbackAction::RestoreUndo
+        } else {
+            RollbackAction::DiscardUndo
+        });
+        if self.restart {
+            actions.push(RollbackAction::Restart);
+        }
+        actions
+    }
+}
+
+/// 回滚被取消或失败的切换：写回（或丢弃）撤销快照，并重新启动被关闭的 Antigravity
+async fn rollback_switch(context: &OperationContext, rollback: &SwitchRollback) -> Result<String, String> {
+    use crate::antigravity::switch_undo;
+
+    context
+        .rollback(async {
+            let mut steps = Vec::new();
+            for action in rollback.actions() {
+                steps.push(match action {
+                    RollbackAction::StopLaunched => stop_antigravity_for_switch().await?,
+                    RollbackAction::RestoreUndo => {
+                        switch_undo::restore_last()?;
+                        "已恢复切换前的数据库状态".to_string()
+                    }
+                    RollbackAction::DiscardUndo => {
+                        switch_undo::discard_last()?;
+                        "数据库未修改".to_string()
+                    }
+                    RollbackAction::Restart => start_antigravity_after_switch().await,
+                });
             }
-        };
+            Ok(steps.join(" -> "))
+        })
+        .await
+}
 
-        // 等待一秒确保进程完全关闭
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+/// 分步执行切换，每一步发送进度事件，取消或失败时回滚已完成的步骤
+async fn switch_account(
+    mut context: OperationContext,
+    app: &tauri::AppHandle,
+    account_name: &str,
+    allow_expired: bool,
+    key_groups: Option<Vec<KeyGroup>>,
+    secret_policy: Option<SecretRestorePolicy>,
+) -> Result<String, String> {
+    // 令牌检查不修改任何数据，不计入步骤
+    if !allow_expired {
+        ensure_token_not_expired(account_name)?;
+    }
+    let groups = resolve_restore_groups(app, account_name, key_groups)?;
+    context.set_total(SWITCH_STEPS);
 
-        // 2. 恢复指定账户到 Antigravity 数据库
-        let restore_result = restore_antigravity_account(account_name.clone()).await?;
+    let mut rollback = SwitchRollback::default();
+    let result = async {
+        // 1. 保存切换前快照
+        context.checkpoint()?;
+        context.step("保存切换前快照", async { snapshot_before_switch(account_name) }).await?;
+        rollback.undo_saved = true;
+
+        // 2. 关闭 Antigravity 进程 (如果存在)
+        context.checkpoint()?;
+        let was_running = crate::platform::is_antigravity_running();
+        let kill_result = context.step("关闭 Antigravity", stop_antigravity_for_switch()).await?;
+        rollback.restart = was_running;
+
+        // 3. 恢复指定账户到 Antigravity 数据库
+        context.checkpoint()?;
+        rollback.db_written = true;
+        let restore_result = context
+            .step("恢复账户数据", restore_account(app, account_name, Some(groups.clone()), secret_policy))
+            .await?;
         tracing::debug!(target: "account::switch::step2", result = %restore_result, "账户数据恢复完成");
 
-        // 等待一秒确保数据库操作完成
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        // 4. 重新启动 Antigravity 进程（启动失败不视为切换失败，此时跳过启动验证）
+        context.checkpoint()?;
+        let start_message = context
+            .step("启动 Antigravity", async {
+                Ok(match launch_antigravity().await {
+                    Ok(message) => {
+                        rollback.launched = true;
+                        message
+                    }
+                    Err(e) => format!("启动失败: {}", e),
+                })
+            })
+            .await?;
+        if !rollback.launched {
+            context.set_total(SWITCH_STEPS - 1);
+            return Ok(format!("{} -> {} -> {}", kill_result, restore_result, start_message));
+        }
 
-        // 3. 重新启动 Antigravity 进程
-        let start_result = crate::antigravity::starter::start_antigravity();
-        let start_message = match start_result {
-            Ok(result) => {
-                tracing::debug!(target: "account::switch::step3", result = %result, "Antigravity 启动成功");
-                result
-            }
-            Err(e) => {
-                tracing::warn!(target: "account::switch::step3", error = %e, "Antigravity 启动失败");
-                format!("启动失败: {}", e)
+        // 5. 确认进程保持运行且登录的是目标账户（未恢复认证信息时只检查进程）
+        context.checkpoint()?;
+        rollback.verifying = true;
+        let expected_email = groups.contains(&KeyGroup::Auth).then_some(account_name);
+        let verify_message = context
+            .step("验证启动", async {
+                let verification = launch_verify::verify(expected_email).await;
+                match verification.status {
+                    LaunchStatus::Verified => Ok(verification.message),
+                    _ => Err(format!("启动验证失败: {}", verification.message)),
+                }
+            })
+            .await?;
+
+        Ok(format!("{} -> {} -> {} -> {}", kill_result, restore_result, start_message, verify_message))
+    }
+    .await;
+
+    let Err(e) = result else {
+        return result;
+    };
+    if rollback.actions().is_empty() {
+        return Err(e);
+    }
+    match rollback_switch(&context, &rollback).await {
+        Ok(message) => {
+            tracing::info!(target: "account::switch", "切换已中断并回滚");
+            if rollback.verifying {
+                launch_verify::mark_rolled_back(&message);
             }
-        };
+            Err(format!("{}（已回滚: {}）", e, message))
+        }
+        Err(rollback_error) => {
+            tracing::error!(target: "account::switch", error = %rollback_error, "切换回滚失败");
+            Err(format!("{}（回滚失败: {}，可通过撤销切换或数据库快照恢复）", e, rollback_error))
+        }
+    }
+}
+
+/// 切换到 Antigravity 账户（恢复逻辑与 restore_antigravity_account 相同）
+///
+/// 目标账户备份的令牌已过期时返回以 `令牌已过期` 开头的错误，
+/// 用户确认后以 `allow_expired = true` 重新调用即可强制切换。
+/// 切换前会把当前状态写入撤销栈，可通过 `undo_last_switch` 恢复。
+/// `key_groups` 指定只恢复部分字段分组（如只切换认证信息、保留本机设置）。
+/// 切换经操作队列串行执行，同一账户的切换已在排队时返回以 `操作繁忙` 开头的错误。
+/// 每一步发送 `operation-progress` 事件；通过 `cancel_operation` 取消后，
+/// 在当前步骤结束时停止并回滚，返回以 `操作已取消` 开头的错误。
+#[tauri::command]
+pub async fn switch_to_antigravity_account(
+    app: tauri::AppHandle,
+    account_name: String,
+    allow_expired: Option<bool>,
+    key_groups: Option<Vec<KeyGroup>>,
+    secret_policy: Option<SecretRestorePolicy>,
+) -> Result<String, String> {
+    crate::log_async_command!("switch_to_antigravity_account", async {
+        operations::run_steps(&app, OperationKind::Switch, Some(&account_name), |context| {
+            switch_account(
+                context,
+                &app,
+                &account_name,
+                allow_expired.unwrap_or(false),
+                key_groups,
+                secret_policy,
+            )
+        })
+        .await
+    })
+}
+
+/// 撤销最近一次账户切换：恢复切换前的数据库状态并重启 Antigravity
+#[tauri::command]
+pub async fn undo_last_switch(app: tauri::AppHandle) -> Result<String, String> {
+    crate::log_async_command!("undo_last_switch", async {
+        operations::run(&app, OperationKind::Switch, None, async {
+            let kill_result = stop_antigravity_for_switch().await?;
+
+            let undone = crate::antigravity::switch_undo::restore_last()?;
+            let restored = undone.from_email.unwrap_or_else(|| "未登录状态".to_string());
+            tracing::info!(target: "account::undo", "切换已撤销");
+
+            let start_message = start_antigravity_after_switch().await;
+            Ok(format!("{} -> 已恢复到 {} -> {}", kill_result, restored, start_message))
+        })
+        .await
+    })
+}
+
+/// 列出切换撤销栈（最新的在前）
+#[tauri::command]
+pub async fn list_switch_undo_stack() -> Result<Vec<crate::antigravity::switch_undo::UndoInfo>, String> {
+    crate::antigravity::switch_undo::list()
+}
+
+/// 读取账户的最新备份（解密并展开快照）
+fn load_account_backup(account_name: &str) -> Result<Value, String> {
+    let backup_file = account_index::resolve_backup_path(account_name)?
+        .filter(|path| path.exists())
+        .ok_or_else(|| format!("备份文件不存在: {}", account_name))?;
+    crate::antigravity::backup::read_backup_file(&backup_file)
+}
+
+/// 预览切换/恢复到指定账户后数据库的变化（值已脱敏）
+#[tauri::command]
+pub async fn preview_switch(
+    app: tauri::AppHandle,
+    account_name: String,
+    key_groups: Option<Vec<KeyGroup>>,
+) -> Result<crate::antigravity::switch_preview::SwitchPreview, String> {
+    crate::log_async_command!("preview_switch", async {
+        crate::antigravity::vault::vault().ensure_unlocked()?;
+        let backup = load_account_backup(&account_name)?;
+        let groups = resolve_restore_groups(&app, &account_name, key_groups)?;
+        let db_path = crate::path_utils::AppPaths::antigravity_data_dir()
+            .map(|dir| dir.join("state.vscdb"))
+            .ok_or("未找到数据库路径")?;
+        crate::antigravity::switch_preview::preview_restore(&db_path, &backup, &groups)
+    })
+}
+
+/// 列出可选的恢复字段分组
+#[tauri::command]
+pub async fn list_key_groups() -> Result<Vec<key_groups::KeyGroupInfo>, String> {
+    Ok(key_groups::describe_all(&crate::antigravity::key_sets::current()))
+}
 
-        let final_message = format!("{} -> {} -> {}", kill_result, restore_result, start_message);
+/// 获取账户的默认恢复分组（未设置时为全部分组）
+#[tauri::command]
+pub async fn get_account_restore_groups(
+    app: tauri::AppHandle,
+    account_name: String,
+) -> Result<Vec<KeyGroup>, String> {
+    resolve_restore_groups(&app, &account_name, None)
+}
+
+/// 设置账户的默认恢复分组，传入 `None` 恢复为全部分组
+#[tauri::command]
+pub async fn set_account_restore_groups(
+    app: tauri::AppHandle,
+    account_name: String,
+    key_groups: Option<Vec<KeyGroup>>,
+) -> Result<Vec<KeyGroup>, String> {
+    crate::log_async_command!("set_account_restore_groups", async {
+        if key_groups.as_ref().is_some_and(|groups| groups.is_empty()) {
+            return Err("至少需要选择一个恢复分组".to_string());
+        }
+        let account_id = account_index::load_or_rebuild()?
+            .find_by_email(&account_name)
+            .map(|summary| summary.id.clone())
+            .ok_or_else(|| format!("账户不存在: {}", account_name))?;
+
+        app.state::<crate::app_settings::AppSettingsManager>()
+            .update_settings(|settings| match key_groups {
+                Some(groups) => {
+                    settings.restore_groups.insert(account_id, key_groups::normalize(groups));
+                }
+                None => {
+                    settings.restore_groups.remove(&account_id);
+                }
+            })?;
+        resolve_restore_groups(&app, &account_name, None)
+    })
+}
 
-        Ok(final_message)
+/// 比较两个账户的备份（值已脱敏）
+#[tauri::command]
+pub async fn compare_account_backups(
+    account_a: String,
+    account_b: String,
+) -> Result<crate::antigravity::switch_preview::SwitchPreview, String> {
+    crate::log_async_command!("compare_account_backups", async {
+        crate::antigravity::vault::vault().ensure_unlocked()?;
+        let a = load_account_backup(&account_a)?;
+        let b = load_account_backup(&account_b)?;
+        Ok(crate::antigravity::switch_preview::compare_backups(&a, &b))
     })
 }
 
+/// 检测当前活动账户及其与备份的关系
+#[tauri::command]
+pub async fn get_active_account_status(
+    app: tauri::AppHandle,
+) -> Result<crate::antigravity::active_account::ActiveAccountStatus, String> {
+    crate::antigravity::active_account::refresh(&app)
+}
+
 /// 从 Antigravity 数据库读取用户状态
-/// 返回 userStatusProtoBinaryBase64 字段，用于解析配额信息
+/// 返回 userStatusProtoBinaryBase64 字段，用于解析配额信息（令牌字段已移除）
 #[tauri::command]
 #[instrument]
 pub async fn get_user_status_from_db() -> Result<Value, String> {
@@ -601,7 +949,8 @@ pub async fn get_user_status_from_db() -> Result<Value, String> {
         let auth_data: Value = from_str(&auth_json)
             .map_err(|e| format!("解析认证信息失败: {}", e))?;
         
-        Ok(auth_data)
+        // 令牌不返回给前端，只保留 hasApiKey 标记
+        Ok(crate::antigravity::credentials::redact_auth_status(auth_data))
     }.await;
     
     let duration = start_time.elapsed();
@@ -626,3 +975,58 @@ pub async fn get_user_status_from_db() -> Result<Value, String> {
 }
 
 // 命令函数将在后续步骤中移动到这里
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch_rollback_matrix() {
+        use RollbackAction::*;
+
+        // (db_written, launched, restart) -> 回滚动作
+        let cases = [
+            ((false, false, false), vec![DiscardUndo]),
+            ((false, false, true), vec![DiscardUndo, Restart]),
+            ((false, true, false), vec![StopLaunched, DiscardUndo]),
+            ((false, true, true), vec![StopLaunched, DiscardUndo, Restart]),
+            ((true, false, false), vec![RestoreUndo]),
+            ((true, false, true), vec![RestoreUndo, Restart]),
+            ((true, true, false), vec![StopLaunched, RestoreUndo]),
+            ((true, true, true), vec![StopLaunched, RestoreUndo, Restart]),
+        ];
+        for ((db_written, launched, restart), expected) in cases {
+            let rollback = SwitchRollback { undo_saved: true, db_written, launched, restart, verifying: false };
+            assert_eq!(rollback.actions(), expected, "{:?}", rollback);
+
+            // 撤销快照未保存时不回滚
+            let unsaved = SwitchRollback { undo_saved: false, ..rollback };
+            assert!(unsaved.actions().is_empty(), "{:?}", unsaved);
+        }
+    }
+
+    #[test]
+    fn test_switch_rollback_by_interrupted_step() {
+        use RollbackAction::*;
+
+        // 按切换流程中各步骤之后设置的标记，列出在该步骤之后中断时的回滚动作
+        let after_snapshot = SwitchRollback { undo_saved: true, ..Default::default() };
+        assert_eq!(after_snapshot.actions(), vec![DiscardUndo]);
+
+        let after_stop = SwitchRollback { restart: true, ..after_snapshot };
+        assert_eq!(after_stop.actions(), vec![DiscardUndo, Restart]);
+
+        let during_restore = SwitchRollback { db_written: true, ..after_stop };
+        assert_eq!(during_restore.actions(), vec![RestoreUndo, Restart]);
+
+        let after_launch = SwitchRollback { launched: true, ..during_restore };
+        assert_eq!(after_launch.actions(), vec![StopLaunched, RestoreUndo, Restart]);
+
+        let verifying = SwitchRollback { verifying: true, ..after_launch };
+        assert_eq!(verifying.actions(), after_launch.actions());
+
+        // 切换前 Antigravity 未运行：回滚后不重新启动
+        let not_running = SwitchRollback { restart: false, ..verifying };
+        assert_eq!(not_running.actions(), vec![StopLaunched, RestoreUndo]);
+    }
+}
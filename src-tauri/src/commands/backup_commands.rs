@@ -1,10 +1,12 @@
+use crate::antigravity::secret_storage::{self, BackupOrigin};
+use crate::operations::{self, OperationKind};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::Path;
 /// 备份相关命令
 /// 负责配置文件和账户的备份、恢复、删除等操作
-use tauri::State;
+use tauri::{Manager, State};
 
 /// 备份数据收集结构
 #[derive(Serialize, Deserialize, Debug)]
@@ -14,6 +16,9 @@ pub struct BackupData {
     content: Value,
     #[serde(rename = "timestamp")]
     timestamp: u64,
+    /// 备份中的扩展密钥字段（依赖系统钥匙串，导出到其他设备后无法解密）
+    #[serde(default, rename = "secretKeys")]
+    secret_keys: Vec<String>,
 }
 
 /// 恢复结果
@@ -22,6 +27,18 @@ pub struct RestoreResult {
     #[serde(rename = "restoredCount")]
     restored_count: u32,
     failed: Vec<FailedBackup>,
+    /// 含有无法在本机解密的扩展密钥的备份
+    #[serde(rename = "secretWarnings")]
+    secret_warnings: Vec<SecretWarning>,
+}
+
+/// 导入的备份中扩展密钥的提示
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SecretWarning {
+    filename: String,
+    #[serde(rename = "secretCount")]
+    secret_count: usize,
+    origin: BackupOrigin,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -194,54 +211,32 @@ pub async fn get_recent_accounts(
 
 /// 收集所有备份文件的完整内容
 #[tauri::command]
-pub async fn collect_backup_contents(
-    state: State<'_, AppState>,
-) -> Result<Vec<BackupData>, String> {
-    let mut backups_with_content = Vec::new();
-
-    // 读取Antigravity账户目录中的JSON文件
-    let antigravity_dir = state.config_dir.join("antigravity-accounts");
-
-    if !antigravity_dir.exists() {
-        return Ok(backups_with_content);
-    }
+pub async fn collect_backup_contents() -> Result<Vec<BackupData>, String> {
+    collect_backups()
+}
 
-    for entry in fs::read_dir(&antigravity_dir).map_err(|e| format!("读取用户目录失败: {}", e))?
-    {
-        let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
-        let path = entry.path();
+/// 按账户索引逐个解密备份，导出为旧版 `<邮箱>.json` 格式（导入后按旧版备份迁移）
+fn collect_backups() -> Result<Vec<BackupData>, String> {
+    use crate::antigravity::{account_index, backup::read_backup_file};
 
-        if path.extension().is_some_and(|ext| ext == "json") {
-            let filename = path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .map(|s| s.to_string())
-                .unwrap_or_default();
-
-            if filename.is_empty() {
-                continue;
-            }
+    let antigravity_dir = crate::path_utils::AppPaths::backup_dir().ok_or("无法获取备份目录")?;
+    let index = account_index::load_or_rebuild()?;
+    let mut backups_with_content = Vec::new();
 
-            match fs::read_to_string(&path).map_err(|e| format!("读取文件失败 {}: {}", filename, e))
-            {
-                Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
-                    Ok(json_value) => {
-                        backups_with_content.push(BackupData {
-                            filename,
-                            content: json_value,
-                            timestamp: std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_secs(),
-                        });
-                    }
-                    Err(e) => {
-                        tracing::warn!(target: "backup::scan", filename = %filename, error = %e, "跳过损坏的备份文件");
-                    }
-                },
-                Err(_) => {
-                    tracing::warn!(target: "backup::scan", filename = %filename, "跳过无法读取的文件");
-                }
+    for summary in index.accounts() {
+        let path = account_index::backup_file_path(&antigravity_dir, &summary.id);
+        match read_backup_file(&path) {
+            Ok(content) => backups_with_content.push(BackupData {
+                filename: format!("{}.json", summary.email),
+                secret_keys: secret_storage::secret_keys(&content),
+                content,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            }),
+            Err(e) => {
+                tracing::warn!(target: "backup::scan", file = %summary.id, error = %e, "跳过无法读取的备份");
             }
         }
     }
@@ -249,22 +244,27 @@ pub async fn collect_backup_contents(
     Ok(backups_with_content)
 }
 
-/// 恢复备份文件到本地
+/// 恢复备份文件到本地（经操作队列串行执行）
 #[tauri::command]
 pub async fn restore_backup_files(
+    app: tauri::AppHandle,
     backups: Vec<BackupData>,
     state: State<'_, AppState>,
 ) -> Result<RestoreResult, String> {
+    // 获取目标目录
+    let antigravity_dir = state.config_dir.join("antigravity-accounts");
+    operations::run(&app, OperationKind::Import, None, import_backups(backups, &antigravity_dir)).await
+}
+
+async fn import_backups(backups: Vec<BackupData>, antigravity_dir: &Path) -> Result<RestoreResult, String> {
     let mut results = RestoreResult {
         restored_count: 0,
         failed: Vec::new(),
+        secret_warnings: Vec::new(),
     };
 
-    // 获取目标目录
-    let antigravity_dir = state.config_dir.join("antigravity-accounts");
-
     // 确保目录存在
-    if let Err(e) = fs::create_dir_all(&antigravity_dir) {
+    if let Err(e) = fs::create_dir_all(antigravity_dir) {
         return Err(format!("创建目录失败: {}", e));
     }
 
@@ -272,6 +272,16 @@ pub async fn restore_backup_files(
     for backup in backups {
         let file_path = antigravity_dir.join(&backup.filename);
 
+        // 扩展密钥只能在备份来源设备上解密，来源不是本机时提示
+        let secrets = secret_storage::inspect(&backup.content);
+        if !secrets.secret_keys.is_empty() && secrets.origin != BackupOrigin::ThisMachine {
+            results.secret_warnings.push(SecretWarning {
+                filename: backup.filename.clone(),
+                secret_count: secrets.secret_keys.len(),
+                origin: secrets.origin,
+            });
+        }
+
         match fs::write(
             &file_path,
             serde_json::to_string_pretty(&backup.content).unwrap_or_default(),
@@ -290,48 +300,98 @@ pub async fn restore_backup_files(
         }
     }
 
+    // 导入的旧版 .json 备份在下次读取索引时迁移，无需重建索引
     Ok(results)
 }
 
-/// 删除指定备份
+/// 删除指定备份（经操作队列串行执行）
 #[tauri::command]
-pub async fn delete_backup(name: String, state: State<'_, AppState>) -> Result<String, String> {
-    // 删除 Antigravity 账户备份文件（支持 .enc 和 .json 格式）
+pub async fn delete_backup(
+    app: tauri::AppHandle,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
     let antigravity_dir = state.config_dir.join("antigravity-accounts");
-    let encrypted_file = antigravity_dir.join(format!("{}.enc", name));
-    let legacy_file = antigravity_dir.join(format!("{}.json", name));
-
-    let mut deleted = false;
+    operations::run(&app, OperationKind::Delete, Some(&name), remove_backup(&app, &name, &antigravity_dir)).await
+}
 
-    // 删除加密文件
-    if encrypted_file.exists() {
-        fs::remove_file(&encrypted_file).map_err(|e| format!("删除加密备份失败: {}", e))?;
-        deleted = true;
-    }
+async fn remove_backup(app: &tauri::AppHandle, name: &str, antigravity_dir: &Path) -> Result<String, String> {
+    use crate::antigravity::{account_index, account_keys, quota_history, snapshot_store};
+
+    // 通过加密索引找到账户 ID 对应的备份文件
+    // 先销毁数据密钥（加密擦除），残留密文即不可恢复；备份文件删除成功后才移除索引记录，
+    // 删除失败时保留记录以便重试，不留下索引之外的孤立文件
+    let removed = account_index::update(|index| {
+        let Some(summary) = index.find_by_email(name).cloned() else {
+            return Ok(None);
+        };
+        account_keys::destroy(&summary.id)?;
+
+        let backup_file = account_index::backup_file_path(antigravity_dir, &summary.id);
+        if backup_file.exists() {
+            fs::remove_file(&backup_file).map_err(|e| format!("删除加密备份失败: {}", e))?;
+        }
+        index.remove_by_email(name);
+        Ok::<_, String>(Some(summary))
+    })??;
 
-    // 删除明文文件（如果存在）
-    if legacy_file.exists() {
-        fs::remove_file(&legacy_file).map_err(|e| format!("删除明文备份失败: {}", e))?;
-        deleted = true;
-    }
+    match removed {
+        Some(summary) => {
 
-    if deleted {
-        Ok(format!("删除用户成功: {}", name))
-    } else {
-        Err("用户文件不存在".to_string())
+            // 删除历史快照与数据块，释放空间
+            snapshot_store::delete_account_data(&summary.id)?;
+            if let Err(e) = snapshot_store::collect_garbage() {
+                tracing::warn!(target: "backup::store", error = %e, "快照垃圾回收失败");
+            }
+            if let Err(e) = quota_history::delete_account(&summary.id) {
+                tracing::warn!(target: "quota::history", error = %e, "删除配额历史失败");
+            }
+            // 无法读取的撤销快照与数据库安全快照可能仍含该账户的令牌，在结果中提示用户
+            let mut unchecked = 0;
+            match crate::antigravity::switch_undo::purge_account(&summary.email) {
+                Ok(report) => unchecked += report.unchecked,
+                Err(e) => tracing::warn!(target: "account::undo", error = %e, "删除撤销快照失败"),
+            }
+            match crate::antigravity::db_snapshots::purge_account(&summary.email) {
+                Ok(report) => unchecked += report.unchecked,
+                Err(e) => tracing::warn!(target: "db::snapshot", error = %e, "删除数据库安全快照失败"),
+            }
+            if let Err(e) = app
+                .state::<crate::app_settings::AppSettingsManager>()
+                .update_settings(|settings| {
+                    settings.restore_groups.remove(&summary.id);
+                })
+            {
+                tracing::warn!(target: "app_settings::update", error = %e, "删除账户恢复分组设置失败");
+            }
+            if unchecked > 0 {
+                return Ok(format!(
+                    "删除用户成功: {}（{} 个撤销快照或数据库安全快照无法读取，可能仍含该账户的登录信息）",
+                    name, unchecked
+                ));
+            }
+            Ok(format!("删除用户成功: {}", name))
+        }
+        None => Err("用户文件不存在".to_string()),
     }
 }
 
-/// 清空所有备份
+/// 清空所有备份（经操作队列串行执行）
 #[tauri::command]
-pub async fn clear_all_backups(state: State<'_, AppState>) -> Result<String, String> {
+pub async fn clear_all_backups(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<String, String> {
     let antigravity_dir = state.config_dir.join("antigravity-accounts");
+    operations::run(&app, OperationKind::Delete, None, clear_backups(&antigravity_dir)).await
+}
 
+async fn clear_backups(antigravity_dir: &Path) -> Result<String, String> {
     if antigravity_dir.exists() {
+        // 先销毁密钥表（加密擦除），所有账户的备份密文即不可恢复
+        crate::antigravity::account_keys::destroy_all()?;
+
         // 读取目录中的所有文件
         let mut deleted_count = 0;
         for entry in
-            fs::read_dir(&antigravity_dir).map_err(|e| format!("读取用户目录失败: {}", e))?
+            fs::read_dir(antigravity_dir).map_err(|e| format!("读取用户目录失败: {}", e))?
         {
             let entry = entry.map_err(|e| format!("读取目录项失败: {}", e))?;
             let path = entry.path();
@@ -345,6 +405,19 @@ pub async fn clear_all_backups(state: State<'_, AppState>) -> Result<String, Str
             }
         }
 
+        // 删除去重快照存储
+        let store_dir = antigravity_dir.join("store");
+        if store_dir.exists() {
+            fs::remove_dir_all(&store_dir).map_err(|e| format!("删除快照存储失败: {}", e))?;
+        }
+
+        // 账户 ID 已全部失效，配额历史一并清空
+        crate::antigravity::quota_history::clear_all()?;
+
+        // 撤销快照与数据库安全快照中同样含有账户令牌
+        crate::antigravity::switch_undo::clear()?;
+        crate::antigravity::db_snapshots::clear()?;
+
         Ok(format!(
             "已清空所有用户备份，共删除 {} 个文件",
             deleted_count
@@ -354,4 +427,93 @@ pub async fn clear_all_backups(state: State<'_, AppState>) -> Result<String, Str
     }
 }
 
+/// 查找账户 ID（通过加密索引，大小写不敏感）
+fn find_account_id(account_name: &str) -> Result<String, String> {
+    crate::antigravity::account_index::load_or_rebuild()?
+        .find_by_email(account_name)
+        .map(|summary| summary.id.clone())
+        .ok_or_else(|| format!("账户不存在: {}", account_name))
+}
+
+/// 列出账户的历史快照
+#[tauri::command]
+pub async fn list_backup_snapshots(
+    account_name: String,
+) -> Result<Vec<crate::antigravity::snapshot_store::SnapshotInfo>, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    let account_id = find_account_id(&account_name)?;
+    crate::antigravity::snapshot_store::list_snapshots(&account_id)
+}
+
+/// 删除账户的单个历史快照
+#[tauri::command]
+pub async fn delete_backup_snapshot(account_name: String, snapshot_id: String) -> Result<String, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    let account_id = find_account_id(&account_name)?;
+    crate::antigravity::snapshot_store::delete_snapshot(&account_id, &snapshot_id)?;
+    Ok(format!("已删除快照: {}", snapshot_id))
+}
+
+/// 将账户的某个历史快照恢复到 Antigravity 数据库
+#[tauri::command]
+pub async fn restore_backup_snapshot(
+    app: tauri::AppHandle,
+    account_name: String,
+    snapshot_id: String,
+) -> Result<String, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    let account_id = find_account_id(&account_name)?;
+    let path = crate::antigravity::snapshot_store::snapshot_path(&account_id, &snapshot_id)?;
+    operations::run(
+        &app,
+        OperationKind::Restore,
+        Some(&account_name),
+        crate::antigravity::restore::restore_all_antigravity_data(path),
+    )
+    .await
+}
+
+/// 回收快照存储中不再被引用的数据
+#[tauri::command]
+pub async fn collect_backup_garbage() -> Result<crate::antigravity::snapshot_store::GcReport, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    crate::antigravity::snapshot_store::collect_garbage()
+}
+
+/// 获取快照存储占用统计
+#[tauri::command]
+pub async fn get_backup_store_stats() -> Result<crate::antigravity::snapshot_store::StoreStats, String> {
+    crate::antigravity::snapshot_store::stats()
+}
+
 // 备份相关函数将在后续步骤中移动到这里
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antigravity::secret_storage::SECRET_KEY_PREFIX;
+    use crate::path_utils::{use_test_config_dir, AppPaths};
+
+    #[test]
+    fn test_collect_backups_reports_secret_keys() {
+        use_test_config_dir("backup-export-secrets");
+        let dir = AppPaths::backup_dir().unwrap();
+        fs::create_dir_all(&dir).unwrap();
+
+        // 旧版明文备份在读取索引时迁移为加密的 <ID>.enc
+        let secret_key = format!("{}{{\"extensionId\":\"x\"}}", SECRET_KEY_PREFIX);
+        let backup = serde_json::json!({
+            "account_email": "a@example.com",
+            "backup_time": "2024-01-01T00:00:00+00:00",
+            secret_key.as_str(): "{\"data\":[1,2,3]}",
+        });
+        fs::write(dir.join("a@example.com.json"), backup.to_string()).unwrap();
+
+        let exported = collect_backups().unwrap();
+        assert!(!dir.join("a@example.com.json").exists());
+        assert_eq!(exported.len(), 1);
+        assert_eq!(exported[0].filename, "a@example.com.json");
+        assert_eq!(exported[0].secret_keys, vec![secret_key.clone()]);
+        assert_eq!(exported[0].content[&secret_key], "{\"data\":[1,2,3]}");
+    }
+}
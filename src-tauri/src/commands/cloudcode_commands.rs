@@ -0,0 +1,122 @@
+/// CloudCode 相关命令
+/// 需要访问令牌的请求都在 Rust 侧完成，前端只传递不透明的账户句柄
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::antigravity::credentials;
+use crate::cloudcode::client::{
+    validate_base_url, CloudCodeConfig, FetchAvailableModelsResponse, DEFAULT_BASE_URL,
+};
+use crate::cloudcode::CloudCodeClient;
+
+/// 单个模型的配额信息
+#[derive(Debug, Serialize)]
+pub struct ModelQuota {
+    pub model: String,
+    pub display_name: Option<String>,
+    pub remaining_fraction: f64,
+    pub reset_time: Option<String>,
+}
+
+/// CloudCode 接口配置（供前端展示）
+#[derive(Debug, Serialize)]
+pub struct CloudCodeSettings {
+    pub base_url: String,
+    pub default_base_url: String,
+    pub user_agent: String,
+}
+
+/// 按应用设置创建客户端
+fn client_from_settings(app: &AppHandle) -> CloudCodeClient {
+    let settings = app
+        .state::<crate::app_settings::AppSettingsManager>()
+        .get_settings();
+    CloudCodeClient::with_config(CloudCodeConfig {
+        base_url: settings
+            .cloudcode_base_url
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        ..Default::default()
+    })
+}
+
+/// 获取账户的可用模型列表（响应中不含令牌）
+async fn fetch_models_for_handle(
+    app: &AppHandle,
+    handle: &str,
+) -> Result<FetchAvailableModelsResponse, String> {
+    crate::antigravity::vault::vault().ensure_unlocked()?;
+    let token = credentials::account_token(handle)?;
+    let client = client_from_settings(app);
+
+    let project = client.project_for(&token).await?;
+    Ok(client.fetch_available_models(&token, &project).await?)
+}
+
+/// 获取账户的可用模型列表
+#[tauri::command]
+pub async fn fetch_account_models(
+    app: AppHandle,
+    handle: String,
+) -> Result<FetchAvailableModelsResponse, String> {
+    crate::log_async_command!("fetch_account_models", async {
+        fetch_models_for_handle(&app, &handle).await
+    })
+}
+
+/// 获取账户各模型的配额
+#[tauri::command]
+pub async fn fetch_account_quota(app: AppHandle, handle: String) -> Result<Vec<ModelQuota>, String> {
+    crate::log_async_command!("fetch_account_quota", async {
+        let response = fetch_models_for_handle(&app, &handle).await?;
+        let quotas = response
+            .models
+            .into_iter()
+            .filter_map(|(model, info)| {
+                let quota = info.quota_info?;
+                Some(ModelQuota {
+                    model,
+                    display_name: info.display_name,
+                    remaining_fraction: quota.remaining_fraction,
+                    reset_time: quota.reset_time,
+                })
+            })
+            .collect();
+        Ok(quotas)
+    })
+}
+
+/// 获取 CloudCode 接口配置
+#[tauri::command]
+pub async fn get_cloudcode_settings(app: AppHandle) -> Result<CloudCodeSettings, String> {
+    let settings = app
+        .state::<crate::app_settings::AppSettingsManager>()
+        .get_settings();
+    Ok(CloudCodeSettings {
+        base_url: settings
+            .cloudcode_base_url
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        default_base_url: DEFAULT_BASE_URL.to_string(),
+        user_agent: crate::cloudcode::client::platform_user_agent(),
+    })
+}
+
+/// 设置 CloudCode 接口地址（传空值恢复默认）
+#[tauri::command]
+pub async fn set_cloudcode_base_url(app: AppHandle, base_url: Option<String>) -> Result<String, String> {
+    crate::log_async_command!("set_cloudcode_base_url", async {
+        let base_url = base_url
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+
+        if let Some(url) = &base_url {
+            validate_base_url(url)?;
+        }
+
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+        settings_manager.update_settings(|settings| {
+            settings.cloudcode_base_url = base_url.clone();
+        })?;
+
+        Ok(base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()))
+    })
+}
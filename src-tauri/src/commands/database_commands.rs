@@ -0,0 +1,77 @@
+/// 数据库维护命令
+/// Antigravity 状态数据库（state.vscdb 及其 .backup）的安全快照、回滚、健康检查与一致性同步
+use crate::antigravity::db_consistency::{self, ConsistencyReport, SyncDirection, SyncResult};
+use crate::antigravity::db_health::{self, DbFile, DbHealth, KeyEntry, VacuumResult};
+use crate::antigravity::db_snapshots::{self, DbSnapshotInfo, SnapshotReason};
+use crate::operations::{self, OperationKind};
+
+/// 列出数据库安全快照（最新的在前）
+#[tauri::command]
+pub async fn list_db_snapshots() -> Result<Vec<DbSnapshotInfo>, String> {
+    db_snapshots::list()
+}
+
+/// 手动创建数据库安全快照
+#[tauri::command]
+pub async fn create_db_snapshot() -> Result<DbSnapshotInfo, String> {
+    crate::log_async_command!("create_db_snapshot", async {
+        db_snapshots::take(SnapshotReason::Manual)?.ok_or_else(|| "Antigravity 数据库不存在".to_string())
+    })
+}
+
+/// 将整个数据库回滚到指定快照（需先关闭 Antigravity）
+#[tauri::command]
+pub async fn rollback_db_snapshot(app: tauri::AppHandle, snapshot_id: String) -> Result<DbSnapshotInfo, String> {
+    crate::log_async_command!("rollback_db_snapshot", async {
+        operations::run(&app, OperationKind::Maintenance, Some(&format!("回滚 {}", snapshot_id)), async {
+            db_snapshots::rollback(&snapshot_id)
+        }).await
+    })
+}
+
+/// 删除数据库安全快照
+#[tauri::command]
+pub async fn delete_db_snapshot(snapshot_id: String) -> Result<String, String> {
+    db_snapshots::delete(&snapshot_id)?;
+    Ok(format!("已删除数据库快照: {}", snapshot_id))
+}
+
+/// 检查数据库完整性与日志模式
+#[tauri::command]
+pub async fn check_db_health() -> Result<Vec<DbHealth>, String> {
+    crate::log_async_command!("check_db_health", async { db_health::check() })
+}
+
+/// 整理（VACUUM）数据库（需先关闭 Antigravity，整理前自动创建安全快照）
+#[tauri::command]
+pub async fn vacuum_db(app: tauri::AppHandle) -> Result<Vec<VacuumResult>, String> {
+    crate::log_async_command!("vacuum_db", async {
+        operations::run(&app, OperationKind::Maintenance, Some("整理"), async { db_health::vacuum() }).await
+    })
+}
+
+/// 列出数据库中的全部字段及其大小与类型
+#[tauri::command]
+pub async fn list_db_keys(file: Option<DbFile>) -> Result<Vec<KeyEntry>, String> {
+    db_health::list_keys(file.unwrap_or(DbFile::Main))
+}
+
+/// 查看单个字段的脱敏值
+#[tauri::command]
+pub async fn get_db_value(key: String, file: Option<DbFile>) -> Result<String, String> {
+    db_health::read_value(file.unwrap_or(DbFile::Main), &key)
+}
+
+/// 比较主库与备份库中参与备份的字段与 Marker
+#[tauri::command]
+pub async fn check_db_consistency() -> Result<ConsistencyReport, String> {
+    crate::log_async_command!("check_db_consistency", async { db_consistency::check() })
+}
+
+/// 按指定方向同步主库与备份库（需先关闭 Antigravity，同步前自动创建安全快照）
+#[tauri::command]
+pub async fn sync_db_files(app: tauri::AppHandle, direction: SyncDirection) -> Result<SyncResult, String> {
+    crate::log_async_command!("sync_db_files", async {
+        operations::run(&app, OperationKind::Maintenance, Some("同步"), async { db_consistency::sync(direction) }).await
+    })
+}
@@ -0,0 +1,27 @@
+/// 字段定义命令
+/// 查看、保存和重新加载备份/恢复/注销所用的字段定义（key_sets.json）
+use crate::antigravity::key_sets::{self, KeySetFile, KeySetInfo};
+
+/// 获取当前生效的字段定义及其来源
+#[tauri::command]
+pub async fn get_key_sets() -> Result<KeySetInfo, String> {
+    Ok(key_sets::info())
+}
+
+/// 获取内置默认字段定义（可作为编辑定义文件的模板）
+#[tauri::command]
+pub async fn get_builtin_key_sets() -> Result<KeySetFile, String> {
+    Ok(key_sets::builtin_definitions())
+}
+
+/// 保存字段定义，传入 `None` 删除定义文件并恢复内置定义
+#[tauri::command]
+pub async fn save_key_sets(definitions: Option<KeySetFile>) -> Result<KeySetInfo, String> {
+    crate::log_async_command!("save_key_sets", async { key_sets::save(definitions) })
+}
+
+/// 重新加载定义文件（手动编辑文件后调用）
+#[tauri::command]
+pub async fn reload_key_sets() -> Result<KeySetInfo, String> {
+    crate::log_async_command!("reload_key_sets", async { key_sets::reload() })
+}
@@ -26,14 +26,38 @@ pub mod settings_commands;
 
 // 数据库监控命令
 pub mod db_monitor_commands;
+
+// 备份保险库命令
+pub mod vault_commands;
+
+// CloudCode 接口命令
+pub mod cloudcode_commands;
+
+// 配额历史命令
+pub mod quota_history_commands;
+
+// 字段定义命令
+pub mod key_set_commands;
+
+// 数据库维护命令
+pub mod database_commands;
+
+// 操作队列命令
+pub mod operation_commands;
 // 语言服务器相关命令（在 src/language_server 下）
 
 // 重新导出所有命令，保持与 main.rs 的兼容性
 pub use account_commands::*;
 pub use backup_commands::*;
+pub use cloudcode_commands::*;
+pub use database_commands::*;
 pub use db_monitor_commands::*;
+pub use key_set_commands::*;
 pub use logging_commands::*;
+pub use operation_commands::*;
 pub use platform_commands::*;
 pub use process_commands::*;
+pub use quota_history_commands::*;
 pub use settings_commands::*;
 pub use tray_commands::*;
+pub use vault_commands::*;
@@ -0,0 +1,17 @@
+/// 操作队列命令
+/// 查看正在执行与排队中的修改类操作，并取消操作
+use tauri::Manager;
+
+use crate::operations::{OperationCoordinator, OperationInfo};
+
+/// 列出正在执行与排队中的操作（正在执行的在前）
+#[tauri::command]
+pub async fn list_operations(app: tauri::AppHandle) -> Result<Vec<OperationInfo>, String> {
+    Ok(app.state::<OperationCoordinator>().list())
+}
+
+/// 取消操作：排队中的操作立即取消，分步执行的操作（如切换账户）在当前步骤结束后取消并回滚
+#[tauri::command]
+pub async fn cancel_operation(app: tauri::AppHandle, operation_id: String) -> Result<OperationInfo, String> {
+    app.state::<OperationCoordinator>().cancel(&operation_id)
+}
@@ -19,48 +19,45 @@ pub async fn is_antigravity_running() -> bool {
     crate::platform::is_antigravity_running()
 }
 
-/// 列出所有 Antigravity 相关的进程（用于调试）
+/// 验证 Antigravity 已启动并保持运行，指定账户时同时确认登录的是该账户
 #[tauri::command]
-pub async fn list_antigravity_processes() -> Result<Vec<serde_json::Value>, String> {
-    use serde_json::json;
-
-    tracing::info!("🔍 搜索所有 Antigravity 相关进程");
-
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
-
-    let mut found_processes = Vec::new();
-    let process_patterns = crate::platform::get_antigravity_process_patterns_for_debug();
-
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
-
-        for (i, pattern) in process_patterns.iter().enumerate() {
-            if crate::platform::matches_antigravity_process_for_debug(
-                process_name,
-                &process_cmd,
-                pattern,
-            ) {
-                found_processes.push(json!({
-                    "pid": pid.to_string(),
-                    "name": process_name,
-                    "command": process_cmd,
-                    "matched_pattern": i,
-                    "pattern_description": format!("{:?}", pattern)
-                }));
-                break; // 每个进程只记录一次
-            }
-        }
-    }
+pub async fn verify_antigravity_launch(
+    expected_email: Option<String>,
+) -> Result<crate::antigravity::launch_verify::LaunchVerification, String> {
+    Ok(crate::antigravity::launch_verify::verify(expected_email.as_deref()).await)
+}
 
-    tracing::info!("📊 找到 {} 个 Antigravity 相关进程", found_processes.len());
-    Ok(found_processes)
+/// 获取最近一次启动验证的结果
+#[tauri::command]
+pub async fn get_last_launch_verification() -> Option<crate::antigravity::launch_verify::LaunchVerification> {
+    crate::antigravity::launch_verify::last()
 }
 
-/// 备份并重启 Antigravity
+/// 获取最近一次启动 Antigravity 的输出（stdout / stderr，已脱敏）与退出情况
 #[tauri::command]
-pub async fn backup_and_restart_antigravity() -> Result<String, String> {
+pub async fn get_last_launch_output() -> Result<crate::antigravity::launch_log::LaunchOutput, String> {
+    crate::antigravity::launch_log::last_output()
+}
+
+/// 列出 Antigravity 相关进程（主进程、辅助进程、语言服务器）及其 CPU 与内存占用
+#[tauri::command]
+pub async fn list_antigravity_processes() -> Result<Vec<crate::platform::process_watcher::AntigravityProcess>, String> {
+    Ok(crate::platform::process_watcher::processes())
+}
+
+/// 备份并重启 Antigravity（经操作队列串行执行）
+#[tauri::command]
+pub async fn backup_and_restart_antigravity(app: tauri::AppHandle) -> Result<String, String> {
+    crate::operations::run(
+        &app,
+        crate::operations::OperationKind::Restart,
+        None,
+        backup_and_restart(),
+    )
+    .await
+}
+
+async fn backup_and_restart() -> Result<String, String> {
     println!("🔄 开始执行 backup_and_restart_antigravity 命令");
 
     // 1. 关闭进程 (如果存在)
@@ -0,0 +1,41 @@
+/// 配额历史命令
+/// 按账户句柄（随机账户 ID）查询配额时间序列与重置时间预测
+use crate::antigravity::quota_history::{self, QuotaSample, ResetPrediction};
+
+/// 单次查询返回的最大样本数
+const DEFAULT_HISTORY_LIMIT: usize = 5000;
+
+/// 查询账户的配额历史（用于图表）
+///
+/// # 参数
+/// - `model`: 仅返回该模型的样本，为空时返回全部模型
+/// - `since`: 起始时间（RFC3339）
+/// - `limit`: 最多返回的最新样本数
+#[tauri::command]
+pub async fn get_quota_history(
+    handle: String,
+    model: Option<String>,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<QuotaSample>, String> {
+    let since = since
+        .map(|s| {
+            chrono::DateTime::parse_from_rfc3339(&s)
+                .map(|t| t.timestamp())
+                .map_err(|e| format!("起始时间格式错误: {}", e))
+        })
+        .transpose()?;
+
+    quota_history::history(
+        &handle,
+        model.as_deref(),
+        since,
+        limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(DEFAULT_HISTORY_LIMIT),
+    )
+}
+
+/// 预测账户各模型的下次配额重置时间
+#[tauri::command]
+pub async fn get_quota_reset_predictions(handle: String) -> Result<Vec<ResetPrediction>, String> {
+    quota_history::predict_resets(&handle)
+}
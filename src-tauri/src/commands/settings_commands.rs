@@ -3,6 +3,8 @@
 
 use tauri::{AppHandle, Manager};
 
+use crate::antigravity::secret_storage::SecretRestorePolicy;
+
 /// 获取静默启动状态
 #[tauri::command]
 pub async fn is_silent_start_enabled(app: AppHandle) -> Result<bool, String> {
@@ -27,6 +29,23 @@ pub async fn save_silent_start_state(app: AppHandle, enabled: bool) -> Result<bo
     })
 }
 
+/// 保存扩展密钥恢复策略
+#[tauri::command]
+pub async fn save_secret_restore_policy(
+    app: AppHandle,
+    policy: SecretRestorePolicy,
+) -> Result<SecretRestorePolicy, String> {
+    crate::log_async_command!("save_secret_restore_policy", async {
+        let settings_manager = app.state::<crate::app_settings::AppSettingsManager>();
+
+        settings_manager.update_settings(|settings| {
+            settings.secret_restore_policy = policy;
+        })?;
+
+        Ok(policy)
+    })
+}
+
 /// 获取所有应用设置
 #[tauri::command]
 pub async fn get_all_settings(app: AppHandle) -> Result<serde_json::Value, String> {
@@ -36,7 +55,10 @@ pub async fn get_all_settings(app: AppHandle) -> Result<serde_json::Value, Strin
 
         Ok(serde_json::json!({
             "system_tray_enabled": settings.system_tray_enabled,
-            "silent_start_enabled": settings.silent_start_enabled
+            "silent_start_enabled": settings.silent_start_enabled,
+            "cloudcode_base_url": settings.cloudcode_base_url,
+            "restore_groups": settings.restore_groups,
+            "secret_restore_policy": settings.secret_restore_policy
         }))
     })
 }
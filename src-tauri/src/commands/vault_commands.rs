@@ -0,0 +1,59 @@
+//! 备份保险库命令
+//! 负责主密码保险库的启用、关闭、解锁与锁定
+
+use tauri::{AppHandle, Emitter};
+
+use crate::antigravity::vault::{vault, VaultStatus};
+
+/// 获取保险库状态
+#[tauri::command]
+pub async fn get_vault_status() -> Result<VaultStatus, String> {
+    crate::log_async_command!("get_vault_status", async { vault().status() })
+}
+
+/// 使用主密码解锁保险库
+#[tauri::command]
+pub async fn unlock_vault(password: String) -> Result<VaultStatus, String> {
+    crate::log_async_command!("unlock_vault", async {
+        vault().unlock(&password)?;
+        vault().status()
+    })
+}
+
+/// 立即锁定保险库
+#[tauri::command]
+pub async fn lock_vault(app: AppHandle) -> Result<VaultStatus, String> {
+    crate::log_async_command!("lock_vault", async {
+        vault().lock();
+        let _ = app.emit("vault-locked", ());
+        vault().status()
+    })
+}
+
+/// 启用主密码保险库（转换现有机器绑定备份）
+#[tauri::command]
+pub async fn enable_vault_mode(password: String) -> Result<String, String> {
+    crate::log_async_command!("enable_vault_mode", async {
+        let converted = vault().enable_password_mode(&password)?;
+        Ok(format!("已启用主密码保险库，转换 {} 个备份", converted))
+    })
+}
+
+/// 关闭主密码保险库（转换回机器绑定加密）
+#[tauri::command]
+pub async fn disable_vault_mode(app: AppHandle, password: String) -> Result<String, String> {
+    crate::log_async_command!("disable_vault_mode", async {
+        let converted = vault().disable_password_mode(&password)?;
+        let _ = app.emit("vault-locked", ());
+        Ok(format!("已关闭主密码保险库，转换 {} 个备份", converted))
+    })
+}
+
+/// 设置保险库空闲自动锁定时间（秒，0 表示不自动锁定）
+#[tauri::command]
+pub async fn set_vault_idle_timeout(seconds: u64) -> Result<VaultStatus, String> {
+    crate::log_async_command!("set_vault_idle_timeout", async {
+        vault().set_idle_timeout(seconds)?;
+        vault().status()
+    })
+}
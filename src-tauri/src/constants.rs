@@ -1,4 +1,6 @@
 /// 数据库字段常量
+///
+/// 备份、恢复与注销涉及的字段列表由字段定义（`antigravity::key_sets`）决定。
 pub mod database {
     /// 认证状态
     pub const AUTH_STATUS: &str = "antigravityAuthStatus";
@@ -29,28 +31,6 @@ pub mod database {
 
     /// 目标存储标记
     pub const TARGET_STORAGE_MARKER: &str = "__$__targetStorageMarker";
-
-    /// 所有需要备份的字段列表（包含设备指纹）
-    pub const ALL_KEYS: &[&str] = &[
-        AUTH_STATUS,
-        PROFILE_URL,
-        USER_SETTINGS,
-        ONBOARDING,
-        GOOGLE_DATA,
-        COMMAND_CONFIGS,
-        AGENT_STATE,
-        CHAT_SESSION,
-        NEW_STORAGE_MARKER,
-    ];
-
-    /// 需要清除的字段列表（不包含会话数据和设备指纹）
-    pub const DELETE_KEYS: &[&str] = &[
-        AUTH_STATUS,
-        PROFILE_URL,
-        USER_SETTINGS,
-        ONBOARDING,
-        COMMAND_CONFIGS,
-    ];
 }
 
 /// 路径常量
@@ -4,7 +4,7 @@ use serde::Serialize;
 use serde_json::Value;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use tokio::time::{interval, Duration};
 use tracing::{error, info, warn};
 
@@ -16,6 +16,30 @@ pub struct DataDiff {
     pub summary: String,
 }
 
+// 账户后续处理任务（记录配额历史并重新检测活动账户）
+struct AccountJob {
+    quota_auth: Option<Value>,
+}
+
+/// 实时登录状态：是否已登录以及会话令牌指纹（取自未脱敏的认证信息）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct LiveSession {
+    logged_in: bool,
+    fingerprints: (Option<String>, Option<String>),
+}
+
+impl LiveSession {
+    fn from_auth(auth: Option<&Value>) -> Self {
+        match auth {
+            Some(auth) => Self {
+                logged_in: true,
+                fingerprints: crate::antigravity::credentials::session_fingerprints(auth),
+            },
+            None => Self::default(),
+        }
+    }
+}
+
 // 数据库监控器
 pub struct DatabaseMonitor {
     app_handle: AppHandle,
@@ -44,8 +68,11 @@ impl DatabaseMonitor {
         // 标记监控为运行状态
         *is_running.lock().await = true;
 
+        let account_jobs = Self::spawn_account_worker(app_handle.clone());
+
         tokio::spawn(async move {
             let mut interval = interval(Duration::from_secs(3)); // 3秒间隔，更敏感
+            let mut last_session: Option<LiveSession> = None;
 
             loop {
                 interval.tick().await;
@@ -60,7 +87,10 @@ impl DatabaseMonitor {
 
                 // 获取当前完整数据
                 match Self::get_complete_data().await {
-                    Ok(new_data) => {
+                    Ok((new_data, session)) => {
+                        // 释放锁后执行的后续处理
+                        let mut refresh_account = false;
+                        let mut quota_auth: Option<Value> = None;
                         let mut last = last_data.lock().await;
 
                         // 检查是否有数据变化
@@ -71,10 +101,10 @@ impl DatabaseMonitor {
                             if diff.has_changes {
                                 info!("📢 检测到数据库变化: {}", diff.summary);
 
-                                // 构建简化的事件数据：newData, oldData, diff
+                                // 构建简化的事件数据：newData, oldData, diff（值均已脱敏）
                                 let event_data = serde_json::json!({
-                                    "newData": new_data,
-                                    "oldData": old_data,
+                                    "newData": Self::redact_data(&new_data),
+                                    "oldData": Self::redact_data(old_data),
                                     "diff": diff
                                 });
 
@@ -84,10 +114,29 @@ impl DatabaseMonitor {
                                 } else {
                                     info!("✅ 数据库变化事件推送成功");
                                 }
+
+                                // 认证信息变化时记录配额历史
+                                if Self::auth_status_changed(&diff) {
+                                    refresh_account = true;
+                                    quota_auth = new_data.get(crate::constants::database::AUTH_STATUS).cloned();
+                                }
                             }
                         }
 
+                        // 登录状态或令牌变化（包括登出、仅令牌不同的重新登录）时重新检测活动账户；
+                        // 首次读取时 last_session 为空，同样会检测一次
+                        if last_session.as_ref() != Some(&session) {
+                            refresh_account = true;
+                            last_session = Some(session);
+                        }
+
                         *last = Some(new_data);
+                        drop(last);
+
+                        // 后续处理交给单独的工作任务，轮询不等待其完成
+                        if refresh_account && account_jobs.send(AccountJob { quota_auth }).is_err() {
+                            error!("❌ 账户后续处理任务已退出");
+                        }
                     }
                     Err(e) => {
                         warn!("⚠️ 获取完整数据失败: {}", e);
@@ -99,14 +148,94 @@ impl DatabaseMonitor {
         Ok(())
     }
 
+    /// 启动账户后续处理的工作任务
+    ///
+    /// 配额历史写入与索引解密是阻塞操作，由单个工作任务按顺序执行，
+    /// 积压的任务合并为一次：配额样本逐个记录，活动账户只按最新状态检测一次，
+    /// 因此不会出现旧结果覆盖新结果的情况。
+    fn spawn_account_worker(app_handle: AppHandle) -> mpsc::UnboundedSender<AccountJob> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<AccountJob>();
+
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                let mut quota_auths: Vec<Value> = job.quota_auth.into_iter().collect();
+                while let Ok(job) = receiver.try_recv() {
+                    quota_auths.extend(job.quota_auth);
+                }
+
+                let app_handle = app_handle.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    for auth in &quota_auths {
+                        Self::record_quota_history(&app_handle, auth);
+                    }
+                    Self::refresh_active_account(&app_handle);
+                })
+                .await;
+                if let Err(e) = result {
+                    error!("❌ 账户后续处理任务失败: {}", e);
+                }
+            }
+        });
+
+        sender
+    }
+
     /// 停止数据库监控
     pub async fn stop_monitoring(&self) {
         info!("⏹️ 停止数据库自动监控");
         *self.is_running.lock().await = false;
     }
 
+    /// 推送到前端前对每个值脱敏（令牌、扩展密钥、不透明长串等不离开 Rust）
+    fn redact_data(data: &Value) -> Value {
+        match data {
+            Value::Object(obj) => Value::Object(
+                obj.iter()
+                    .map(|(key, value)| {
+                        let value = crate::antigravity::switch_preview::redact_entry(key, value.clone());
+                        (key.clone(), value)
+                    })
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// 差异中是否包含认证信息的变化
+    fn auth_status_changed(diff: &DataDiff) -> bool {
+        let prefix = format!("{}:", crate::constants::database::AUTH_STATUS);
+        diff.changed_fields
+            .iter()
+            .any(|field| field.starts_with(&prefix) && !field.ends_with("removed"))
+    }
+
+    /// 记录当前账户的配额样本（账户尚未备份或保险库未解锁时跳过）
+    fn record_quota_history(app_handle: &AppHandle, auth: &Value) {
+        use crate::antigravity::quota_history::{self, SampleSource};
+
+        match quota_history::record_for_current_auth(auth, SampleSource::Monitor) {
+            Ok(Some((handle, inserted))) if inserted > 0 => {
+                let payload = serde_json::json!({ "handle": handle, "inserted": inserted });
+                if let Err(e) = app_handle.emit("quota-history-updated", &payload) {
+                    error!("❌ 推送配额历史事件失败: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("⚠️ 记录配额历史失败: {}", e),
+        }
+    }
+
+    /// 重新检测活动账户（保险库锁定等情况下跳过）
+    fn refresh_active_account(app_handle: &AppHandle) {
+        if let Err(e) = crate::antigravity::active_account::refresh(app_handle) {
+            tracing::debug!(target: "account::active", error = %e, "活动账户检测跳过");
+        }
+    }
+
     /// 获取完整数据库数据
-    async fn get_complete_data() -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+    ///
+    /// 返回脱敏后的数据，以及脱敏前从认证信息中得到的登录状态
+    async fn get_complete_data() -> Result<(Value, LiveSession), Box<dyn std::error::Error + Send + Sync>> {
         // 检测数据库路径
         let db_path = if cfg!(windows) {
             dirs::home_dir()
@@ -127,6 +256,7 @@ impl DatabaseMonitor {
         };
 
         let mut complete_data = serde_json::Map::new();
+        let mut session = LiveSession::default();
 
         if db_path.exists() {
             let conn = rusqlite::Connection::open(&db_path)?;
@@ -146,11 +276,19 @@ impl DatabaseMonitor {
                     Err(_) => Value::String(value.clone()),
                 };
 
+                // 认证信息中的令牌不随事件推送到前端
+                let json_value = if key == crate::constants::database::AUTH_STATUS {
+                    session = LiveSession::from_auth(Some(&json_value));
+                    crate::antigravity::credentials::redact_auth_status(json_value)
+                } else {
+                    json_value
+                };
+
                 complete_data.insert(key, json_value);
             }
         }
 
-        Ok(Value::Object(complete_data))
+        Ok((Value::Object(complete_data), session))
     }
 
     /// 分析两个数据之间的差异
@@ -209,3 +347,52 @@ impl DatabaseMonitor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antigravity::credentials::redact_auth_status;
+    use serde_json::json;
+
+    #[test]
+    fn test_event_data_is_redacted() {
+        let opaque = "A".repeat(64);
+        let data = json!({
+            "secret://{\"extensionId\":\"x\"}": "{\"data\":[1,2,3]}",
+            "jetskiStateSync.agentManagerInitState": opaque,
+            "antigravityAuthStatus": {"email": "a@b.c", "apiKey": "ya29.token"},
+            "nested": {"refresh_token": "r", "plain": "value"},
+            "theme": "dark"
+        });
+
+        let value = DatabaseMonitor::redact_data(&data);
+        assert_eq!(value["secret://{\"extensionId\":\"x\"}"], "[REDACTED]");
+        let redacted = value.to_string();
+        assert!(!redacted.contains(&opaque));
+        assert!(!redacted.contains("ya29.token"));
+        assert!(!redacted.contains("\"r\""));
+        assert!(redacted.contains("a@b.c"));
+        assert!(redacted.contains("\"theme\":\"dark\""));
+        assert!(redacted.contains("\"plain\":\"value\""));
+    }
+
+    #[test]
+    fn test_live_session_tracks_logout_and_token_only_changes() {
+        let auth = json!({"email": "a@b.c", "apiKey": "ya29.first"});
+        let relogin = json!({"email": "a@b.c", "apiKey": "ya29.second"});
+
+        let session = LiveSession::from_auth(Some(&auth));
+        assert!(session.logged_in);
+        assert!(session.fingerprints.0.is_some());
+        assert_eq!(session, LiveSession::from_auth(Some(&auth)));
+
+        // 仅令牌不同（脱敏后数据完全一致）也视为变化
+        assert_eq!(redact_auth_status(auth.clone()), redact_auth_status(relogin.clone()));
+        assert_ne!(session, LiveSession::from_auth(Some(&relogin)));
+
+        // 登出（认证信息被删除）
+        let logged_out = LiveSession::from_auth(None);
+        assert!(!logged_out.logged_in);
+        assert_ne!(session, logged_out);
+    }
+}
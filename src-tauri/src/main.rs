@@ -11,6 +11,7 @@ use tracing_subscriber::prelude::*;
 // Modules
 mod antigravity;
 mod app_settings;
+mod cloudcode;
 mod config_manager;
 mod constants;
 mod platform;
@@ -20,7 +21,9 @@ mod window;
 
 mod commands;
 mod db_monitor;
+mod operations;
 mod path_utils;
+mod process_monitor;
 mod setup;
 mod state;
 
@@ -121,6 +124,11 @@ fn main() {
             restore_backup_files,
             delete_backup,
             clear_all_backups,
+            list_backup_snapshots,
+            delete_backup_snapshot,
+            restore_backup_snapshot,
+            collect_backup_garbage,
+            get_backup_store_stats,
             // Antigravity 相关命令
             switch_antigravity_account,
             get_antigravity_accounts,
@@ -128,7 +136,32 @@ fn main() {
             backup_antigravity_current_account,
             restore_antigravity_account,
             switch_to_antigravity_account,
+            get_active_account_status,
+            undo_last_switch,
+            list_switch_undo_stack,
+            preview_switch,
+            list_key_groups,
+            get_account_restore_groups,
+            set_account_restore_groups,
+            get_key_sets,
+            get_builtin_key_sets,
+            save_key_sets,
+            reload_key_sets,
+            list_db_snapshots,
+            create_db_snapshot,
+            rollback_db_snapshot,
+            delete_db_snapshot,
+            check_db_health,
+            vacuum_db,
+            list_db_keys,
+            get_db_value,
+            check_db_consistency,
+            sync_db_files,
+            compare_account_backups,
             clear_all_antigravity_data,
+            preview_logout,
+            inspect_account_secrets,
+            logout_antigravity,
             get_user_status_from_db,
             // 进程管理命令
             kill_antigravity,
@@ -136,6 +169,12 @@ fn main() {
             list_antigravity_processes,
             start_antigravity,
             backup_and_restart_antigravity,
+            verify_antigravity_launch,
+            get_last_launch_verification,
+            get_last_launch_output,
+            // 操作队列命令
+            list_operations,
+            cancel_operation,
             // 平台支持命令
             get_platform_info,
             find_antigravity_installations,
@@ -155,6 +194,7 @@ fn main() {
             update_tray_menu_command,
             is_silent_start_enabled,
             save_silent_start_state,
+            save_secret_restore_policy,
             get_all_settings,
             // 数据库监控命令
             is_database_monitoring_running,
@@ -166,6 +206,21 @@ fn main() {
             encrypt_config_data,
             write_text_file,
             write_frontend_log,
+            // 备份保险库命令
+            get_vault_status,
+            unlock_vault,
+            lock_vault,
+            enable_vault_mode,
+            disable_vault_mode,
+            set_vault_idle_timeout,
+            // CloudCode 接口命令（令牌仅在 Rust 侧使用）
+            fetch_account_models,
+            fetch_account_quota,
+            get_cloudcode_settings,
+            set_cloudcode_base_url,
+            // 配额历史命令
+            get_quota_history,
+            get_quota_reset_predictions,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
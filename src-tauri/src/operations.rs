@@ -0,0 +1,781 @@
+//! 操作协调模块
+//! 托盘、主界面与 backup_and_restart_antigravity 都可能同时关闭进程、写入数据库、启动进程，
+//! 所有会修改账户数据的操作（切换、恢复、注销、备份、导入、数据库维护）都经此串行执行。
+//!
+//! - 操作按提交顺序排队执行（tokio Mutex 按等待顺序唤醒）
+//! - 每个操作分配 ID，排队中的操作可以取消
+//! - 同一操作（类型与目标相同）已在队列中或队列已满时，直接返回以 `操作繁忙` 开头的错误
+//! - 队列变化时向前端发送 `operations-changed` 事件（当前运行与排队中的操作列表）
+//! - 分步执行的操作（`run_steps`）逐步发送 `operation-progress` 事件，执行中也可请求取消，
+//!   取消在步骤之间生效，由操作自身回滚已完成的步骤
+//! - 事件经 `OperationEvents` 发送，应用中发往前端，队列本身不依赖 AppHandle
+
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::oneshot;
+
+/// 队列变化事件名
+pub const OPERATIONS_CHANGED_EVENT: &str = "operations-changed";
+
+/// 步骤进度事件名
+pub const OPERATION_PROGRESS_EVENT: &str = "operation-progress";
+
+/// 队列繁忙时错误信息的前缀
+pub const BUSY_ERROR_PREFIX: &str = "操作繁忙";
+
+/// 操作被取消时错误信息的前缀
+pub const CANCELLED_ERROR_PREFIX: &str = "操作已取消";
+
+/// 最多排队的操作数量（不含正在执行的操作）
+const MAX_PENDING: usize = 4;
+
+/// 操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Switch,
+    Restore,
+    Logout,
+    Backup,
+    Import,
+    /// 删除单个或全部账户备份
+    Delete,
+    /// 备份当前账户并重启 Antigravity
+    Restart,
+    /// 数据库回滚、整理、同步等维护操作
+    Maintenance,
+}
+
+impl OperationKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            OperationKind::Switch => "切换账户",
+            OperationKind::Restore => "恢复账户",
+            OperationKind::Logout => "注销",
+            OperationKind::Backup => "备份账户",
+            OperationKind::Import => "导入备份",
+            OperationKind::Delete => "删除备份",
+            OperationKind::Restart => "备份并重启",
+            OperationKind::Maintenance => "数据库维护",
+        }
+    }
+}
+
+/// 操作状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Pending,
+    Running,
+}
+
+/// 操作信息
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    pub kind: OperationKind,
+    /// 操作对象（如账户邮箱）
+    pub target: Option<String>,
+    pub status: OperationStatus,
+    /// 提交时间（RFC3339）
+    pub queued_at: String,
+    /// 开始执行时间（RFC3339）
+    pub started_at: Option<String>,
+    /// 执行中的操作已请求取消（将在当前步骤结束后生效）
+    pub cancel_requested: bool,
+}
+
+impl OperationInfo {
+    fn describe(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{} {}（{}）", self.kind.label(), target, self.id),
+            None => format!("{}（{}）", self.kind.label(), self.id),
+        }
+    }
+
+    fn same_as(&self, kind: OperationKind, target: Option<&str>) -> bool {
+        self.kind == kind && self.target.as_deref() == target
+    }
+}
+
+/// 步骤状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Running,
+    Succeeded,
+    Failed,
+    /// 在此步骤之前响应了取消
+    Cancelled,
+    /// 已回滚之前完成的步骤
+    RolledBack,
+}
+
+/// 步骤进度事件
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationProgress {
+    pub operation_id: String,
+    pub kind: OperationKind,
+    /// 步骤名称
+    pub step: String,
+    /// 步骤序号（从 1 开始）
+    pub index: usize,
+    pub total: usize,
+    pub status: StepStatus,
+    /// 操作开始至今的耗时（毫秒）
+    pub elapsed_ms: u64,
+    /// 当前步骤的耗时（毫秒）
+    pub step_elapsed_ms: u64,
+    /// 步骤结果或错误信息
+    pub message: Option<String>,
+}
+
+/// 队列与步骤进度事件的接收方
+pub trait OperationEvents: Send + Sync {
+    /// 队列变化（当前运行与排队中的操作）
+    fn operations_changed(&self, operations: Vec<OperationInfo>);
+    /// 步骤进度
+    fn progress(&self, progress: OperationProgress);
+}
+
+/// 应用中以 Tauri 事件发往前端
+impl OperationEvents for AppHandle {
+    fn operations_changed(&self, operations: Vec<OperationInfo>) {
+        if let Err(e) = self.emit(OPERATIONS_CHANGED_EVENT, operations) {
+            tracing::warn!(target: "operations", error = %e, "发送队列变化事件失败");
+        }
+    }
+
+    fn progress(&self, progress: OperationProgress) {
+        if let Err(e) = self.emit(OPERATION_PROGRESS_EVENT, progress) {
+            tracing::warn!(target: "operations", error = %e, "发送步骤进度事件失败");
+        }
+    }
+}
+
+/// 执行中操作的上下文：发送步骤进度，并在步骤之间响应取消
+pub struct OperationContext {
+    events: Arc<dyn OperationEvents>,
+    info: OperationInfo,
+    cancel: Arc<AtomicBool>,
+    started: Instant,
+    index: usize,
+    total: usize,
+}
+
+impl OperationContext {
+    /// 设置步骤总数
+    pub fn set_total(&mut self, total: usize) {
+        self.total = total;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// 步骤之间的取消检查点：已请求取消时发送取消事件并返回取消错误
+    pub fn checkpoint(&self) -> Result<(), String> {
+        if !self.is_cancelled() {
+            return Ok(());
+        }
+        tracing::info!(target: "operations", id = %self.info.id, step = self.index, "操作在步骤之间响应取消");
+        self.emit("取消", StepStatus::Cancelled, 0, None);
+        Err(format!("{}: {}", CANCELLED_ERROR_PREFIX, self.info.describe()))
+    }
+
+    /// 执行一个步骤，开始与结束时各发送一次进度事件
+    pub async fn step<Fut>(&mut self, name: &str, step: Fut) -> Result<String, String>
+    where
+        Fut: Future<Output = Result<String, String>>,
+    {
+        self.index += 1;
+        self.emit(name, StepStatus::Running, 0, None);
+        let started = Instant::now();
+        let result = step.await;
+        let step_elapsed = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(message) => self.emit(name, StepStatus::Succeeded, step_elapsed, Some(message.clone())),
+            Err(e) => {
+                tracing::warn!(target: "operations", id = %self.info.id, step = name, error = %e, "操作步骤失败");
+                self.emit(name, StepStatus::Failed, step_elapsed, Some(e.clone()))
+            }
+        }
+        result
+    }
+
+    /// 回滚已完成的步骤（不计入步骤总数，也不响应取消）
+    pub async fn rollback<Fut>(&self, rollback: Fut) -> Result<String, String>
+    where
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let started = Instant::now();
+        let result = rollback.await;
+        let step_elapsed = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(message) => self.emit("回滚", StepStatus::RolledBack, step_elapsed, Some(message.clone())),
+            Err(e) => self.emit("回滚", StepStatus::Failed, step_elapsed, Some(e.clone())),
+        }
+        result
+    }
+
+    fn emit(&self, step: &str, status: StepStatus, step_elapsed_ms: u64, message: Option<String>) {
+        let progress = OperationProgress {
+            operation_id: self.info.id.clone(),
+            kind: self.info.kind,
+            step: step.to_string(),
+            index: self.index,
+            total: self.total.max(self.index),
+            status,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            step_elapsed_ms,
+            message,
+        };
+        self.events.progress(progress);
+    }
+}
+
+struct RunningOperation {
+    info: OperationInfo,
+    /// 支持执行中取消的操作才有取消标记
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: Option<RunningOperation>,
+    pending: Vec<(OperationInfo, oneshot::Sender<()>)>,
+}
+
+impl QueueState {
+    fn snapshot(&self) -> Vec<OperationInfo> {
+        self.running
+            .iter()
+            .map(|running| running.info.clone())
+            .chain(self.pending.iter().map(|(info, _)| info.clone()))
+            .collect()
+    }
+}
+
+/// 操作协调器
+pub struct OperationCoordinator {
+    events: Arc<dyn OperationEvents>,
+    /// 执行权（tokio Mutex 按等待顺序获取，保证先提交先执行）
+    slot: tokio::sync::Mutex<()>,
+    state: Mutex<QueueState>,
+    next_id: AtomicU64,
+}
+
+impl OperationCoordinator {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self::with_events(Arc::new(app_handle))
+    }
+
+    /// 使用指定的事件接收方创建
+    pub fn with_events(events: Arc<dyn OperationEvents>) -> Self {
+        Self {
+            events,
+            slot: tokio::sync::Mutex::new(()),
+            state: Mutex::new(QueueState::default()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// 当前运行与排队中的操作（运行中的在前）
+    pub fn list(&self) -> Vec<OperationInfo> {
+        self.state.lock().unwrap().snapshot()
+    }
+
+    fn notify(&self, operations: Vec<OperationInfo>) {
+        self.events.operations_changed(operations);
+    }
+
+    /// 提交操作并等待执行结果
+    ///
+    /// 同一操作已在队列中或队列已满时立即返回繁忙错误；排队期间被取消时返回取消错误。
+    pub async fn run<T, F>(&self, kind: OperationKind, target: Option<&str>, operation: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        self.execute(kind, target, None, |_| operation).await
+    }
+
+    /// 提交分步执行的操作：操作通过上下文发送步骤进度，执行中也可被取消
+    pub async fn run_steps<T, F, Fut>(&self, kind: OperationKind, target: Option<&str>, operation: F) -> Result<T, String>
+    where
+        F: FnOnce(OperationContext) -> Fut,
+        Fut: Future<Output = Result<T, String>>,
+    {
+        self.execute(kind, target, Some(Arc::new(AtomicBool::new(false))), operation).await
+    }
+
+    async fn execute<T, F, Fut>(
+        &self,
+        kind: OperationKind,
+        target: Option<&str>,
+        cancel: Option<Arc<AtomicBool>>,
+        operation: F,
+    ) -> Result<T, String>
+    where
+        F: FnOnce(OperationContext) -> Fut,
+        Fut: Future<Output = Result<T, String>>,
+    {
+        let (info, cancelled) = self.enqueue(kind, target)?;
+        let id = info.id.clone();
+
+        // 排队期间被丢弃（如调用方超时）时从队列中移除
+        let pending = PendingGuard { coordinator: self, id: &id };
+        let slot = tokio::select! {
+            guard = self.slot.lock() => guard,
+            _ = cancelled => {
+                tracing::info!(target: "operations", id = %id, "排队中的操作已取消");
+                return Err(format!("{}: {}", CANCELLED_ERROR_PREFIX, info.describe()));
+            }
+        };
+
+        let info = OperationInfo {
+            status: OperationStatus::Running,
+            started_at: Some(chrono::Local::now().to_rfc3339()),
+            ..info
+        };
+        let operations = {
+            let mut state = self.state.lock().unwrap();
+            state.pending.retain(|(pending, _)| pending.id != id);
+            state.running = Some(RunningOperation { info: info.clone(), cancel: cancel.clone() });
+            state.snapshot()
+        };
+        // 已移出排队列表，守卫不再需要处理
+        drop(pending);
+        self.notify(operations);
+        tracing::info!(target: "operations", id = %id, kind = ?kind, "开始执行操作");
+
+        // 操作结束（包括被中途丢弃）时释放执行权并更新队列
+        let _finish = FinishGuard { coordinator: self, _slot: slot };
+        let context = OperationContext {
+            events: self.events.clone(),
+            info,
+            cancel: cancel.unwrap_or_default(),
+            started: Instant::now(),
+            index: 0,
+            total: 0,
+        };
+        operation(context).await
+    }
+
+    fn enqueue(
+        &self,
+        kind: OperationKind,
+        target: Option<&str>,
+    ) -> Result<(OperationInfo, oneshot::Receiver<()>), String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(existing) = state
+            .running
+            .iter()
+            .map(|running| &running.info)
+            .chain(state.pending.iter().map(|(info, _)| info))
+            .find(|info| info.same_as(kind, target))
+        {
+            return Err(format!("{}: {} 已在执行或排队中", BUSY_ERROR_PREFIX, existing.describe()));
+        }
+        if state.pending.len() >= MAX_PENDING {
+            let running = state.running.as_ref().map(|running| running.info.describe()).unwrap_or_default();
+            return Err(format!("{}: 排队的操作过多，请等待 {} 完成后再试", BUSY_ERROR_PREFIX, running));
+        }
+
+        let info = OperationInfo {
+            id: format!("op-{}", self.next_id.fetch_add(1, Ordering::Relaxed)),
+            kind,
+            target: target.map(|t| t.to_string()),
+            status: OperationStatus::Pending,
+            queued_at: chrono::Local::now().to_rfc3339(),
+            started_at: None,
+            cancel_requested: false,
+        };
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        state.pending.push((info.clone(), cancel_tx));
+        let operations = state.snapshot();
+        drop(state);
+
+        tracing::debug!(target: "operations", id = %info.id, kind = ?kind, "操作已加入队列");
+        self.notify(operations);
+        Ok((info, cancel_rx))
+    }
+
+    /// 取消操作
+    ///
+    /// 排队中的操作立即取消；分步执行的操作在当前步骤结束后取消并回滚，其余已开始执行的操作无法取消。
+    pub fn cancel(&self, id: &str) -> Result<OperationInfo, String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(running) = state.running.as_mut().filter(|running| running.info.id == id) {
+            let Some(cancel) = &running.cancel else {
+                return Err("操作已开始执行，无法取消".to_string());
+            };
+            cancel.store(true, Ordering::SeqCst);
+            running.info.cancel_requested = true;
+            let info = running.info.clone();
+            let operations = state.snapshot();
+            drop(state);
+
+            tracing::info!(target: "operations", id = %id, "已请求取消执行中的操作，将在当前步骤结束后生效");
+            self.notify(operations);
+            return Ok(info);
+        }
+        let position = state
+            .pending
+            .iter()
+            .position(|(info, _)| info.id == id)
+            .ok_or_else(|| format!("操作不存在或已完成: {}", id))?;
+        let (info, cancel_tx) = state.pending.remove(position);
+        let operations = state.snapshot();
+        drop(state);
+
+        let _ = cancel_tx.send(());
+        self.notify(operations);
+        Ok(info)
+    }
+}
+
+struct PendingGuard<'a> {
+    coordinator: &'a OperationCoordinator,
+    id: &'a str,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let operations = {
+            let mut state = self.coordinator.state.lock().unwrap();
+            let before = state.pending.len();
+            state.pending.retain(|(pending, _)| pending.id != self.id);
+            if state.pending.len() == before {
+                return;
+            }
+            state.snapshot()
+        };
+        tracing::info!(target: "operations", id = %self.id, "排队中的操作已被放弃");
+        self.coordinator.notify(operations);
+    }
+}
+
+struct FinishGuard<'a> {
+    coordinator: &'a OperationCoordinator,
+    _slot: tokio::sync::MutexGuard<'a, ()>,
+}
+
+impl Drop for FinishGuard<'_> {
+    fn drop(&mut self) {
+        let operations = {
+            let mut state = self.coordinator.state.lock().unwrap();
+            if let Some(running) = state.running.take() {
+                tracing::info!(target: "operations", id = %running.info.id, "操作执行结束");
+            }
+            state.snapshot()
+        };
+        self.coordinator.notify(operations);
+    }
+}
+
+/// 通过应用的操作协调器串行执行操作
+pub async fn run<T, F>(app: &AppHandle, kind: OperationKind, target: Option<&str>, operation: F) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    app.state::<OperationCoordinator>().run(kind, target, operation).await
+}
+
+/// 通过应用的操作协调器分步执行操作
+pub async fn run_steps<T, F, Fut>(app: &AppHandle, kind: OperationKind, target: Option<&str>, operation: F) -> Result<T, String>
+where
+    F: FnOnce(OperationContext) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    app.state::<OperationCoordinator>().run_steps(kind, target, operation).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// 记录收到的事件
+    #[derive(Default)]
+    struct RecordedEvents {
+        changes: Mutex<Vec<Vec<OperationInfo>>>,
+        progress: Mutex<Vec<OperationProgress>>,
+    }
+
+    impl OperationEvents for RecordedEvents {
+        fn operations_changed(&self, operations: Vec<OperationInfo>) {
+            self.changes.lock().unwrap().push(operations);
+        }
+
+        fn progress(&self, progress: OperationProgress) {
+            self.progress.lock().unwrap().push(progress);
+        }
+    }
+
+    fn coordinator() -> (Arc<OperationCoordinator>, Arc<RecordedEvents>) {
+        let events = Arc::new(RecordedEvents::default());
+        (Arc::new(OperationCoordinator::with_events(events.clone())), events)
+    }
+
+    /// 等待队列达到预期长度
+    async fn wait_for_len(coordinator: &OperationCoordinator, len: usize) {
+        for _ in 0..200 {
+            if coordinator.list().len() == len {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("队列长度未达到 {}: {:?}", len, coordinator.list());
+    }
+
+    /// 提交一个等待放行信号的操作，返回放行发送端与任务句柄
+    fn spawn_blocked(
+        coordinator: &Arc<OperationCoordinator>,
+        kind: OperationKind,
+        target: &str,
+        order: &Arc<Mutex<Vec<String>>>,
+    ) -> (oneshot::Sender<()>, tokio::task::JoinHandle<Result<String, String>>) {
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+        let coordinator = coordinator.clone();
+        let order = order.clone();
+        let target = target.to_string();
+        let handle = tokio::spawn(async move {
+            coordinator
+                .run(kind, Some(&target.clone()), async move {
+                    order.lock().unwrap().push(target.clone());
+                    let _ = release_rx.await;
+                    Ok(target)
+                })
+                .await
+        });
+        (release_tx, handle)
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_submission_order() {
+        let (coordinator, events) = coordinator();
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let mut releases = Vec::new();
+        let mut handles = Vec::new();
+        for (i, target) in ["a", "b", "c"].iter().enumerate() {
+            let (release, handle) = spawn_blocked(&coordinator, OperationKind::Switch, target, &order);
+            wait_for_len(&coordinator, i + 1).await;
+            releases.push(release);
+            handles.push(handle);
+        }
+
+        let listed = coordinator.list();
+        assert_eq!(listed[0].status, OperationStatus::Running);
+        assert!(listed[1..].iter().all(|info| info.status == OperationStatus::Pending));
+        assert_eq!(listed[1].target.as_deref(), Some("b"));
+
+        for (release, handle) in releases.into_iter().zip(handles) {
+            release.send(()).unwrap();
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["a", "b", "c"]);
+        assert!(coordinator.list().is_empty());
+        assert!(events.changes.lock().unwrap().last().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_busy_errors() {
+        let (coordinator, _) = coordinator();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (release, running) = spawn_blocked(&coordinator, OperationKind::Switch, "a", &order);
+        wait_for_len(&coordinator, 1).await;
+
+        // 同一操作已在执行
+        let err = coordinator.run(OperationKind::Switch, Some("a"), async { Ok(()) }).await.unwrap_err();
+        assert!(err.starts_with(BUSY_ERROR_PREFIX), "{}", err);
+
+        // 队列已满
+        let mut queued = Vec::new();
+        for i in 0..MAX_PENDING {
+            queued.push(spawn_blocked(&coordinator, OperationKind::Restore, &format!("q{}", i), &order));
+            wait_for_len(&coordinator, i + 2).await;
+        }
+        let err = coordinator.run(OperationKind::Backup, None, async { Ok(()) }).await.unwrap_err();
+        assert!(err.starts_with(BUSY_ERROR_PREFIX), "{}", err);
+        assert_eq!(coordinator.list().len(), MAX_PENDING + 1);
+
+        release.send(()).unwrap();
+        running.await.unwrap().unwrap();
+        for (release, handle) in queued {
+            release.send(()).unwrap();
+            handle.await.unwrap().unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_pending_operation() {
+        let (coordinator, _) = coordinator();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (release, running) = spawn_blocked(&coordinator, OperationKind::Switch, "a", &order);
+        wait_for_len(&coordinator, 1).await;
+        let (_pending_release, pending) = spawn_blocked(&coordinator, OperationKind::Switch, "b", &order);
+        wait_for_len(&coordinator, 2).await;
+
+        // 不支持分步取消的操作开始执行后无法取消
+        let running_id = coordinator.list()[0].id.clone();
+        assert!(coordinator.cancel(&running_id).is_err());
+
+        let pending_id = coordinator.list()[1].id.clone();
+        let cancelled = coordinator.cancel(&pending_id).unwrap();
+        assert_eq!(cancelled.target.as_deref(), Some("b"));
+        let err = pending.await.unwrap().unwrap_err();
+        assert!(err.starts_with(CANCELLED_ERROR_PREFIX), "{}", err);
+        assert!(coordinator.cancel(&pending_id).is_err());
+        assert_eq!(coordinator.list().len(), 1);
+
+        release.send(()).unwrap();
+        running.await.unwrap().unwrap();
+        assert_eq!(*order.lock().unwrap(), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_futures_release_queue() {
+        let (coordinator, _) = coordinator();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let (_release, running) = spawn_blocked(&coordinator, OperationKind::Switch, "a", &order);
+        wait_for_len(&coordinator, 1).await;
+        let (_pending_release, pending) = spawn_blocked(&coordinator, OperationKind::Switch, "b", &order);
+        wait_for_len(&coordinator, 2).await;
+
+        // 排队中被丢弃：移出队列
+        pending.abort();
+        wait_for_len(&coordinator, 1).await;
+        assert_eq!(coordinator.list()[0].target.as_deref(), Some("a"));
+
+        // 执行中被丢弃：释放执行权
+        running.abort();
+        wait_for_len(&coordinator, 0).await;
+        let result = tokio::time::timeout(
+            Duration::from_secs(1),
+            coordinator.run(OperationKind::Switch, Some("b"), async { Ok("done") }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.unwrap(), "done");
+        assert_eq!(*order.lock().unwrap(), vec!["a"]);
+    }
+
+    #[tokio::test]
+    async fn test_step_progress_events() {
+        let (coordinator, events) = coordinator();
+        let result = coordinator
+            .run_steps(OperationKind::Switch, Some("a"), |mut context| async move {
+                context.set_total(2);
+                context.step("一", async { Ok("ok".to_string()) }).await?;
+                context.step("二", async { Err::<String, _>("失败".to_string()) }).await
+            })
+            .await;
+        assert_eq!(result.unwrap_err(), "失败");
+
+        let progress = events.progress.lock().unwrap();
+        let statuses: Vec<(usize, StepStatus)> = progress.iter().map(|p| (p.index, p.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (1, StepStatus::Running),
+                (1, StepStatus::Succeeded),
+                (2, StepStatus::Running),
+                (2, StepStatus::Failed),
+            ]
+        );
+        assert!(progress.iter().all(|p| p.total == 2 && p.kind == OperationKind::Switch));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_between_each_step() {
+        const STEPS: usize = 3;
+
+        for cancel_after in 0..=STEPS {
+            let (coordinator, events) = coordinator();
+            let executed = Arc::new(Mutex::new(Vec::new()));
+            let rolled_back = Arc::new(AtomicBool::new(false));
+
+            let result = {
+                let coordinator_ref = coordinator.clone();
+                let executed = executed.clone();
+                let rolled_back = rolled_back.clone();
+                coordinator
+                    .run_steps(OperationKind::Switch, Some("a"), |mut context| async move {
+                        context.set_total(STEPS);
+                        let result = async {
+                            for step in 1..=STEPS {
+                                context.checkpoint()?;
+                                context
+                                    .step(&format!("步骤{}", step), async {
+                                        executed.lock().unwrap().push(step);
+                                        // 在第 cancel_after 步执行期间请求取消
+                                        if step == cancel_after {
+                                            let id = coordinator_ref.list()[0].id.clone();
+                                            let info = coordinator_ref.cancel(&id).unwrap();
+                                            assert!(info.cancel_requested);
+                                        }
+                                        Ok(format!("完成{}", step))
+                                    })
+                                    .await?;
+                            }
+                            context.checkpoint()?;
+                            Ok("全部完成".to_string())
+                        }
+                        .await;
+                        if result.is_err() {
+                            context
+                                .rollback(async {
+                                    rolled_back.store(true, Ordering::SeqCst);
+                                    Ok("已回滚".to_string())
+                                })
+                                .await?;
+                        }
+                        result
+                    })
+                    .await
+            };
+
+            let progress = events.progress.lock().unwrap();
+            if cancel_after == 0 {
+                // 未取消：全部步骤执行完成
+                assert_eq!(result.unwrap(), "全部完成");
+                assert_eq!(*executed.lock().unwrap(), (1..=STEPS).collect::<Vec<_>>());
+                assert!(!rolled_back.load(Ordering::SeqCst));
+                continue;
+            }
+
+            // 取消在当前步骤结束后生效，之后的步骤不再执行，已完成的步骤被回滚
+            let err = result.unwrap_err();
+            assert!(err.starts_with(CANCELLED_ERROR_PREFIX), "{}", err);
+            assert_eq!(*executed.lock().unwrap(), (1..=cancel_after).collect::<Vec<_>>());
+            assert!(rolled_back.load(Ordering::SeqCst));
+            let statuses: Vec<StepStatus> = progress.iter().map(|p| p.status).collect();
+            assert_eq!(
+                &statuses[statuses.len() - 3..],
+                &[StepStatus::Succeeded, StepStatus::Cancelled, StepStatus::RolledBack]
+            );
+            let cancelled = &progress[progress.len() - 2];
+            assert_eq!((cancelled.index, cancelled.total), (cancel_after, STEPS));
+            assert!(coordinator.list().is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_without_cancel() {
+        let (coordinator, events) = coordinator();
+        let result = coordinator
+            .run_steps(OperationKind::Backup, None, |context| async move {
+                context.checkpoint()?;
+                assert!(!context.is_cancelled());
+                Ok(())
+            })
+            .await;
+        assert!(result.is_ok());
+        assert!(events.progress.lock().unwrap().is_empty());
+    }
+}
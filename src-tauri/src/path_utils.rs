@@ -7,6 +7,23 @@ use std::path::{Path, PathBuf};
 /// 应用程序相关路径管理器
 pub struct AppPaths;
 
+#[cfg(test)]
+thread_local! {
+    /// 测试用配置目录（每个测试线程独立）
+    static TEST_CONFIG_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// 为当前测试线程创建空的临时配置目录，AppPaths 的配置、备份、日志目录
+/// 以及 Antigravity 数据目录（其中的 globalStorage/）都指向其中
+#[cfg(test)]
+pub fn use_test_config_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ag-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    TEST_CONFIG_DIR.with(|current| *current.borrow_mut() = Some(dir.clone()));
+    dir
+}
+
 impl AppPaths {
     /// 获取 Antigravity 数据目录
     ///
@@ -15,6 +32,10 @@ impl AppPaths {
     /// - macOS: ~/Library/Application Support/Antigravity/User/globalStorage/
     /// - Linux: ~/.config/Antigravity/User/globalStorage/
     pub fn antigravity_data_dir() -> Option<PathBuf> {
+        #[cfg(test)]
+        if let Some(dir) = TEST_CONFIG_DIR.with(|dir| dir.borrow().clone()) {
+            return Some(dir.join("globalStorage"));
+        }
         let result = match std::env::consts::OS {
             "windows" => Self::windows_antigravity_data_dir(),
             "macos" => Self::macos_antigravity_data_dir(),
@@ -198,6 +219,10 @@ impl AppPaths {
     ///
     /// 统一的配置目录获取，避免硬编码
     pub fn config_dir() -> Option<PathBuf> {
+        #[cfg(test)]
+        if let Some(dir) = TEST_CONFIG_DIR.with(|dir| dir.borrow().clone()) {
+            return Some(dir);
+        }
         config_dir().map(|path| path.join(".antigravity-agent"))
     }
 
@@ -206,6 +231,11 @@ impl AppPaths {
         Self::config_dir().map(|path| path.join("antigravity-accounts"))
     }
 
+    /// 获取日志目录
+    pub fn log_dir() -> Option<PathBuf> {
+        Self::config_dir().map(|path| path.join("logs"))
+    }
+
     // Windows 特定的辅助方法
     #[cfg(target_os = "windows")]
     fn get_program_files_dir() -> Option<PathBuf> {
@@ -3,6 +3,7 @@
 
 pub mod antigravity;
 pub mod process;
+pub mod process_watcher;
 
 // Re-export commonly used types and functions
 pub use antigravity::*;
@@ -1,46 +1,12 @@
-/// 关闭Antigravity进程 - 使用sysinfo库实现跨平台统一处理
+/// 关闭Antigravity进程 - 使用进程缓存中的 sysinfo 实例跨平台统一处理
 pub fn kill_antigravity_processes() -> Result<String, String> {
     tracing::info!("🔍 开始搜索并关闭 Antigravity 进程");
 
-    // 使用sysinfo库获取所有进程
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
-
-    let mut killed_processes = Vec::new();
-
-    // 定义需要关闭的进程模式（按优先级排序）
-    let process_patterns = get_antigravity_process_patterns();
-
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
-
-        // 检查进程名或命令行是否匹配任何模式
-        if matches_antigravity_process(process_name, &process_cmd, &process_patterns) {
-            tracing::info!("🎯 找到目标进程: {} (PID: {})", process_name, pid);
-            tracing::info!("📝 命令行: {}", process_cmd);
-
-            // 尝试终止进程
-            if process.kill() {
-                killed_processes.push(format!("{} (PID: {})", process_name, pid));
-                tracing::info!("✅ 成功终止进程: {} (PID: {})", process_name, pid);
-            } else {
-                tracing::warn!("⚠️ 终止进程失败: {} (PID: {})", process_name, pid);
-
-                // 尝试多次终止（如果第一次失败）
-                if process.kill() {
-                    killed_processes.push(format!("{} (PID: {} - 强制)", process_name, pid));
-                    tracing::info!("✅ 强制终止进程: {} (PID: {})", process_name, pid);
-                } else {
-                    tracing::error!("❌ 强制终止也失败: {} (PID: {})", process_name, pid);
-                }
-            }
-        }
-    }
+    let killed_processes = super::process_watcher::kill_matched();
 
     if killed_processes.is_empty() {
         tracing::info!("ℹ️ 未找到匹配的 Antigravity 进程");
-        tracing::info!("🔍 搜索的进程模式: {:?}", process_patterns);
+        tracing::info!("🔍 搜索的进程模式: {:?}", get_antigravity_process_patterns());
         Err("未找到Antigravity进程".to_string())
     } else {
         let success_msg = format!("已成功关闭Antigravity进程: {}", killed_processes.join(", "));
@@ -49,35 +15,17 @@ pub fn kill_antigravity_processes() -> Result<String, String> {
     }
 }
 
-/// 检查 Antigravity 进程是否正在运行（使用 sysinfo）
+/// 检查 Antigravity 进程是否正在运行（使用进程缓存，缓存过期时刷新）
 pub fn is_antigravity_running() -> bool {
-    tracing::debug!("🔍 检查 Antigravity 进程是否运行");
-
-    let mut system = sysinfo::System::new_all();
-    system.refresh_all();
-
-    let process_patterns = get_antigravity_process_patterns();
-
-    for (pid, process) in system.processes() {
-        let process_name = process.name();
-        let process_cmd = process.cmd().join(" ");
-
-        if matches_antigravity_process(process_name, &process_cmd, &process_patterns) {
-            tracing::debug!(
-                "✅ 发现运行中的 Antigravity 进程: {} (PID: {})",
-                process_name,
-                pid
-            );
-            return true;
-        }
-    }
-
-    tracing::debug!("ℹ️ 未发现运行中的 Antigravity 进程");
-    false
+    let running = super::process_watcher::processes()
+        .iter()
+        .any(|process| process.is_antigravity());
+    tracing::debug!(running, "检查 Antigravity 进程是否运行");
+    running
 }
 
 /// 获取 Antigravity 进程匹配模式
-fn get_antigravity_process_patterns() -> Vec<ProcessPattern> {
+pub(super) fn get_antigravity_process_patterns() -> Vec<ProcessPattern> {
     match std::env::consts::OS {
         "macos" => {
             vec![
@@ -111,7 +59,7 @@ fn get_antigravity_process_patterns() -> Vec<ProcessPattern> {
 }
 
 /// 检查进程是否匹配 Antigravity 模式
-fn matches_antigravity_process(
+pub(super) fn matches_antigravity_process(
     process_name: &str,
     process_cmd: &str,
     patterns: &[ProcessPattern],
@@ -122,14 +70,12 @@ fn matches_antigravity_process(
             ProcessPattern::ExactName(name) => {
                 if process_name == *name {
                     tracing::debug!("✅ 精确匹配进程名: {}", name);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::ExactName(\"{}\")", name);
                     matched = true;
                 }
             }
             ProcessPattern::CmdContains(text) => {
                 if process_cmd.contains(text) {
                     tracing::debug!("✅ 命令行包含匹配: {}", text);
-                    tracing::info!("🎯 匹配模式: ProcessPattern::CmdContains(\"{}\")", text);
                     matched = true;
                 }
             }
@@ -144,17 +90,3 @@ pub enum ProcessPattern {
     ExactName(&'static str),   // 精确匹配进程名
     CmdContains(&'static str), // 命令行包含指定文本
 }
-
-/// 获取 Antigravity 进程匹配模式（用于调试）
-pub fn get_antigravity_process_patterns_for_debug() -> Vec<ProcessPattern> {
-    get_antigravity_process_patterns()
-}
-
-/// 检查进程是否匹配 Antigravity 模式（用于调试）
-pub fn matches_antigravity_process_for_debug(
-    process_name: &str,
-    process_cmd: &str,
-    pattern: &ProcessPattern,
-) -> bool {
-    matches_antigravity_process(process_name, process_cmd, &[pattern.clone()])
-}
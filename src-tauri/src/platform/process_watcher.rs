@@ -0,0 +1,228 @@
+//! Antigravity 进程缓存
+//! 保留同一个 sysinfo::System，只刷新进程信息（不刷新磁盘、网络、CPU 列表等），
+//! 缓存匹配到的 Antigravity 进程（主进程、辅助进程、语言服务器）及其 CPU 与内存占用。
+//!
+//! CPU 使用率由相邻两次刷新的差值计算，因此由后台监视器（process_monitor）定期刷新；
+//! 缓存超过 CACHE_MAX_AGE 时，查询方会先同步刷新一次。
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, System, UpdateKind};
+
+use super::process::{get_antigravity_process_patterns, matches_antigravity_process, ProcessPattern};
+
+/// 缓存的最长有效时间
+const CACHE_MAX_AGE: Duration = Duration::from_millis(1000);
+
+/// 进程角色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessRole {
+    /// Antigravity 主进程
+    Main,
+    /// Electron 辅助进程（渲染、GPU、扩展宿主等）
+    Helper,
+    /// 语言服务器
+    LanguageServer,
+}
+
+/// Antigravity 相关进程
+#[derive(Debug, Clone, Serialize)]
+pub struct AntigravityProcess {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub role: ProcessRole,
+    /// CPU 使用率（百分比，多核时可能超过 100）
+    pub cpu_usage: f32,
+    /// 内存占用（字节）
+    pub memory_bytes: u64,
+}
+
+impl AntigravityProcess {
+    /// 是否为 Antigravity 本身的进程（语言服务器除外，与进程匹配模式一致）
+    pub fn is_antigravity(&self) -> bool {
+        self.role != ProcessRole::LanguageServer
+    }
+}
+
+struct Watcher {
+    system: System,
+    processes: Vec<AntigravityProcess>,
+    refreshed_at: Option<Instant>,
+}
+
+fn watcher() -> &'static Mutex<Watcher> {
+    static WATCHER: OnceLock<Mutex<Watcher>> = OnceLock::new();
+    WATCHER.get_or_init(|| {
+        Mutex::new(Watcher {
+            system: System::new(),
+            processes: Vec::new(),
+            refreshed_at: None,
+        })
+    })
+}
+
+fn is_language_server(name: &str) -> bool {
+    name.to_lowercase().contains("language_server")
+}
+
+/// 按进程名与命令行判断已匹配进程的角色
+fn classify(name: &str, command: &str) -> ProcessRole {
+    if command.contains("--type=") || name.contains("Helper") {
+        ProcessRole::Helper
+    } else {
+        ProcessRole::Main
+    }
+}
+
+/// 从全部进程中筛选 Antigravity 相关进程
+///
+/// 语言服务器不在进程匹配模式中：父进程是 Antigravity 进程，或可执行文件位于 Antigravity 安装目录时计入。
+fn select(candidates: Vec<AntigravityProcess>, patterns: &[ProcessPattern]) -> Vec<AntigravityProcess> {
+    let (mut matched, rest): (Vec<_>, Vec<_>) = candidates
+        .into_iter()
+        .partition(|p| matches_antigravity_process(&p.name, &p.command, patterns));
+    for process in &mut matched {
+        process.role = classify(&process.name, &process.command);
+    }
+
+    let parents: HashSet<u32> = matched.iter().map(|p| p.pid).collect();
+    matched.extend(rest.into_iter().filter_map(|mut process| {
+        let owned = process.parent_pid.is_some_and(|ppid| parents.contains(&ppid))
+            || process.command.to_lowercase().contains("antigravity");
+        (is_language_server(&process.name) && owned).then(|| {
+            process.role = ProcessRole::LanguageServer;
+            process
+        })
+    }));
+
+    matched.sort_by_key(|p| (p.role, p.pid));
+    matched
+}
+
+impl Watcher {
+    fn refresh(&mut self) {
+        self.system.refresh_processes_specifics(
+            ProcessRefreshKind::new()
+                .with_cpu()
+                .with_memory()
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+        let candidates = self
+            .system
+            .processes()
+            .iter()
+            .map(|(pid, process)| AntigravityProcess {
+                pid: pid.as_u32(),
+                parent_pid: process.parent().map(|ppid| ppid.as_u32()),
+                name: process.name().to_string(),
+                command: process.cmd().join(" "),
+                role: ProcessRole::Main,
+                cpu_usage: process.cpu_usage(),
+                memory_bytes: process.memory(),
+            })
+            .collect();
+        self.processes = select(candidates, &get_antigravity_process_patterns());
+        self.refreshed_at = Some(Instant::now());
+    }
+
+    fn is_stale(&self) -> bool {
+        self.refreshed_at.is_none_or(|at| at.elapsed() > CACHE_MAX_AGE)
+    }
+}
+
+/// 立即刷新并返回 Antigravity 相关进程
+pub fn refresh() -> Vec<AntigravityProcess> {
+    let mut watcher = watcher().lock().unwrap();
+    watcher.refresh();
+    watcher.processes.clone()
+}
+
+/// 返回 Antigravity 相关进程（缓存过期时先刷新）
+pub fn processes() -> Vec<AntigravityProcess> {
+    let mut watcher = watcher().lock().unwrap();
+    if watcher.is_stale() {
+        watcher.refresh();
+    }
+    watcher.processes.clone()
+}
+
+/// 终止所有匹配的 Antigravity 进程，返回已终止的进程描述
+pub(super) fn kill_matched() -> Vec<String> {
+    let mut watcher = watcher().lock().unwrap();
+    watcher.refresh();
+
+    let mut killed_processes = Vec::new();
+    for target in watcher.processes.iter().filter(|p| p.is_antigravity()) {
+        let Some(process) = watcher.system.process(Pid::from_u32(target.pid)) else {
+            continue;
+        };
+        tracing::info!("🎯 找到目标进程: {} (PID: {})", target.name, target.pid);
+        tracing::info!("📝 命令行: {}", target.command);
+
+        // 尝试终止进程
+        if process.kill() {
+            killed_processes.push(format!("{} (PID: {})", target.name, target.pid));
+            tracing::info!("✅ 成功终止进程: {} (PID: {})", target.name, target.pid);
+        } else {
+            tracing::warn!("⚠️ 终止进程失败: {} (PID: {})", target.name, target.pid);
+
+            // 尝试多次终止（如果第一次失败）
+            if process.kill() {
+                killed_processes.push(format!("{} (PID: {} - 强制)", target.name, target.pid));
+                tracing::info!("✅ 强制终止进程: {} (PID: {})", target.name, target.pid);
+            } else {
+                tracing::error!("❌ 强制终止也失败: {} (PID: {})", target.name, target.pid);
+            }
+        }
+    }
+
+    // 进程退出需要时间，下次查询时重新扫描
+    watcher.refreshed_at = None;
+    killed_processes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent_pid: Option<u32>, name: &str, command: &str) -> AntigravityProcess {
+        AntigravityProcess {
+            pid,
+            parent_pid,
+            name: name.to_string(),
+            command: command.to_string(),
+            role: ProcessRole::Main,
+            cpu_usage: 0.0,
+            memory_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_select_roles() {
+        let patterns = [ProcessPattern::ExactName("antigravity")];
+        let selected = select(
+            vec![
+                process(30, Some(10), "language_server", "/opt/ls/language_server_linux_x64"),
+                process(11, Some(10), "antigravity", "/usr/share/antigravity/antigravity --type=renderer"),
+                process(10, Some(1), "antigravity", "/usr/share/antigravity/antigravity"),
+                process(40, Some(1), "language_server", "/opt/other/language_server"),
+                process(50, Some(1), "bash", "bash"),
+            ],
+            &patterns,
+        );
+        let roles: Vec<(u32, ProcessRole)> = selected.iter().map(|p| (p.pid, p.role)).collect();
+        assert_eq!(
+            roles,
+            vec![
+                (10, ProcessRole::Main),
+                (11, ProcessRole::Helper),
+                (30, ProcessRole::LanguageServer),
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Antigravity 进程监视器
+//! 后台定期刷新进程缓存（platform::process_watcher），
+//! Antigravity 启动或退出时向前端发送 `antigravity-started` / `antigravity-stopped` 事件，
+//! 前端无需再轮询进程状态。
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::time::{interval, Duration};
+
+use crate::platform::process_watcher;
+
+/// Antigravity 启动事件名
+pub const STARTED_EVENT: &str = "antigravity-started";
+
+/// Antigravity 退出事件名
+pub const STOPPED_EVENT: &str = "antigravity-stopped";
+
+/// 刷新间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 进程状态变化事件
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessStateEvent {
+    /// 当前的 Antigravity 进程（退出时为空）
+    pub pids: Vec<u32>,
+}
+
+/// 启动后台进程监视
+pub fn start(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = interval(REFRESH_INTERVAL);
+        // 首次刷新只记录状态，不发送事件
+        let mut was_running: Option<bool> = None;
+        loop {
+            ticker.tick().await;
+
+            let processes = match tokio::task::spawn_blocking(process_watcher::refresh).await {
+                Ok(processes) => processes,
+                Err(e) => {
+                    tracing::warn!(target: "process::monitor", error = %e, "刷新进程状态失败");
+                    continue;
+                }
+            };
+            let pids: Vec<u32> = processes
+                .iter()
+                .filter(|process| process.is_antigravity())
+                .map(|process| process.pid)
+                .collect();
+            let running = !pids.is_empty();
+
+            if was_running.is_some_and(|was| was != running) {
+                let event = if running { STARTED_EVENT } else { STOPPED_EVENT };
+                tracing::info!(target: "process::monitor", event, pids = ?pids, "Antigravity 进程状态变化");
+                if let Err(e) = app_handle.emit(event, ProcessStateEvent { pids }) {
+                    tracing::warn!(target: "process::monitor", error = %e, "发送进程状态事件失败");
+                }
+            }
+            was_running = Some(running);
+        }
+    });
+}
@@ -1,6 +1,6 @@
-use crate::{app_settings, db_monitor, system_tray, window};
+use crate::{antigravity, app_settings, db_monitor, operations, process_monitor, system_tray, window};
 use std::sync::Arc;
-use tauri::{App, Manager};
+use tauri::{App, Emitter, Manager};
 
 pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>> {
     tracing::info!(target: "app::setup", "开始应用程序设置");
@@ -12,6 +12,9 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
     // 初始化系统托盘管理器
     app.manage(system_tray::SystemTrayManager::new());
 
+    // 初始化操作协调器（串行执行切换、恢复、注销等修改类操作）
+    app.manage(operations::OperationCoordinator::new(app.handle().clone()));
+
     // Tracing 日志记录器已在 main 函数中初始化，这里跳过
 
     // 在 release 模式下禁用右键菜单
@@ -32,6 +35,21 @@ pub fn init(app: &mut App) -> std::result::Result<(), Box<dyn std::error::Error>
 
     tracing::info!(target: "app::setup::db_monitor", "数据库监控器初始化完成");
 
+    // 后台刷新 Antigravity 进程缓存，进程启动或退出时通知前端
+    process_monitor::start(app.handle().clone());
+
+    // 保险库空闲超时检查：超时后清除内存中的密钥并通知前端
+    let app_handle_for_vault = app.handle().clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(tokio::time::Duration::from_secs(30));
+        loop {
+            ticker.tick().await;
+            if antigravity::vault::vault().lock_if_idle() {
+                let _ = app_handle_for_vault.emit("vault-locked", ());
+            }
+        }
+    });
+
     // 初始化窗口事件处理器
     if let Err(e) = window::init_window_event_handler(app) {
         tracing::error!(target: "app::setup::window", error = %e, "窗口事件处理器初始化失败");
@@ -18,13 +18,21 @@ pub struct AntigravityAccount {
     pub id: String,
     pub name: String,
     pub email: String,
-    pub api_key: String,
+    /// 不透明账户句柄（随机账户 ID），需要令牌的操作由 Rust 命令凭句柄完成
+    pub handle: String,
     pub profile_url: String,   // Base64 编码的头像
-    pub user_settings: String, // 编码后的用户设置
     pub created_at: String,
     pub last_switched: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_status_proto: Option<String>, // Base64 编码的用户状态 protobuf 数据
+    /// 备份中访问令牌的过期时间（RFC3339）
+    pub token_expires_at: Option<String>,
+    /// 访问令牌状态：fresh / expiring_soon / expired / unknown
+    pub token_status: crate::antigravity::credentials::TokenStatus,
+    /// 备份中的扩展密钥数量
+    pub secret_count: usize,
+    /// 扩展密钥来自其他设备，恢复到本机后无法解密
+    pub secrets_at_risk: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,7 +40,9 @@ pub struct AppState {
     pub profiles: HashMap<String, ProfileInfo>,
     pub config_dir: PathBuf,
     pub antigravity_accounts: HashMap<String, AntigravityAccount>,
-    pub current_account_id: Option<String>,
+    /// 当前活动账户的句柄（由活动账户检测更新）
+    #[serde(skip)]
+    pub current_account_id: std::sync::Mutex<Option<String>>,
 }
 
 impl Default for AppState {
@@ -71,7 +81,7 @@ impl Default for AppState {
             profiles: HashMap::new(),
             config_dir,
             antigravity_accounts: HashMap::new(),
-            current_account_id: None,
+            current_account_id: std::sync::Mutex::new(None),
         }
     }
 }
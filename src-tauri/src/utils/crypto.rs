@@ -2,7 +2,7 @@
 //! 使用 AES-256-GCM 加密敏感数据，Argon2 进行密钥派生
 
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
 use argon2::{
@@ -48,6 +48,9 @@ impl std::error::Error for CryptoError {}
 const ENCRYPTED_FILE_MAGIC_V1: &[u8] = b"AGCRYPT1";
 /// 加密文件头魔数（V2: Argon2id）
 const ENCRYPTED_FILE_MAGIC_V2: &[u8] = b"AGCRYPT2";
+/// 加密文件头魔数（保险库模式：主密码派生密钥）
+const ENCRYPTED_FILE_MAGIC_VAULT: &[u8] = b"AGVAULT1";
+const ENCRYPTED_FILE_MAGIC_DATA_KEY: &[u8] = b"AGDKEY01";
 /// Nonce 长度（12 字节）
 const NONCE_SIZE: usize = 12;
 /// Salt 长度（16 字节）
@@ -157,7 +160,9 @@ fn derive_machine_key_v1() -> Result<Zeroizing<[u8; 32]>, CryptoError> {
 }
 
 /// 新版机器密钥派生 (V2: Argon2id)
-fn derive_machine_key_v2() -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+///
+/// 派生开销较大（19MB 内存、2 轮），调用者应在会话内缓存结果
+pub fn derive_machine_key_v2() -> Result<Zeroizing<[u8; 32]>, CryptoError> {
     let machine_id = get_machine_id()?; // 必须成功，不回退
     let username = whoami::username();
     let app_salt = "antigravity-agent-v2-argon2"; // 更新 Salt
@@ -176,11 +181,23 @@ fn derive_machine_key_v2() -> Result<Zeroizing<[u8; 32]>, CryptoError> {
     Ok(Zeroizing::new(key))
 }
 
-/// 自动加密机器数据 (使用 V2)
-pub fn encrypt_machine_data(plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
-    let key = derive_machine_key_v2()?;
-    
-    let cipher = Aes256Gcm::new_from_slice(&*key)
+/// 本机标识的指纹（机器 ID 与系统用户名的哈希，不可逆）
+///
+/// 用于判断备份是否来自本机：系统钥匙串加密的数据只能在同一台机器、同一用户下解密。
+pub fn machine_fingerprint() -> Result<String, CryptoError> {
+    let machine_id = get_machine_id()?;
+    let username = whoami::username();
+
+    let mut hasher = Sha256::new();
+    hasher.update(format!("antigravity-agent-machine:{}:{}", machine_id, username).as_bytes());
+    Ok(hex::encode(&hasher.finalize()[..16]))
+}
+
+/// 使用已派生的 V2 机器密钥加密（避免重复执行 Argon2）
+///
+/// 备份流程通过保险库获取会话内缓存的机器密钥（见 `vault::VaultManager::encrypt`）
+pub fn encrypt_machine_data_with_key(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let cipher = Aes256Gcm::new_from_slice(key)
         .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
     
     let mut nonce_bytes = [0u8; NONCE_SIZE];
@@ -220,6 +237,14 @@ pub fn decrypt_machine_data(encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
     Err(CryptoError::InvalidData("未知的文件格式或版本".to_string()))
 }
 
+/// 使用已派生的 V2 机器密钥解密（V1 旧格式仍回退到 SHA-256 派生）
+pub fn decrypt_machine_data_with_key(encrypted: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    if encrypted.starts_with(ENCRYPTED_FILE_MAGIC_V2) {
+        return decrypt_data_internal(encrypted, key, ENCRYPTED_FILE_MAGIC_V2.len());
+    }
+    decrypt_machine_data(encrypted)
+}
+
 /// 内部解密逻辑
 fn decrypt_data_internal(encrypted: &[u8], key: &[u8; 32], magic_len: usize) -> Result<Vec<u8>, CryptoError> {
     if encrypted.len() < magic_len + NONCE_SIZE + 16 {
@@ -240,7 +265,7 @@ fn decrypt_data_internal(encrypted: &[u8], key: &[u8; 32], magic_len: usize) ->
 }
 
 /// 保持向后兼容的公开 API (如果需要)
-/// 注意：这个函数现在被标记为过时，建议使用 encrypt_machine_data
+/// 注意：这个函数现在被标记为过时，建议使用 encrypt_machine_data_with_key
 pub fn derive_machine_key() -> Result<[u8; 32], CryptoError> {
     let key = derive_machine_key_v1()?;
     Ok(*key) // 返回拷贝，注意这里是不安全的（内存残留），但为了兼容性保留
@@ -258,7 +283,7 @@ pub fn encrypt_data(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoE
     // 但是调用者自己派生了 Key。如果调用者用 V1 Key，我们却写 V2 Magic，那就乱了。
     // 所以：encrypt_data 应该只负责加密，不负责 Magic？
     // 原来的实现是负责 Magic 的。
-    // 既然我们提供了 encrypt_machine_data，那个函数负责 Magic。
+    // 既然我们提供了 encrypt_machine_data_with_key，那个函数负责 Magic。
     // 这个函数如果被直接调用，我们假设它是 V1 行为（为了兼容）。
     
     let cipher = Aes256Gcm::new_from_slice(key)
@@ -357,12 +382,117 @@ pub fn decrypt_with_password(encrypted: &[u8], password: &str) -> Result<Vec<u8>
     decrypt_data(ciphertext, &key)
 }
 
+/// 使用保险库密钥加密数据（主密码模式）
+///
+/// 输出格式：AGVAULT1 + nonce + ciphertext，密钥由调用者通过
+/// `derive_key_from_password` 派生并负责缓存
+pub fn encrypt_vault_data(plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+
+    let mut output = Vec::with_capacity(ENCRYPTED_FILE_MAGIC_VAULT.len() + NONCE_SIZE + ciphertext.len());
+    output.extend_from_slice(ENCRYPTED_FILE_MAGIC_VAULT);
+    output.extend_from_slice(&nonce_bytes);
+    output.extend_from_slice(&ciphertext);
+
+    Ok(output)
+}
+
+/// 使用保险库密钥解密数据（主密码模式）
+pub fn decrypt_vault_data(encrypted: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    if !is_vault_encrypted(encrypted) {
+        return Err(CryptoError::InvalidData("不是保险库加密格式".to_string()));
+    }
+    decrypt_data_internal(encrypted, key, ENCRYPTED_FILE_MAGIC_VAULT.len())
+}
+
+/// 检查数据是否由保险库密钥加密
+pub fn is_vault_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_FILE_MAGIC_VAULT)
+}
+
+/// 使用独立数据密钥加密（每个备份一个密钥，用于加密擦除）
+///
+/// 输出格式：AGDKEY01 + 密钥 ID 长度(1 字节) + 密钥 ID + nonce + ciphertext，
+/// 密钥 ID 作为附加认证数据，防止密文被挪用到其他密钥名下
+pub fn encrypt_with_data_key(key_id: &str, plaintext: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let id_len = u8::try_from(key_id.len())
+        .map_err(|_| CryptoError::EncryptionFailed("密钥 ID 过长".to_string()))?;
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+
+    let mut nonce_bytes = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, Payload { msg: plaintext, aad: key_id.as_bytes() })
+        .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+
+    let mut output = Vec::with_capacity(
+        ENCRYPTED_FILE_MAGIC_DATA_KEY.len() + 1 + key_id.len() + NONCE_SIZE + ciphertext.len(),
+    );
+    output.extend_from_slice(ENCRYPTED_FILE_MAGIC_DATA_KEY);
+    output.push(id_len);
+    output.extend_from_slice(key_id.as_bytes());
+    output.extend_from_slice(&nonce_bytes);
+    output.extend_from_slice(&ciphertext);
+
+    Ok(output)
+}
+
+/// 读取数据密钥格式密文中的密钥 ID
+pub fn data_key_id(data: &[u8]) -> Option<&str> {
+    let rest = data.strip_prefix(ENCRYPTED_FILE_MAGIC_DATA_KEY)?;
+    let (&id_len, rest) = rest.split_first()?;
+    std::str::from_utf8(rest.get(..id_len as usize)?).ok()
+}
+
+/// 使用独立数据密钥解密
+pub fn decrypt_with_data_key(encrypted: &[u8], key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+    let key_id = data_key_id(encrypted)
+        .ok_or_else(|| CryptoError::InvalidData("不是数据密钥加密格式".to_string()))?;
+    let offset = ENCRYPTED_FILE_MAGIC_DATA_KEY.len() + 1 + key_id.len();
+    if encrypted.len() < offset + NONCE_SIZE {
+        return Err(CryptoError::InvalidData("数据太短".to_string()));
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(key)
+        .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))?;
+    let nonce = Nonce::from_slice(&encrypted[offset..offset + NONCE_SIZE]);
+    cipher
+        .decrypt(nonce, Payload { msg: &encrypted[offset + NONCE_SIZE..], aad: key_id.as_bytes() })
+        .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))
+}
+
+/// 检查数据是否由独立数据密钥加密
+pub fn is_data_key_encrypted(data: &[u8]) -> bool {
+    data.starts_with(ENCRYPTED_FILE_MAGIC_DATA_KEY)
+}
+
+/// 生成密码学安全的随机字节（用于 salt、标识符等）
+pub fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
 /// 检查数据是否已加密
 pub fn is_encrypted(data: &[u8]) -> bool {
     (data.len() >= ENCRYPTED_FILE_MAGIC_V1.len() 
         && &data[..ENCRYPTED_FILE_MAGIC_V1.len()] == ENCRYPTED_FILE_MAGIC_V1) ||
     (data.len() >= ENCRYPTED_FILE_MAGIC_V2.len() 
-        && &data[..ENCRYPTED_FILE_MAGIC_V2.len()] == ENCRYPTED_FILE_MAGIC_V2)
+        && &data[..ENCRYPTED_FILE_MAGIC_V2.len()] == ENCRYPTED_FILE_MAGIC_V2) ||
+    is_vault_encrypted(data) ||
+    is_data_key_encrypted(data)
 }
 
 /// 检查文件是否已加密（带 salt 前缀）
@@ -543,7 +673,8 @@ mod tests {
             return;
         }
 
-        let encrypted = encrypt_machine_data(plaintext).unwrap();
+        let key = derive_machine_key_v2().unwrap();
+        let encrypted = encrypt_machine_data_with_key(plaintext, &key).unwrap();
         assert!(encrypted.starts_with(ENCRYPTED_FILE_MAGIC_V2));
 
         let decrypted = decrypt_machine_data(&encrypted).unwrap();
@@ -597,6 +728,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_vault_encrypt_decrypt() {
+        let salt: [u8; SALT_SIZE] = random_bytes();
+        let key = derive_key_from_password("Vault-Password-123!", &salt).unwrap();
+        let plaintext = b"Vault protected backup";
+
+        let encrypted = encrypt_vault_data(plaintext, &key).unwrap();
+        assert!(is_vault_encrypted(&encrypted));
+        assert!(is_encrypted(&encrypted));
+
+        let decrypted = decrypt_vault_data(&encrypted, &key).unwrap();
+        assert_eq!(plaintext.to_vec(), decrypted);
+
+        let wrong_key = derive_key_from_password("Other-Password-123!", &salt).unwrap();
+        assert!(decrypt_vault_data(&encrypted, &wrong_key).is_err());
+    }
+
+    #[test]
+    fn test_data_key_encrypt_decrypt() {
+        let key: [u8; 32] = random_bytes();
+        let plaintext = b"Per-account backup data";
+
+        let encrypted = encrypt_with_data_key("account-1", plaintext, &key).unwrap();
+        assert!(is_data_key_encrypted(&encrypted));
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(data_key_id(&encrypted), Some("account-1"));
+
+        let decrypted = decrypt_with_data_key(&encrypted, &key).unwrap();
+        assert_eq!(plaintext.to_vec(), decrypted);
+
+        let other_key: [u8; 32] = random_bytes();
+        assert!(decrypt_with_data_key(&encrypted, &other_key).is_err());
+
+        // 篡改密钥 ID 后认证失败
+        let mut tampered = encrypted.clone();
+        tampered[ENCRYPTED_FILE_MAGIC_DATA_KEY.len() + 1] = b'b';
+        assert!(decrypt_with_data_key(&tampered, &key).is_err());
+    }
+
     #[test]
     fn test_secure_delete() {
         let temp_dir = std::env::temp_dir();
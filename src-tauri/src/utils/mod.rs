@@ -4,3 +4,4 @@ pub mod tracing_config;
 pub mod log_decorator;
 pub mod log_sanitizer;
 pub mod crypto;
+pub mod secret;
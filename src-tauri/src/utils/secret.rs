@@ -0,0 +1,42 @@
+//! 敏感字符串类型
+//! 访问令牌等凭据只在 Rust 侧以该类型持有：释放时清零内存，
+//! Debug/Display 输出均为脱敏占位符，且不实现 Serialize，无法被意外返回给前端。
+
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// 脱敏占位符
+const REDACTED: &str = "[REDACTED]";
+
+/// 释放时清零的敏感字符串
+#[derive(Clone)]
+pub struct SecretString(Zeroizing<String>);
+
+impl SecretString {
+    pub fn new(value: String) -> Self {
+        Self(Zeroizing::new(value))
+    }
+
+    /// 显式取出明文（调用处即为令牌的使用点，便于审计）
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        Self::new(value.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretString({})", REDACTED)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
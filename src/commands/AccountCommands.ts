@@ -1,5 +1,18 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { AntigravityAuthInfo, AntigravityAccount } from './types/account.types';
+import type {
+  ActiveAccountStatus,
+  AntigravityAuthInfo,
+  AntigravityAccount,
+  KeyGroup,
+  KeyGroupInfo,
+  LogoutPlan,
+  LogoutProfile,
+  LogoutReport,
+  SecretReport,
+  SecretRestorePolicy,
+  SwitchPreview,
+  UndoInfo,
+} from './types/account.types';
 
 /**
  * Antigravity 账户管理命令
@@ -13,6 +26,14 @@ export class AccountCommands {
     return invoke('get_current_antigravity_info');
   }
 
+  /**
+   * 检测当前活动账户及其与备份的关系
+   * @returns 活动账户检测结果
+   */
+  static async getActiveAccountStatus(): Promise<ActiveAccountStatus> {
+    return invoke('get_active_account_status');
+  }
+
   /**
    * 获取所有已备份的账户列表
    * @returns 账户列表
@@ -32,19 +53,107 @@ export class AccountCommands {
   /**
    * 恢复账户数据（不包含进程管理）
    * @param accountName 账户名（邮箱）
+   * @param keyGroups 只恢复这些字段分组（省略时使用账户的默认分组）
+   * @param secretPolicy 扩展密钥恢复策略（省略时使用设置）
    * @returns 恢复结果消息
    */
-  static async restoreAccount(accountName: string): Promise<string> {
-    return invoke('restore_antigravity_account', { account_name: accountName });
+  static async restoreAccount(
+    accountName: string,
+    keyGroups?: KeyGroup[],
+    secretPolicy?: SecretRestorePolicy,
+  ): Promise<string> {
+    return invoke('restore_antigravity_account', { accountName, keyGroups, secretPolicy });
+  }
+
+  /**
+   * 检查账户备份中的扩展密钥能否在本机解密
+   * @param accountName 账户名（邮箱）
+   * @returns 扩展密钥的可迁移情况
+   */
+  static async inspectSecrets(accountName: string): Promise<SecretReport> {
+    return invoke('inspect_account_secrets', { accountName });
   }
 
   /**
-   * 切换到指定账户（完整流程：关闭进程 → 恢复数据 → 重启）
+   * 切换到指定账户（完整流程：保存快照 → 关闭进程 → 恢复数据 → 重启 → 验证启动）
+   * 启动验证失败时自动回滚，结果可通过 ProcessCommands.getLastLaunchVerification 查询
+   * 每一步发送 operation-progress 事件，可通过 OperationCommands.cancel 取消并回滚
    * @param accountName 账户名（邮箱）
+   * @param allowExpired 目标账户令牌已过期时仍然切换
+   * @param keyGroups 只恢复这些字段分组（省略时使用账户的默认分组）
+   * @param secretPolicy 扩展密钥恢复策略（省略时使用设置）
    * @returns 切换结果消息
    */
-  static async switchToAccount(accountName: string): Promise<string> {
-    return invoke('switch_to_antigravity_account', { account_name: accountName });
+  static async switchToAccount(
+    accountName: string,
+    allowExpired = false,
+    keyGroups?: KeyGroup[],
+    secretPolicy?: SecretRestorePolicy,
+  ): Promise<string> {
+    return invoke('switch_to_antigravity_account', { accountName, allowExpired, keyGroups, secretPolicy });
+  }
+
+  /**
+   * 预览切换到指定账户后数据库的变化
+   * @param accountName 账户名（邮箱）
+   * @param keyGroups 只预览这些字段分组（省略时使用账户的默认分组）
+   * @returns 字段变化与摘要（值已脱敏）
+   */
+  static async previewSwitch(accountName: string, keyGroups?: KeyGroup[]): Promise<SwitchPreview> {
+    return invoke('preview_switch', { accountName, keyGroups });
+  }
+
+  /**
+   * 获取可选的恢复字段分组
+   * @returns 分组说明列表
+   */
+  static async listKeyGroups(): Promise<KeyGroupInfo[]> {
+    return invoke('list_key_groups');
+  }
+
+  /**
+   * 获取账户的默认恢复分组
+   * @param accountName 账户名（邮箱）
+   * @returns 默认恢复的分组（未设置时为全部分组）
+   */
+  static async getRestoreGroups(accountName: string): Promise<KeyGroup[]> {
+    return invoke('get_account_restore_groups', { accountName });
+  }
+
+  /**
+   * 设置账户的默认恢复分组
+   * @param accountName 账户名（邮箱）
+   * @param keyGroups 默认恢复的分组，传 null 恢复为全部分组
+   * @returns 保存后的默认分组
+   */
+  static async setRestoreGroups(accountName: string, keyGroups: KeyGroup[] | null): Promise<KeyGroup[]> {
+    return invoke('set_account_restore_groups', { accountName, keyGroups });
+  }
+
+  /**
+   * 比较两个账户的备份
+   * @param accountA 账户 A（邮箱）
+   * @param accountB 账户 B（邮箱）
+   * @returns 字段变化与摘要（值已脱敏）
+   */
+  static async compareBackups(accountA: string, accountB: string): Promise<SwitchPreview> {
+    return invoke('compare_account_backups', { accountA, accountB });
+  }
+
+  /**
+   * 撤销最近一次账户切换（恢复切换前状态并重启 Antigravity）
+   * @returns 撤销结果消息
+   */
+  static async undoLastSwitch(): Promise<string> {
+    return invoke('undo_last_switch');
+  }
+
+  /**
+   * 获取切换撤销栈（最新的在前）
+   * @returns 撤销栈条目
+   */
+  static async listUndoStack(): Promise<UndoInfo[]> {
+    return invoke('list_switch_undo_stack');
   }
 
   /**
@@ -54,4 +163,22 @@ export class AccountCommands {
   static async clearAllData(): Promise<string> {
     return invoke('clear_all_antigravity_data');
   }
+
+  /**
+   * 预览指定深度的注销将删除的内容
+   * @param profile 注销深度，默认标准注销
+   * @returns 注销预览
+   */
+  static async previewLogout(profile?: LogoutProfile): Promise<LogoutPlan> {
+    return invoke('preview_logout', { profile });
+  }
+
+  /**
+   * 按指定深度注销（深度注销需先关闭 Antigravity）
+   * @param profile 注销深度，默认标准注销
+   * @returns 实际删除的内容
+   */
+  static async logout(profile?: LogoutProfile): Promise<LogoutReport> {
+    return invoke('logout_antigravity', { profile });
+  }
 }
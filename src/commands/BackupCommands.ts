@@ -1,5 +1,11 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { BackupData, RestoreResult } from './types/backup.types';
+import type {
+  BackupData,
+  GcReport,
+  RestoreResult,
+  SnapshotInfo,
+  StoreStats,
+} from './types/backup.types';
 
 /**
  * 备份管理命令
@@ -47,4 +53,49 @@ export class BackupCommands {
   static async clearAll(): Promise<string> {
     return invoke('clear_all_backups');
   }
+
+  /**
+   * 列出账户的历史快照
+   * @param accountName 账户邮箱
+   * @returns 快照列表（最新的在前）
+   */
+  static async listSnapshots(accountName: string): Promise<SnapshotInfo[]> {
+    return invoke('list_backup_snapshots', { accountName });
+  }
+
+  /**
+   * 删除账户的单个历史快照
+   * @param accountName 账户邮箱
+   * @param snapshotId 快照 ID
+   * @returns 删除结果消息
+   */
+  static async deleteSnapshot(accountName: string, snapshotId: string): Promise<string> {
+    return invoke('delete_backup_snapshot', { accountName, snapshotId });
+  }
+
+  /**
+   * 将历史快照恢复到 Antigravity 数据库
+   * @param accountName 账户邮箱
+   * @param snapshotId 快照 ID
+   * @returns 恢复结果消息
+   */
+  static async restoreSnapshot(accountName: string, snapshotId: string): Promise<string> {
+    return invoke('restore_backup_snapshot', { accountName, snapshotId });
+  }
+
+  /**
+   * 回收快照存储中不再被引用的数据
+   * @returns 回收结果
+   */
+  static async collectGarbage(): Promise<GcReport> {
+    return invoke('collect_backup_garbage');
+  }
+
+  /**
+   * 获取快照存储占用统计
+   * @returns 存储统计
+   */
+  static async getStoreStats(): Promise<StoreStats> {
+    return invoke('get_backup_store_stats');
+  }
 }
@@ -0,0 +1,44 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { CloudCodeAPITypes } from '@/services/cloudcode-api.types';
+import type { CloudCodeSettings, ModelQuota } from './types/cloudcode.types';
+
+/**
+ * CloudCode 接口命令
+ * 访问令牌只在后端使用，前端通过账户句柄发起请求
+ */
+export class CloudCodeCommands {
+  /**
+   * 获取账户的可用模型列表
+   * @param handle 账户句柄
+   * @returns 模型列表（含配额信息）
+   */
+  static async fetchAccountModels(handle: string): Promise<CloudCodeAPITypes.FetchAvailableModelsResponse> {
+    return invoke('fetch_account_models', { handle });
+  }
+
+  /**
+   * 获取账户各模型的配额
+   * @param handle 账户句柄
+   * @returns 模型配额列表
+   */
+  static async fetchAccountQuota(handle: string): Promise<ModelQuota[]> {
+    return invoke('fetch_account_quota', { handle });
+  }
+
+  /**
+   * 获取 CloudCode 接口配置
+   * @returns 接口地址与 User-Agent
+   */
+  static async getSettings(): Promise<CloudCodeSettings> {
+    return invoke('get_cloudcode_settings');
+  }
+
+  /**
+   * 设置 CloudCode 接口地址
+   * @param baseUrl 接口地址，传 null 恢复默认
+   * @returns 生效的接口地址
+   */
+  static async setBaseUrl(baseUrl: string | null): Promise<string> {
+    return invoke('set_cloudcode_base_url', { baseUrl });
+  }
+}
@@ -0,0 +1,103 @@
+import { invoke } from '@tauri-apps/api/core';
+import type {
+  ConsistencyReport,
+  DbFile,
+  DbHealth,
+  DbSnapshotInfo,
+  KeyEntry,
+  SyncDirection,
+  SyncResult,
+  VacuumResult,
+} from './types/database.types';
+
+/**
+ * 数据库维护命令
+ * 恢复与清除登录数据前会自动为 state.vscdb 及其 .backup 创建整库安全快照
+ */
+export class DatabaseCommands {
+  /**
+   * 列出数据库安全快照（最新的在前）
+   * @returns 快照列表
+   */
+  static async listSnapshots(): Promise<DbSnapshotInfo[]> {
+    return invoke('list_db_snapshots');
+  }
+
+  /**
+   * 手动创建数据库安全快照
+   * @returns 快照信息
+   */
+  static async createSnapshot(): Promise<DbSnapshotInfo> {
+    return invoke('create_db_snapshot');
+  }
+
+  /**
+   * 将整个数据库回滚到指定快照（需先关闭 Antigravity）
+   * @param snapshotId 快照 ID
+   * @returns 回滚到的快照信息
+   */
+  static async rollbackSnapshot(snapshotId: string): Promise<DbSnapshotInfo> {
+    return invoke('rollback_db_snapshot', { snapshotId });
+  }
+
+  /**
+   * 删除数据库安全快照
+   * @param snapshotId 快照 ID
+   * @returns 删除结果消息
+   */
+  static async deleteSnapshot(snapshotId: string): Promise<string> {
+    return invoke('delete_db_snapshot', { snapshotId });
+  }
+
+  /**
+   * 检查 state.vscdb 与 .backup 的完整性与日志模式
+   * @returns 各数据库的健康状态
+   */
+  static async checkHealth(): Promise<DbHealth[]> {
+    return invoke('check_db_health');
+  }
+
+  /**
+   * 整理（VACUUM）数据库（需先关闭 Antigravity，整理前自动创建安全快照）
+   * @returns 各数据库的整理结果
+   */
+  static async vacuum(): Promise<VacuumResult[]> {
+    return invoke('vacuum_db');
+  }
+
+  /**
+   * 列出数据库中的全部字段及其大小与类型
+   * @param file 数据库文件，默认主库
+   * @returns 字段列表
+   */
+  static async listKeys(file?: DbFile): Promise<KeyEntry[]> {
+    return invoke('list_db_keys', { file });
+  }
+
+  /**
+   * 查看单个字段的脱敏值
+   * @param key 字段名
+   * @param file 数据库文件，默认主库
+   * @returns 脱敏后的值
+   */
+  static async getValue(key: string, file?: DbFile): Promise<string> {
+    return invoke('get_db_value', { key, file });
+  }
+
+  /**
+   * 比较主库与备份库中参与备份的字段与同步标记
+   * @returns 一致性检查结果
+   */
+  static async checkConsistency(): Promise<ConsistencyReport> {
+    return invoke('check_db_consistency');
+  }
+
+  /**
+   * 按指定方向同步主库与备份库（需先关闭 Antigravity，同步前自动创建安全快照）
+   * @param direction 同步方向
+   * @returns 同步结果
+   */
+  static async syncFiles(direction: SyncDirection): Promise<SyncResult> {
+    return invoke('sync_db_files', { direction });
+  }
+}
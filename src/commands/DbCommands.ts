@@ -11,7 +11,8 @@ import { invoke } from '@tauri-apps/api/core';
 export interface AuthStatusFromDb {
   name?: string;
   email?: string;
-  apiKey?: string;
+  /** 是否存在访问令牌（令牌本身不会返回给前端） */
+  hasApiKey?: boolean;
   userStatusProtoBinaryBase64?: string;
 }
 
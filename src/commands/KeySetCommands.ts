@@ -0,0 +1,41 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { KeySetFile, KeySetInfo } from './types/key-sets.types';
+
+/**
+ * 字段定义命令
+ * 备份、恢复与注销涉及的数据库字段由配置目录下的 key_sets.json 定义，不存在时使用内置定义
+ */
+export class KeySetCommands {
+  /**
+   * 获取当前生效的字段定义
+   * @returns 定义内容及来源
+   */
+  static async get(): Promise<KeySetInfo> {
+    return invoke('get_key_sets');
+  }
+
+  /**
+   * 获取内置默认字段定义
+   * @returns 内置定义
+   */
+  static async getBuiltin(): Promise<KeySetFile> {
+    return invoke('get_builtin_key_sets');
+  }
+
+  /**
+   * 保存字段定义（保存前会校验规则）
+   * @param definitions 定义内容，传 null 删除定义文件并恢复内置定义
+   * @returns 保存后生效的定义
+   */
+  static async save(definitions: KeySetFile | null): Promise<KeySetInfo> {
+    return invoke('save_key_sets', { definitions });
+  }
+
+  /**
+   * 重新加载定义文件
+   * @returns 重新加载后生效的定义
+   */
+  static async reload(): Promise<KeySetInfo> {
+    return invoke('reload_key_sets');
+  }
+}
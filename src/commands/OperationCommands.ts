@@ -0,0 +1,26 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { OperationInfo } from './types/operation.types';
+
+/**
+ * 操作队列命令
+ * 修改类操作按提交顺序串行执行，排队中的操作可以取消；
+ * 分步执行的操作（如切换账户）通过 operation-progress 事件报告每一步的进度
+ */
+export class OperationCommands {
+  /**
+   * 列出正在执行与排队中的操作（正在执行的在前）
+   * @returns 操作列表
+   */
+  static async list(): Promise<OperationInfo[]> {
+    return invoke('list_operations');
+  }
+
+  /**
+   * 取消操作：排队中的操作立即取消，分步执行的操作在当前步骤结束后取消并回滚
+   * @param operationId 操作 ID
+   * @returns 被取消的操作
+   */
+  static async cancel(operationId: string): Promise<OperationInfo> {
+    return invoke('cancel_operation', { operationId });
+  }
+}
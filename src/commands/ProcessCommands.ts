@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { ProcessInfo } from './types/process.types';
+import type { LaunchOutput, LaunchVerification, ProcessInfo } from './types/process.types';
 
 /**
  * 进程管理命令
@@ -30,7 +30,32 @@ export class ProcessCommands {
   }
 
   /**
-   * 列出所有 Antigravity 相关的进程（用于调试）
+   * 验证 Antigravity 已启动并保持运行，指定账户时同时确认登录的是该账户
+   * @param expectedEmail 期望登录的账户
+   * @returns 验证结果
+   */
+  static async verifyLaunch(expectedEmail?: string): Promise<LaunchVerification> {
+    return invoke('verify_antigravity_launch', { expectedEmail });
+  }
+
+  /**
+   * 获取最近一次启动验证的结果（切换账户后自动验证）
+   * @returns 验证结果，尚未验证时为 null
+   */
+  static async getLastLaunchVerification(): Promise<LaunchVerification | null> {
+    return invoke('get_last_launch_verification');
+  }
+
+  /**
+   * 获取最近一次启动 Antigravity 的输出与退出情况（用于诊断启动失败）
+   * @returns 启动输出
+   */
+  static async getLastLaunchOutput(): Promise<LaunchOutput> {
+    return invoke('get_last_launch_output');
+  }
+
+  /**
+   * 列出 Antigravity 相关进程（主进程、辅助进程、语言服务器）及其 CPU 与内存占用
    * @returns 进程信息列表
    */
   static async listProcesses(): Promise<ProcessInfo[]> {
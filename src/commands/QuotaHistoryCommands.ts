@@ -0,0 +1,32 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { QuotaSample, ResetPrediction } from './types/quota-history.types';
+
+/**
+ * 配额历史命令
+ * 配额样本在数据库监控发现认证信息变化或执行备份时自动记录
+ */
+export class QuotaHistoryCommands {
+  /**
+   * 查询账户的配额历史（按时间升序，用于图表）
+   * @param handle 账户句柄
+   * @param options.model 仅查询该模型
+   * @param options.since 起始时间（RFC3339）
+   * @param options.limit 最多返回的最新样本数
+   * @returns 配额样本列表
+   */
+  static async getHistory(
+    handle: string,
+    options: { model?: string; since?: string; limit?: number } = {}
+  ): Promise<QuotaSample[]> {
+    return invoke('get_quota_history', { handle, ...options });
+  }
+
+  /**
+   * 预测账户各模型的下次配额重置时间
+   * @param handle 账户句柄
+   * @returns 各模型的预测结果
+   */
+  static async getResetPredictions(handle: string): Promise<ResetPrediction[]> {
+    return invoke('get_quota_reset_predictions', { handle });
+  }
+}
@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { SecretRestorePolicy } from './types/account.types';
 import type { AppSettings } from './types/settings.types';
 
 /**
@@ -23,6 +24,15 @@ export class SettingsCommands {
     return invoke('save_silent_start_state', { enabled });
   }
 
+  /**
+   * 保存扩展密钥（secret://）恢复策略
+   * @param policy 恢复策略
+   * @returns 保存后的策略
+   */
+  static async saveSecretRestorePolicy(policy: SecretRestorePolicy): Promise<SecretRestorePolicy> {
+    return invoke('save_secret_restore_policy', { policy });
+  }
+
   /**
    * 获取所有应用设置
    * @returns 应用设置对象
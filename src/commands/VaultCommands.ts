@@ -0,0 +1,59 @@
+import { invoke } from '@tauri-apps/api/core';
+import type { VaultStatus } from './types/vault.types';
+
+/**
+ * 备份保险库命令
+ */
+export class VaultCommands {
+  /**
+   * 获取保险库状态
+   * @returns 加密模式与解锁状态
+   */
+  static async getStatus(): Promise<VaultStatus> {
+    return invoke('get_vault_status');
+  }
+
+  /**
+   * 使用主密码解锁保险库
+   * @param password 主密码
+   * @returns 解锁后的状态
+   */
+  static async unlock(password: string): Promise<VaultStatus> {
+    return invoke('unlock_vault', { password });
+  }
+
+  /**
+   * 立即锁定保险库
+   * @returns 锁定后的状态
+   */
+  static async lock(): Promise<VaultStatus> {
+    return invoke('lock_vault');
+  }
+
+  /**
+   * 启用主密码保险库，现有备份会被转换为主密码加密
+   * @param password 主密码（至少 12 位，包含数字、大小写字母和特殊字符）
+   * @returns 转换结果消息
+   */
+  static async enable(password: string): Promise<string> {
+    return invoke('enable_vault_mode', { password });
+  }
+
+  /**
+   * 关闭主密码保险库，备份会被转换回机器绑定加密
+   * @param password 主密码
+   * @returns 转换结果消息
+   */
+  static async disable(password: string): Promise<string> {
+    return invoke('disable_vault_mode', { password });
+  }
+
+  /**
+   * 设置空闲自动锁定时间
+   * @param seconds 秒数，0 表示不自动锁定
+   * @returns 更新后的状态
+   */
+  static async setIdleTimeout(seconds: number): Promise<VaultStatus> {
+    return invoke('set_vault_idle_timeout', { seconds });
+  }
+}
@@ -30,15 +30,12 @@ export interface AntigravityAccount {
   /** 邮箱地址 */
   email: string;
 
-  /** API密钥或访问令牌 */
-  api_key: string;
+  /** 不透明账户句柄，用于调用需要访问令牌的后端命令（令牌不会返回给前端） */
+  handle: string;
 
   /** Base64 编码的头像URL */
   profile_url: string;
 
-  /** 编码后的用户设置 */
-  user_settings: string;
-
   /** 账户创建时间 */
   created_at: string;
 
@@ -47,4 +44,216 @@ export interface AntigravityAccount {
 
   /** Base64 编码的用户状态 protobuf 数据（包含配额信息） */
   user_status_proto?: string;
+
+  /** 备份中访问令牌的过期时间（RFC3339，无法确定时为空） */
+  token_expires_at?: string | null;
+
+  /** 访问令牌状态 */
+  token_status: TokenStatus;
+
+  /** 备份中的扩展密钥（secret://）数量 */
+  secret_count: number;
+
+  /** 扩展密钥来自其他设备，恢复到本机后无法解密 */
+  secrets_at_risk: boolean;
+}
+
+/**
+ * 访问令牌状态
+ * - fresh: 有效
+ * - expiring_soon: 24 小时内过期
+ * - expired: 已过期
+ * - unknown: 无法确定过期时间
+ */
+export type TokenStatus = 'fresh' | 'expiring_soon' | 'expired' | 'unknown';
+
+/** 切换到令牌已过期账户时后端返回的错误前缀 */
+export const TOKEN_EXPIRED_ERROR_PREFIX = '令牌已过期';
+
+/**
+ * 实时会话与备份的关系
+ * - not_logged_in: Antigravity 未登录
+ * - unsaved: 已登录但该账户没有备份
+ * - in_sync: 实时令牌与备份一致
+ * - newer: 同一会话的令牌已刷新，比备份更新
+ * - older: 实时令牌比备份更旧
+ * - divergent: 令牌不同且无法判断先后（如重新登录）
+ */
+export type SessionState = 'not_logged_in' | 'unsaved' | 'in_sync' | 'newer' | 'older' | 'divergent';
+
+/**
+ * 活动账户检测结果（active-account-changed 事件载荷）
+ */
+export interface ActiveAccountStatus {
+  /** 活动账户的句柄（没有对应备份时为空） */
+  handle?: string | null;
+
+  /** 实时登录的邮箱 */
+  email?: string | null;
+
+  /** 实时会话与备份的关系 */
+  state: SessionState;
+
+  /** 实时令牌的过期时间（Unix 秒） */
+  live_token_expires_at?: number | null;
+
+  /** 备份令牌的过期时间（Unix 秒） */
+  saved_token_expires_at?: number | null;
+}
+
+/**
+ * 切换撤销栈条目
+ */
+export interface UndoInfo {
+  /** 条目 ID */
+  id: string;
+
+  /** 快照时间（RFC3339） */
+  created_at: string;
+
+  /** 切换前登录的账户（未登录时为空） */
+  from_email?: string | null;
+
+  /** 切换的目标账户 */
+  to_email: string;
+}
+
+/**
+ * 字段变化类型
+ */
+export type ChangeKind = 'added' | 'removed' | 'changed';
+
+/**
+ * 单个字段的变化（值已脱敏）
+ */
+export interface KeyChange {
+  /** 数据库字段名 */
+  key: string;
+
+  /** 变化类型 */
+  kind: ChangeKind;
+
+  /** 变化前的值 */
+  before?: string | null;
+
+  /** 变化后的值 */
+  after?: string | null;
+
+  /** 两侧均为 JSON 对象时，不同的顶层子字段 */
+  changed_fields: string[];
+}
+
+/**
+ * 切换预览结果
+ */
+export interface SwitchPreview {
+  /** 变化前登录的账户 */
+  from_account?: string | null;
+
+  /** 变化后登录的账户 */
+  to_account?: string | null;
+
+  /** 字段变化列表 */
+  changes: KeyChange[];
+
+  /** 摘要，如 "账户 a → b，用户设置有 4 项不同" */
+  summary: string;
+}
+
+/**
+ * 恢复字段分组
+ */
+export type KeyGroup =
+  | 'auth'
+  | 'settings'
+  | 'command_configs'
+  | 'agent_state'
+  | 'chat_sessions'
+  | 'notifications';
+
+/**
+ * 恢复字段分组说明
+ */
+export interface KeyGroupInfo {
+  /** 分组标识 */
+  group: KeyGroup;
+
+  /** 展示名称 */
+  label: string;
+
+  /** 分组包含的字段规则（通配规则带 glob:/regex: 前缀） */
+  keys: string[];
+}
+
+/**
+ * 注销深度
+ * - soft：只删除认证字段
+ * - standard：删除注销相关字段（保留设备指纹、聊天会话与 Agent 状态）
+ * - deep：删除全部参与备份的字段、账户文件与缓存
+ */
+export type LogoutProfile = 'soft' | 'standard' | 'deep';
+
+/**
+ * 注销删除的内容
+ */
+export interface LogoutItems {
+  /** state.vscdb 中的字段 */
+  main_keys: string[];
+
+  /** state.vscdb.backup 中的字段 */
+  backup_keys: string[];
+
+  /** 文件与目录（相对 Antigravity 应用数据目录） */
+  files: string[];
+}
+
+/**
+ * 注销预览
+ */
+export interface LogoutPlan {
+  profile: LogoutProfile;
+  items: LogoutItems;
+
+  /** 是否保留设备指纹 */
+  keeps_fingerprint: boolean;
+}
+
+/**
+ * 注销结果
+ */
+export interface LogoutReport {
+  profile: LogoutProfile;
+
+  /** 实际删除的内容 */
+  removed: LogoutItems;
+
+  /** 未能删除的内容及原因 */
+  failures: string[];
+  message: string;
+}
+
+/**
+ * 扩展密钥（secret://）恢复策略
+ * - auto：备份来自其他设备时跳过
+ * - include：始终恢复
+ * - exclude：始终跳过
+ */
+export type SecretRestorePolicy = 'auto' | 'include' | 'exclude';
+
+/**
+ * 备份的来源设备
+ */
+export type BackupOrigin = 'this_machine' | 'other_machine' | 'unknown';
+
+/**
+ * 备份中扩展密钥的可迁移情况
+ */
+export interface SecretReport {
+  origin: BackupOrigin;
+
+  /** 扩展密钥字段 */
+  secret_keys: string[];
+
+  /** 密钥在本机是否可能无法解密 */
+  at_risk: boolean;
 }
@@ -2,6 +2,8 @@
  * 备份相关类型定义
  */
 
+import type { BackupOrigin } from './account.types';
+
 /**
  * JSON 值类型（匹配 Rust 的 serde_json::Value）
  */
@@ -25,6 +27,9 @@ export interface BackupData {
 
   /** 备份时间戳（Unix 时间戳，秒） */
   timestamp: number;
+
+  /** 备份中的扩展密钥字段（导出到其他设备后无法解密） */
+  secretKeys?: string[];
 }
 
 /**
@@ -47,4 +52,66 @@ export interface RestoreResult {
 
   /** 失败的备份列表 */
   failed: FailedBackup[];
+
+  /** 含有无法在本机解密的扩展密钥的备份 */
+  secretWarnings: SecretWarning[];
+}
+
+/**
+ * 导入的备份中扩展密钥的提示
+ */
+export interface SecretWarning {
+  /** 文件名 */
+  filename: string;
+
+  /** 扩展密钥数量 */
+  secretCount: number;
+
+  /** 备份来源设备 */
+  origin: BackupOrigin;
+}
+
+/**
+ * 历史快照信息
+ */
+export interface SnapshotInfo {
+  /** 快照 ID（以时间戳开头） */
+  snapshot_id: string;
+
+  /** 创建时间（RFC3339） */
+  created_at: string;
+
+  /** 快照包含的键数量 */
+  key_count: number;
+}
+
+/**
+ * 快照存储垃圾回收结果
+ */
+export interface GcReport {
+  /** 删除的数据块数量 */
+  removed_blobs: number;
+
+  /** 释放的字节数 */
+  freed_bytes: number;
+
+  /** 仍被引用的数据块数量 */
+  remaining_blobs: number;
+
+  /** 无法读取而跳过的清单数量（其所属账户的数据块本次不回收） */
+  skipped_manifests: number;
+}
+
+/**
+ * 快照存储占用统计
+ */
+export interface StoreStats {
+  /** 数据块数量 */
+  blob_count: number;
+
+  /** 数据块总字节数 */
+  blob_bytes: number;
+
+  /** 历史快照数量 */
+  snapshot_count: number;
 }
@@ -0,0 +1,34 @@
+/**
+ * CloudCode 接口相关类型定义
+ */
+
+/**
+ * 单个模型的配额信息
+ */
+export interface ModelQuota {
+  /** 模型 ID */
+  model: string;
+
+  /** 模型显示名称 */
+  display_name?: string | null;
+
+  /** 剩余配额比例（0-1） */
+  remaining_fraction: number;
+
+  /** 配额重置时间 */
+  reset_time?: string | null;
+}
+
+/**
+ * CloudCode 接口配置
+ */
+export interface CloudCodeSettings {
+  /** 当前使用的接口地址 */
+  base_url: string;
+
+  /** 默认接口地址 */
+  default_base_url: string;
+
+  /** 请求使用的 User-Agent（与当前平台一致） */
+  user_agent: string;
+}
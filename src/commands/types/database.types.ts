@@ -0,0 +1,146 @@
+/**
+ * 数据库维护相关类型
+ */
+
+/**
+ * 创建数据库安全快照的原因
+ */
+export type DbSnapshotReason = 'restore' | 'cleanup' | 'rollback' | 'vacuum' | 'sync' | 'manual';
+
+/**
+ * 快照中的数据库文件
+ */
+export interface DbFileInfo {
+  /** 文件名（state.vscdb / state.vscdb.backup） */
+  name: string;
+
+  /** 大小（字节） */
+  size: number;
+}
+
+/**
+ * 数据库安全快照
+ */
+export interface DbSnapshotInfo {
+  /** 快照 ID */
+  id: string;
+
+  /** 创建时间（RFC3339） */
+  created_at: string;
+
+  /** 创建原因 */
+  reason: DbSnapshotReason;
+
+  /** 快照时登录的账户 */
+  email?: string | null;
+
+  /** 快照包含的数据库文件 */
+  databases: DbFileInfo[];
+}
+
+/**
+ * 数据库文件
+ */
+export type DbFile = 'main' | 'backup';
+
+/**
+ * 单个数据库的健康状态
+ */
+export interface DbHealth {
+  file: DbFile;
+  exists: boolean;
+
+  /** 文件大小（字节） */
+  size: number;
+
+  /** 日志模式（delete / wal 等） */
+  journal_mode?: string | null;
+  page_count?: number | null;
+
+  /** 空闲页数量，较多时可通过整理回收空间 */
+  freelist_count?: number | null;
+
+  /** integrity_check 是否返回 ok */
+  integrity_ok: boolean;
+
+  /** integrity_check 报告的问题 */
+  problems: string[];
+}
+
+/**
+ * 单个数据库的整理结果
+ */
+export interface VacuumResult {
+  file: DbFile;
+  size_before: number;
+  size_after: number;
+}
+
+/**
+ * 字段值的类型
+ */
+export type ValueKind = 'null' | 'json_object' | 'json_array' | 'json_scalar' | 'text' | 'blob';
+
+/**
+ * ItemTable 中的单个字段
+ */
+export interface KeyEntry {
+  key: string;
+
+  /** 值的大小（字节） */
+  size: number;
+  kind: ValueKind;
+}
+
+/**
+ * 主库与备份库的同步方向
+ */
+export type SyncDirection = 'main_to_backup' | 'backup_to_main';
+
+/**
+ * 字段差异类型
+ */
+export type DriftKind = 'missing_in_main' | 'missing_in_backup' | 'different';
+
+/**
+ * 单个字段的差异
+ */
+export interface KeyDrift {
+  key: string;
+  kind: DriftKind;
+
+  /** 主库中的值（已脱敏） */
+  main?: string | null;
+
+  /** 备份库中的值（已脱敏） */
+  backup?: string | null;
+
+  /** 两侧均为 JSON 对象时不同的子字段 */
+  changed_fields: string[];
+}
+
+/**
+ * 主库与备份库一致性检查结果
+ */
+export interface ConsistencyReport {
+  backup_exists: boolean;
+  consistent: boolean;
+
+  /** 比较的字段数量（含 Marker） */
+  checked_keys: number;
+  drifts: KeyDrift[];
+  summary: string;
+}
+
+/**
+ * 同步结果
+ */
+export interface SyncResult {
+  direction: SyncDirection;
+
+  /** 写入的字段数量 */
+  written: number;
+
+  /** 删除的字段数量 */
+  removed: number;
+}
@@ -0,0 +1,72 @@
+/**
+ * 字段定义相关类型
+ */
+
+import type { KeyGroup } from './account.types';
+
+/**
+ * 字段匹配规则（精确字段名、glob 通配符或正则表达式三选一）
+ */
+export type KeyPattern = { exact: string } | { glob: string } | { regex: string };
+
+/**
+ * 单条字段定义
+ */
+export interface KeyDefinition {
+  /** 匹配规则 */
+  pattern: KeyPattern;
+
+  /** 所属恢复分组（为空表示随任意分组一起恢复） */
+  group?: KeyGroup | null;
+
+  /** 恢复后是否在 Marker 中登记（默认 true） */
+  marker?: boolean;
+
+  /** 注销时是否删除 */
+  logout?: boolean;
+
+  /** 说明 */
+  description?: string | null;
+}
+
+/**
+ * 账户文件/目录定义（ItemTable 之外的账户状态）
+ */
+export interface FileDefinition {
+  /** 相对 Antigravity User 目录的路径，如 settings.json、globalStorage/<扩展 ID> */
+  path: string;
+
+  /** 所属恢复分组（为空表示随任意分组一起恢复） */
+  group?: KeyGroup | null;
+
+  /** 说明 */
+  description?: string | null;
+}
+
+/**
+ * 字段定义文件
+ */
+export interface KeySetFile {
+  /** 定义格式版本 */
+  version: number;
+
+  /** 字段定义（同一字段匹配多条时取第一条） */
+  keys: KeyDefinition[];
+
+  /** 随备份保存的账户文件/目录（恢复时整体替换） */
+  files?: FileDefinition[];
+}
+
+/**
+ * 当前生效的字段定义
+ */
+export interface KeySetInfo {
+  /** 定义来源 */
+  source: 'builtin' | 'file';
+
+  /** 定义文件路径 */
+  path?: string | null;
+
+  /** 定义内容 */
+  definitions: KeySetFile;
+}
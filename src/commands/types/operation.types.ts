@@ -0,0 +1,85 @@
+/**
+ * 操作队列相关类型定义
+ * 切换、恢复、注销、备份、导入与数据库维护都经后端操作队列串行执行
+ */
+
+/** 队列繁忙（同一操作已在排队或排队过多）时错误信息的前缀 */
+export const OPERATION_BUSY_ERROR_PREFIX = '操作繁忙';
+
+/** 操作被取消时错误信息的前缀 */
+export const OPERATION_CANCELLED_ERROR_PREFIX = '操作已取消';
+
+/** 队列变化事件名，负载为当前的 OperationInfo 列表 */
+export const OPERATIONS_CHANGED_EVENT = 'operations-changed';
+
+/** 步骤进度事件名，负载为 OperationProgress */
+export const OPERATION_PROGRESS_EVENT = 'operation-progress';
+
+/**
+ * 操作类型
+ */
+export type OperationKind = 'switch' | 'restore' | 'logout' | 'backup' | 'import' | 'delete' | 'restart' | 'maintenance';
+
+/**
+ * 操作状态
+ */
+export type OperationStatus = 'pending' | 'running';
+
+/**
+ * 操作信息
+ */
+export interface OperationInfo {
+  /** 操作 ID */
+  id: string;
+
+  kind: OperationKind;
+
+  /** 操作对象（如账户邮箱） */
+  target?: string | null;
+
+  status: OperationStatus;
+
+  /** 提交时间（RFC3339） */
+  queued_at: string;
+
+  /** 开始执行时间（RFC3339） */
+  started_at?: string | null;
+
+  /** 执行中的操作已请求取消（将在当前步骤结束后生效） */
+  cancel_requested: boolean;
+}
+
+/**
+ * 步骤状态
+ * - cancelled: 在此步骤之前响应了取消
+ * - rolled_back: 已回滚之前完成的步骤
+ */
+export type StepStatus = 'running' | 'succeeded' | 'failed' | 'cancelled' | 'rolled_back';
+
+/**
+ * 步骤进度
+ */
+export interface OperationProgress {
+  operation_id: string;
+
+  kind: OperationKind;
+
+  /** 步骤名称 */
+  step: string;
+
+  /** 步骤序号（从 1 开始） */
+  index: number;
+
+  total: number;
+
+  status: StepStatus;
+
+  /** 操作开始至今的耗时（毫秒） */
+  elapsed_ms: number;
+
+  /** 当前步骤的耗时（毫秒） */
+  step_elapsed_ms: number;
+
+  /** 步骤结果或错误信息 */
+  message?: string | null;
+}
@@ -2,12 +2,29 @@
  * 进程相关类型定义
  */
 
+/** Antigravity 启动事件名，负载为 ProcessStateEvent */
+export const ANTIGRAVITY_STARTED_EVENT = 'antigravity-started';
+
+/** Antigravity 退出事件名，负载为 ProcessStateEvent */
+export const ANTIGRAVITY_STOPPED_EVENT = 'antigravity-stopped';
+
+/**
+ * 进程角色
+ * - main: Antigravity 主进程
+ * - helper: Electron 辅助进程（渲染、GPU、扩展宿主等）
+ * - language_server: 语言服务器
+ */
+export type ProcessRole = 'main' | 'helper' | 'language_server';
+
 /**
  * 进程信息
  */
 export interface ProcessInfo {
   /** 进程 ID */
-  pid: string;
+  pid: number;
+
+  /** 父进程 ID */
+  parent_pid?: number | null;
 
   /** 进程名称 */
   name: string;
@@ -15,9 +32,89 @@ export interface ProcessInfo {
   /** 命令行参数 */
   command: string;
 
-  /** 匹配的模式索引 */
-  matched_pattern: number;
+  role: ProcessRole;
+
+  /** CPU 使用率（百分比，多核时可能超过 100） */
+  cpu_usage: number;
+
+  /** 内存占用（字节） */
+  memory_bytes: number;
+}
+
+/**
+ * 进程状态变化事件
+ */
+export interface ProcessStateEvent {
+  /** 当前的 Antigravity 进程（退出时为空） */
+  pids: number[];
+}
+
+/**
+ * 启动验证状态
+ * - verified: 进程保持运行，且登录的是目标账户
+ * - failed: 进程未出现、启动后退出，或登录的不是目标账户
+ * - rolled_back: 验证失败后已回滚到启动前的状态
+ */
+export type LaunchStatus = 'verified' | 'failed' | 'rolled_back';
+
+/**
+ * 启动验证结果
+ */
+export interface LaunchVerification {
+  status: LaunchStatus;
+
+  /** 期望登录的账户（为空时不检查账户） */
+  expected_email?: string | null;
+
+  /** 启动后数据库中登录的账户 */
+  actual_email?: string | null;
+
+  /** 进程是否在稳定期内保持运行 */
+  process_alive: boolean;
+
+  message: string;
+
+  /** 验证耗时（毫秒） */
+  duration_ms: number;
+
+  /** 验证完成时间（RFC3339） */
+  checked_at: string;
+}
+
+/**
+ * 启动记录
+ */
+export interface LaunchRecord {
+  /** 启动 ID（启动日志中的起止标记） */
+  id: string;
+
+  /** 启动的程序（已脱敏） */
+  program: string;
+
+  pid?: number | null;
+
+  /** 启动时间（RFC3339） */
+  started_at: string;
+
+  /** 退出码（被信号终止时为空） */
+  exit_code?: number | null;
+
+  /** 退出时间（RFC3339），仍在运行时为空 */
+  exited_at?: string | null;
+
+  /** 启动后短时间内以非零状态退出 */
+  crashed: boolean;
+}
+
+/**
+ * 最近一次启动的输出
+ */
+export interface LaunchOutput {
+  launch?: LaunchRecord | null;
+
+  /** 子进程的 stdout / stderr（已脱敏） */
+  output: string;
 
-  /** 模式描述 */
-  pattern_description: string;
+  /** 输出过长，只保留了末尾部分 */
+  truncated: boolean;
 }
@@ -0,0 +1,72 @@
+/**
+ * 配额历史相关类型定义
+ */
+
+/**
+ * 单条配额样本
+ */
+export interface QuotaSample {
+  /** 模型名称 */
+  model: string;
+
+  /** 记录时间（RFC3339） */
+  recorded_at: string;
+
+  /** 剩余配额比例（0-1） */
+  remaining_fraction: number;
+
+  /** 报告的配额重置时间（RFC3339） */
+  reset_time?: string | null;
+
+  /** 可用提示积分 */
+  prompt_credits?: number | null;
+
+  /** 可用流程积分 */
+  flow_credits?: number | null;
+
+  /** 样本来源：数据库监控或账户备份 */
+  source: 'monitor' | 'backup';
+}
+
+/**
+ * 预测依据
+ * - reported: 最新样本报告的重置时间
+ * - observed_cycle: 根据历史重置周期推算
+ * - full: 配额已满，没有待发生的重置
+ * - unknown: 数据不足
+ */
+export type PredictionBasis = 'reported' | 'observed_cycle' | 'full' | 'unknown';
+
+/**
+ * 单个模型的下次重置预测
+ */
+export interface ResetPrediction {
+  /** 模型名称 */
+  model: string;
+
+  /** 最新剩余配额比例 */
+  remaining_fraction: number;
+
+  /** 最新样本的记录时间 */
+  last_recorded_at: string;
+
+  /** 预测的下次重置时间（RFC3339） */
+  predicted_reset?: string | null;
+
+  /** 预测依据 */
+  basis: PredictionBasis;
+
+  /** 观察到的重置周期（秒） */
+  observed_cycle_secs?: number | null;
+}
+
+/**
+ * quota-history-updated 事件载荷
+ */
+export interface QuotaHistoryUpdatedEvent {
+  /** 账户句柄 */
+  handle: string;
+
+  /** 新写入的样本数 */
+  inserted: number;
+}
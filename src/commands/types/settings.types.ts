@@ -2,6 +2,8 @@
  * 设置相关类型定义
  */
 
+import type { KeyGroup, SecretRestorePolicy } from './account.types';
+
 /**
  * 应用设置
  */
@@ -11,4 +13,13 @@ export interface AppSettings {
 
   /** 静默启动是否启用 */
   silent_start_enabled: boolean;
+
+  /** 自定义 CloudCode 接口地址（为空时使用默认地址） */
+  cloudcode_base_url?: string | null;
+
+  /** 各账户（按账户 ID）默认恢复的字段分组 */
+  restore_groups?: Record<string, KeyGroup[]>;
+
+  /** 恢复备份时如何处理扩展密钥（secret://） */
+  secret_restore_policy?: SecretRestorePolicy;
 }
@@ -0,0 +1,27 @@
+/**
+ * 备份保险库相关类型定义
+ */
+
+/**
+ * 备份加密模式
+ * - machine: 绑定本机机器 ID 和用户名
+ * - password: 使用主密码派生的密钥
+ */
+export type VaultMode = 'machine' | 'password';
+
+/**
+ * 保险库状态
+ */
+export interface VaultStatus {
+  /** 当前加密模式 */
+  mode: VaultMode;
+
+  /** 是否已解锁（机器模式始终为 true） */
+  unlocked: boolean;
+
+  /** 空闲自动锁定时间（秒），0 表示不自动锁定 */
+  idle_timeout_secs: number;
+
+  /** 距离自动锁定的剩余秒数 */
+  seconds_until_lock?: number | null;
+}
@@ -5,9 +5,12 @@ import {useAntigravityAccount, useCurrentAntigravityAccount} from "@/modules/use
 import {useAvailableModels} from "@/modules/use-available-models.ts";
 import {useLanguageServerUserInfo} from "@/modules/use-language-server-user-info.ts";
 import {useTrayMenu} from "@/hooks/useTrayMenu.ts";
+import {useActiveAccount} from "@/modules/use-active-account.ts";
+import {AccountCommands} from "@/commands/AccountCommands.ts";
 
 import BusinessConfirmDialog from "@/components/business/ConfirmDialog.tsx";
 import toast from 'react-hot-toast';
+import {Button, Modal} from 'antd';
 import {maskEmail, maskName} from "@/utils/string-masking.ts";
 import {useAppGlobalLoader} from "@/modules/use-app-global-loader.ts";
 import {AccountSessionListCard} from "@/components/business/AccountSessionListCard.tsx";
@@ -20,6 +23,7 @@ export function AppContent() {
   const languageServerUserInfo = useLanguageServerUserInfo();
   const currentAntigravityAccount = useCurrentAntigravityAccount();
   const appGlobalLoader = useAppGlobalLoader();
+  const activeAccount = useActiveAccount();
 
   // 初始化托盘菜单更新
   useTrayMenu();
@@ -50,7 +54,37 @@ export function AppContent() {
     loadUsers();
   }, []);
 
+  // 监听活动账户变化；实时会话比备份更新或已分叉时提示更新备份
   useEffect(() => {
+    const unlisten = activeAccount.listen();
+    return () => {
+      unlisten.then(f => f());
+    };
+  }, []);
+
+  useEffect(() => {
+    const status = activeAccount.status;
+    if (!status?.handle || (status.state !== 'newer' && status.state !== 'divergent')) {
+      return;
+    }
+    toast((t) => (
+      <span className="flex items-center gap-2">
+        {status.state === 'newer' ? '当前登录状态比备份更新' : '当前登录状态与备份不一致'}
+        <Button size="small" type="primary" onClick={async () => {
+          toast.dismiss(t.id);
+          try {
+            await antigravityAccount.insertOrUpdateCurrentAccount();
+            toast.success('备份已更新');
+          } catch (error) {
+            toast.error(`更新备份失败: ${error}`);
+          }
+        }}>更新备份</Button>
+      </span>
+    ), {id: 'active-account-outdated', duration: 10000});
+  }, [activeAccount.status?.handle, activeAccount.status?.state]);
+
+  useEffect(() => {
+    activeAccount.refresh()
     antigravityAccount.updateCurrentAccount()
     antigravityAccount.accounts.forEach(user => {
       availableModels.fetchData(user)
@@ -70,21 +104,60 @@ export function AppContent() {
   const confirmDeleteAccount = async () => {
     if (!accountToDelete) return;
 
-    await antigravityAccount.delete(accountToDelete);
-    toast.success(`账户 "${accountToDelete}" 删除成功`);
+    const result = await antigravityAccount.delete(accountToDelete);
+    // 部分撤销快照或数据库安全快照无法确认是否含有该账户时，后端在结果中附带说明
+    if (result.includes('无法读取')) {
+      toast(result, {icon: '⚠️', duration: 8000});
+    } else {
+      toast.success(`账户 "${accountToDelete}" 删除成功`);
+    }
     setDeleteDialogOpen(false);
     setAccountToDelete(null);
   };
 
-  const handleSwitchAccount = async (user: AntigravityAccount) => {
+  const switchAccount = async (user: AntigravityAccount, allowExpired: boolean) => {
     try {
       appGlobalLoader.open({label: `正在切换到用户: ${maskEmail(user.email)}...`});
-      await antigravityAccount.switchToAccount(user.email);
+      await antigravityAccount.switchToAccount(user.email, allowExpired);
+      toast((t) => (
+        <span className="flex items-center gap-2">
+          已切换到 {maskEmail(user.email)}
+          <Button size="small" onClick={async () => {
+            toast.dismiss(t.id);
+            await undoLastSwitch();
+          }}>撤销</Button>
+        </span>
+      ), {duration: 8000});
+    } finally {
+      appGlobalLoader.close();
+    }
+  };
+
+  const undoLastSwitch = async () => {
+    try {
+      appGlobalLoader.open({label: '正在撤销切换...'});
+      await AccountCommands.undoLastSwitch();
+      toast.success('已恢复到切换前的账户');
+    } catch (error) {
+      toast.error(`撤销切换失败: ${error}`);
     } finally {
       appGlobalLoader.close();
     }
   };
 
+  const handleSwitchAccount = async (user: AntigravityAccount) => {
+    // 备份中的登录状态已过期时先提示用户确认
+    if (user.token_status === 'expired') {
+      Modal.confirm({
+        title: '登录状态已过期',
+        content: `账户 ${maskEmail(user.email)} 备份的登录状态已过期，切换后需要在 Antigravity 中重新登录。仍要切换吗？`,
+        onOk: () => switchAccount(user, true),
+      });
+      return;
+    }
+    await switchAccount(user, false);
+  };
+
   const handleClearAllBackups = () => {
     if (antigravityAccount.accounts.length === 0) {
       toast.error('当前没有用户备份可清空');
@@ -124,7 +197,7 @@ export function AppContent() {
           ) : (
             <div className="flex flex-row gap-2 p-2">
               {antigravityAccount.accounts.map((user) => {
-                const model = availableModels.data[user.api_key]
+                const model = availableModels.data[user.handle]
                 const offlineModel = languageServerUserInfo.users[user.id]
 
                 let geminiQuota = -1
@@ -168,6 +241,7 @@ export function AppContent() {
                   claudeResetTime={claudeResetTime}
                   key={user.id}
                   isCurrentUser={currentAntigravityAccount?.id === user.id}
+                  tokenStatus={user.token_status}
                   email={maskEmail(user.email)}
                   nickName={maskName(user.name)}
                   userAvatar={user.profile_url}
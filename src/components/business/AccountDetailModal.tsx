@@ -1,5 +1,5 @@
 import React, {useState} from 'react';
-import {Check, Clock, Copy, Key, User} from 'lucide-react';
+import {Check, Clock, Copy, User} from 'lucide-react';
 import type {AntigravityAccount} from '@/commands/types/account.types';
 import {BaseButton} from '@/components/base-ui/BaseButton';
 import {cn} from '@/utils/utils';
@@ -167,13 +167,6 @@ const BusinessUserDetail: React.FC<BusinessUserDetailProps> = ({
 
         <div className="h-px bg-gray-100 dark:bg-gray-800"/>
 
-        <InfoItem
-          icon={<Key className="h-4 w-4 text-orange-500"/>}
-          label="API 密钥"
-          value={"****"}
-          copyable
-          fieldName="api_key"
-        />
         <InfoItem
           icon={<Clock className="h-4 w-4 text-gray-500"/>}
           label="最近切换时间"
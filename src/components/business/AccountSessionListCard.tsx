@@ -3,6 +3,7 @@ import {motion} from 'framer-motion';
 import {cn} from "@/utils/utils.ts";
 import {Button, Space} from "antd";
 import {BaseTooltip} from "@/components/base-ui/BaseTooltip";
+import type {TokenStatus} from "@/commands/types/account.types.ts";
 
 interface UserSessionCardProps {
   nickName: string;
@@ -14,6 +15,8 @@ interface UserSessionCardProps {
   claudeQuota: number | -1;
   geminiResetTime?: string;
   claudeResetTime?: string;
+  // 备份中访问令牌的状态
+  tokenStatus?: TokenStatus;
   // current
   isCurrentUser: boolean;
   onSelect: () => void
@@ -54,6 +57,17 @@ export function AccountSessionListCard(props: UserSessionCardProps) {
             </div>
             : ""
         }
+        {
+          !props.isCurrentUser && (props.tokenStatus === 'expired' || props.tokenStatus === 'expiring_soon')
+            ? <div
+              className={cn(
+                "px-2.5 py-1 text-xs font-bold rounded-md flex items-center gap-1",
+                props.tokenStatus === 'expired' ? "bg-red-100/80 text-red-700" : "bg-amber-100/80 text-amber-700"
+              )}>
+              {props.tokenStatus === 'expired' ? '已过期' : '即将过期'}
+            </div>
+            : ""
+        }
       </header>
 
       {/* 进度条区域 */}
@@ -1,10 +1,16 @@
 /**
  * Antigravity 进程运行状态 Store
- * 全局单例，每 10 秒自动检测 Antigravity 是否在运行
+ * 全局单例，监听后端进程监视器的 antigravity-started / antigravity-stopped 事件，
+ * 并每 30 秒检测一次作为兜底
  */
 
 import { create } from 'zustand';
+import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { ProcessCommands } from '@/commands/ProcessCommands';
+import {
+  ANTIGRAVITY_STARTED_EVENT,
+  ANTIGRAVITY_STOPPED_EVENT,
+} from '@/commands/types/process.types';
 import { logger } from '../utils/logger';
 
 // 状态接口
@@ -30,8 +36,11 @@ interface AntigravityIsRunningActions {
 // 全局定时器 ID
 let checkIntervalId: NodeJS.Timeout | null = null;
 
-// 检查间隔（10 秒）
-const CHECK_INTERVAL = 10000;
+// 全局事件取消函数
+let unlistenFns: UnlistenFn[] = [];
+
+// 检查间隔（30 秒，状态变化由事件实时通知）
+const CHECK_INTERVAL = 30000;
 
 /**
  * Antigravity 运行状态 Store
@@ -82,6 +91,16 @@ export const useAntigravityIsRunning = create<
       clearInterval(checkIntervalId);
     }
 
+    // 监听进程状态事件
+    unlistenFns.forEach((unlisten) => unlisten());
+    unlistenFns = [];
+    Promise.all([
+      listen(ANTIGRAVITY_STARTED_EVENT, () => set({ isRunning: true, lastChecked: new Date() })),
+      listen(ANTIGRAVITY_STOPPED_EVENT, () => set({ isRunning: false, lastChecked: new Date() })),
+    ]).then((fns) => {
+      unlistenFns = fns;
+    });
+
     // 立即检查一次
     get().checkStatus();
 
@@ -99,6 +118,9 @@ export const useAntigravityIsRunning = create<
 
   // 停止自动检查
   stopAutoCheck: () => {
+    unlistenFns.forEach((unlisten) => unlisten());
+    unlistenFns = [];
+
     if (checkIntervalId !== null) {
       clearInterval(checkIntervalId);
       checkIntervalId = null;
@@ -3,7 +3,10 @@ import { listen } from "@tauri-apps/api/event";
 import { logger } from "../utils/logger";
 import { useAntigravityAccount } from "@/modules/use-antigravity-account.ts";
 import { TrayCommands } from "@/commands/TrayCommands.ts";
+import { TOKEN_EXPIRED_ERROR_PREFIX } from "@/commands/types/account.types.ts";
+import { maskEmail } from "@/utils/string-masking.ts";
 import toast from "react-hot-toast";
+import { Modal } from "antd";
 
 /**
  * 系统托盘菜单更新 Hook
@@ -32,13 +35,26 @@ export function useTrayMenu() {
       const email = event.payload as string;
       logger.info("收到托盘账户切换请求", { email });
 
-      try {
-        await switchToAccount(email);
-        toast.success(`已切换到账户: ${email}`);
-      } catch (error) {
-        logger.error("托盘账户切换失败", error);
-        toast.error(`切换账户失败: ${error}`);
-      }
+      const switchFromTray = async (allowExpired: boolean) => {
+        try {
+          await switchToAccount(email, allowExpired);
+          toast.success(`已切换到账户: ${email}`);
+        } catch (error) {
+          // 备份的登录状态已过期时与账户列表一样先提示用户确认
+          if (!allowExpired && String(error).startsWith(TOKEN_EXPIRED_ERROR_PREFIX)) {
+            Modal.confirm({
+              title: '登录状态已过期',
+              content: `账户 ${maskEmail(email)} 备份的登录状态已过期，切换后需要在 Antigravity 中重新登录。仍要切换吗？`,
+              onOk: () => switchFromTray(true),
+            });
+            return;
+          }
+          logger.error("托盘账户切换失败", error);
+          toast.error(`切换账户失败: ${error}`);
+        }
+      };
+
+      await switchFromTray(false);
     });
 
     return () => {
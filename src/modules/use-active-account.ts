@@ -0,0 +1,35 @@
+import {create} from "zustand";
+import {listen, type UnlistenFn} from "@tauri-apps/api/event";
+import {AccountCommands} from "@/commands/AccountCommands.ts";
+import type {ActiveAccountStatus} from "@/commands/types/account.types.ts";
+import {logger} from "@/utils/logger.ts";
+
+type State = {
+  status: ActiveAccountStatus | null
+}
+
+type Actions = {
+  // 主动检测一次（账户列表变化后调用）
+  refresh: () => Promise<void>
+  // 监听后端推送的 active-account-changed 事件
+  listen: () => Promise<UnlistenFn>
+}
+
+export const useActiveAccount = create<State & Actions>((setState) => ({
+  status: null,
+  refresh: async () => {
+    try {
+      const status = await AccountCommands.getActiveAccountStatus();
+      setState({status});
+    } catch (error) {
+      logger.warn('检测活动账户失败', {
+        module: 'ActiveAccount',
+        error: error instanceof Error ? error.message : String(error)
+      });
+    }
+  },
+  listen: () => listen<ActiveAccountStatus>('active-account-changed', (event) => {
+    logger.info('活动账户状态变化', {module: 'ActiveAccount', state: event.payload.state});
+    setState({status: event.payload});
+  }),
+}))
@@ -5,6 +5,7 @@ import type {AntigravityCurrentUserInfo, BackupCurrentAccountResult} from '../ty
 import {AccountCommands} from '@/commands/AccountCommands.ts';
 import type {AntigravityAccount, AntigravityAuthInfo} from '@/commands/types/account.types.ts';
 import {BackupCommands} from "@/commands/BackupCommands.ts";
+import {useActiveAccount} from "@/modules/use-active-account.ts";
 
 // 常量定义
 const FILE_WRITE_DELAY_MS = 500; // 等待文件写入完成的延迟时间
@@ -18,9 +19,9 @@ export interface AntigravityAccountState {
 // Store Actions
 export interface AntigravityAccountActions {
   // 基础操作
-  delete: (email: string) => Promise<void>;
+  delete: (email: string) => Promise<string>;
   insertOrUpdateCurrentAccount: () => Promise<void>;
-  switchToAccount: (email: string) => Promise<void>;
+  switchToAccount: (email: string, allowExpired?: boolean) => Promise<void>;
   updateCurrentAccount: () => Promise<AntigravityAuthInfo | null>;
 
   // 批量操作
@@ -37,18 +38,19 @@ export const useAntigravityAccount = create<AntigravityAccountState & Antigravit
   currentAuthInfo: null,
 
   // ============ 基础操作 ============
-  delete: async (email: string): Promise<void> => {
+  delete: async (email: string): Promise<string> => {
     logger.info('开始删除用户', { module: 'UserManagement', email });
 
     try {
       // 调用 Tauri 删除命令，与 ManageSection 保持一致
-      await invoke('delete_backup', { name: email });
+      const result = await invoke<string>('delete_backup', { name: email });
 
       // 删除成功后重新获取数据
       const accounts = await AccountCommands.getAccounts();
       set({ accounts: accounts });
 
       logger.info('用户删除成功', { module: 'UserManagement', email, remainingUsers: accounts.length });
+      return result;
     } catch (error) {
       logger.error('用户删除失败', {
         module: 'UserManagement',
@@ -67,14 +69,14 @@ export const useAntigravityAccount = create<AntigravityAccountState & Antigravit
       const currentInfo = await invoke<AntigravityCurrentUserInfo>('get_current_antigravity_info');
 
       // 2. 检查是否有有效的用户信息（通过API Key或用户状态判断）
-      if (currentInfo && (currentInfo.apiKey || currentInfo.userStatusProtoBinaryBase64)) {
+      if (currentInfo && (currentInfo.hasApiKey || currentInfo.userStatusProtoBinaryBase64)) {
         // 3. 从认证信息中提取邮箱
         const userEmail = currentInfo.email;
 
         logger.info('检测到已登录用户，开始备份', {
           module: 'UserManagement',
           email: userEmail,
-          hasApiKey: !!currentInfo.apiKey,
+          hasApiKey: !!currentInfo.hasApiKey,
           hasUserStatus: !!currentInfo.userStatusProtoBinaryBase64
         });
 
@@ -99,7 +101,7 @@ export const useAntigravityAccount = create<AntigravityAccountState & Antigravit
       } else {
         logger.warn('未检测到已登录的用户', {
           module: 'UserManagement',
-          hasApiKey: !!currentInfo.apiKey,
+          hasApiKey: !!currentInfo.hasApiKey,
           hasUserStatus: !!currentInfo.userStatusProtoBinaryBase64
         });
         throw new Error('未检测到已登录的用户');
@@ -113,13 +115,14 @@ export const useAntigravityAccount = create<AntigravityAccountState & Antigravit
     }
   },
 
-  switchToAccount: async (email: string): Promise<void> => {
+  switchToAccount: async (email: string, allowExpired = false): Promise<void> => {
     logger.info('开始切换用户', { module: 'UserManagement', email });
 
     try {
-      // 调用后端切换用户命令
+      // 调用后端切换用户命令（令牌已过期时后端会拒绝，除非 allowExpired）
       const result = await invoke<string>('switch_to_antigravity_account', {
-        accountName: email
+        accountName: email,
+        allowExpired
       });
 
       logger.info('切换用户成功', { module: 'UserManagement', email, result });
@@ -175,4 +178,10 @@ export const useAntigravityAccount = create<AntigravityAccountState & Antigravit
   },
 }));
 
-export const useCurrentAntigravityAccount: () => AntigravityAccount | undefined = () => useAntigravityAccount(state => state.accounts.find(user => user.email === state.currentAuthInfo?.email));
+// 优先使用后端检测到的活动账户句柄，检测结果尚未就绪时按邮箱匹配
+export const useCurrentAntigravityAccount: () => AntigravityAccount | undefined = () => {
+  const activeHandle = useActiveAccount(state => state.status?.handle);
+  return useAntigravityAccount(state => activeHandle
+    ? state.accounts.find(user => user.handle === activeHandle)
+    : state.accounts.find(user => user.email === state.currentAuthInfo?.email));
+};
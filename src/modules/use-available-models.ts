@@ -1,9 +1,10 @@
-﻿import {create} from "zustand";
+import {create} from "zustand";
 import type {AntigravityAccount} from "@/commands/types/account.types.ts";
-import {CloudCodeAPI} from "@/services/cloudcode-api.ts";
+import {CloudCodeCommands} from "@/commands/CloudCodeCommands.ts";
 import {CloudCodeAPITypes} from "@/services/cloudcode-api.types.ts";
 
 type State = {
+  // 账户句柄 -> 可用模型
   data: Record<string, CloudCodeAPITypes.FetchAvailableModelsResponse>
 }
 
@@ -14,14 +15,13 @@ type Actions = {
 export const useAvailableModels = create<State & Actions>((setState, getState) => ({
   data: {},
   fetchData: async (antigravityAccount: AntigravityAccount) => {
-    const codeAssistResponse = await CloudCodeAPI.loadCodeAssist(antigravityAccount.api_key);
-
-    const modelsResponse = await CloudCodeAPI.fetchAvailableModels(antigravityAccount.api_key, codeAssistResponse.cloudaicompanionProject);
+    // 令牌只在后端使用，这里仅传递账户句柄
+    const modelsResponse = await CloudCodeCommands.fetchAccountModels(antigravityAccount.handle);
 
     setState({
       data: {
         ...getState().data,
-        [antigravityAccount.api_key]: modelsResponse
+        [antigravityAccount.handle]: modelsResponse
       }
     })
   }
@@ -142,6 +142,15 @@ export const useImportExportAccount = create<ConfigState & ConfigActions>()(
             });
             toast.success(`配置文件导入成功，已恢复 ${result.restoredCount} 个账户`);
           }
+
+          // 扩展密钥由来源设备的系统钥匙串加密，在本机无法解密
+          if (result.secretWarnings.length > 0) {
+            logger.warn('导入的备份包含来自其他设备的扩展密钥', {
+              module: 'useImportExportAccount',
+              accounts: result.secretWarnings.map(w => w.filename)
+            });
+            toast(`${result.secretWarnings.length} 个账户包含来自其他设备的扩展密钥，恢复后相关扩展需重新登录`, {icon: '⚠️'});
+          }
         } catch (error) {
           logger.error('导入失败', {
             module: 'useImportExportAccount',
@@ -216,6 +225,12 @@ export const useImportExportAccount = create<ConfigState & ConfigActions>()(
           await LoggingCommands.writeTextFile(savePath, encryptedData);
 
           toast.success(`配置文件已保存: ${savePath}`);
+
+          // 扩展密钥依赖本机系统钥匙串，在其他设备上无法解密
+          const withSecrets = pendingExportData.filter(backup => (backup.secretKeys?.length ?? 0) > 0);
+          if (withSecrets.length > 0) {
+            toast(`${withSecrets.length} 个账户包含扩展密钥，在其他设备上无法解密，相关扩展需重新登录`, {icon: '⚠️'});
+          }
           logger.info('导出配置成功', {
             module: 'useImportExportAccount',
             savePath,
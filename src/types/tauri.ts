@@ -2,7 +2,8 @@
 // Antigravity 当前用户信息类型
 export interface AntigravityCurrentUserInfo {
   email: string;
-  apiKey?: string;
+  /** 是否存在访问令牌（令牌本身不会返回给前端） */
+  hasApiKey?: boolean;
   userStatusProtoBinaryBase64?: string;
   [key: string]: any;
 }